@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Keeps track of when the filesystem was last used
+// This is shared between the filesystem and the thread that mounted it, so that the
+// mount can be stopped after it was not used for a while
+#[derive(Clone)]
+pub struct Activity {
+    last_activity: Arc<Mutex<Instant>>,
+    // Set when the filesystem was unmounted by somebody else (e.g. umount)
+    destroyed: Arc<AtomicBool>,
+}
+
+impl Activity {
+    pub fn new() -> Self {
+        Activity {
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            destroyed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Record that the filesystem is being used right now
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    // Return how long the filesystem has not been used
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+
+    pub fn mark_destroyed(&self) {
+        self.destroyed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.destroyed.load(Ordering::SeqCst)
+    }
+}
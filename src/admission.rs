@@ -0,0 +1,137 @@
+// Inventory of the extensions of the API server that every request can pass through
+// admission/ in the context directory lists the mutating and validating webhooks and the
+// aggregated APIServices with the services they call, since a webhook or APIService whose
+// service is down can break requests across the whole cluster.
+
+use crate::kubectl::{self, K8sError};
+use crate::reports::{array, field};
+use serde_json::Value;
+
+// Name of the directory in the context directory that contains the inventory
+pub const ADMISSION_DIRECTORY_NAME: &str = "admission";
+
+// Files inside the admission directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Inventory {
+    MutatingWebhooks,
+    ValidatingWebhooks,
+    ApiServices,
+}
+
+impl Inventory {
+    pub fn all() -> [Inventory; 3] {
+        [
+            Inventory::MutatingWebhooks,
+            Inventory::ValidatingWebhooks,
+            Inventory::ApiServices,
+        ]
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Inventory::MutatingWebhooks => "mutating-webhooks",
+            Inventory::ValidatingWebhooks => "validating-webhooks",
+            Inventory::ApiServices => "apiservices",
+        }
+    }
+
+    pub fn report(&self, context: &str) -> Result<String, K8sError> {
+        match self {
+            Inventory::MutatingWebhooks => webhooks(
+                context,
+                "mutatingwebhookconfigurations.admissionregistration.k8s.io",
+            ),
+            Inventory::ValidatingWebhooks => webhooks(
+                context,
+                "validatingwebhookconfigurations.admissionregistration.k8s.io",
+            ),
+            Inventory::ApiServices => api_services(context),
+        }
+    }
+}
+
+// List the webhooks of all webhook configurations of a kind, one line per webhook
+fn webhooks(context: &str, resource: &str) -> Result<String, K8sError> {
+    let configurations = kubectl::get_items(context, None, resource, &[])?;
+    let mut report = format!(
+        "{:<40}{:<50}{:<10}{:<8}{}\n",
+        "CONFIGURATION", "WEBHOOK", "FAILURE", "TIMEOUT", "TARGET"
+    );
+    for configuration in &configurations {
+        for webhook in array(configuration, "/webhooks") {
+            // The API server defaults the policy to Fail and the timeout to 10s
+            let failure_policy = match field(webhook, "/failurePolicy") {
+                "" => "Fail",
+                policy => policy,
+            };
+            let timeout = webhook
+                .pointer("/timeoutSeconds")
+                .and_then(Value::as_u64)
+                .unwrap_or(10);
+            report.push_str(&format!(
+                "{:<40}{:<50}{:<10}{:<8}{}\n",
+                field(configuration, "/metadata/name"),
+                field(webhook, "/name"),
+                failure_policy,
+                format!("{}s", timeout),
+                webhook_target(webhook)
+            ));
+        }
+    }
+    Ok(report)
+}
+
+// Describe where the API server sends the requests of a webhook to
+//   service <namespace>/<name>:<port><path> or the URL of webhooks outside the cluster
+fn webhook_target(webhook: &Value) -> String {
+    match webhook.pointer("/clientConfig/service") {
+        Some(service) => format!(
+            "service {}/{}:{}{}",
+            field(service, "/namespace"),
+            field(service, "/name"),
+            service
+                .pointer("/port")
+                .and_then(Value::as_u64)
+                .unwrap_or(443),
+            field(service, "/path")
+        ),
+        None => field(webhook, "/clientConfig/url").to_string(),
+    }
+}
+
+// List the APIServices that are served by an extension API server (instead of the API server
+// itself) with the service they use and whether they are available
+fn api_services(context: &str) -> Result<String, K8sError> {
+    let api_services = kubectl::get_items(context, None, "apiservices", &[])?;
+    let mut report = format!(
+        "{:<50}{:<50}{:<10}{}\n",
+        "NAME", "SERVICE", "AVAILABLE", "MESSAGE"
+    );
+    for api_service in &api_services {
+        let Some(service) = api_service
+            .pointer("/spec/service")
+            .filter(|service| !service.is_null())
+        else {
+            continue;
+        };
+        let available = array(api_service, "/status/conditions")
+            .iter()
+            .find(|condition| field(condition, "/type") == "Available");
+        report.push_str(&format!(
+            "{:<50}{:<50}{:<10}{}\n",
+            field(api_service, "/metadata/name"),
+            format!(
+                "{}/{}:{}",
+                field(service, "/namespace"),
+                field(service, "/name"),
+                service
+                    .pointer("/port")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(443)
+            ),
+            available.map_or("Unknown", |condition| field(condition, "/status")),
+            available.map_or("", |condition| field(condition, "/message"))
+        ));
+    }
+    Ok(report)
+}
@@ -0,0 +1,316 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Default amount of memory that cached file content is allowed to use
+pub const DEFAULT_CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+// How long cached content is considered up to date
+// This mainly prevents that every getattr / read pair results in multiple kubectl calls
+pub const CACHE_TTL: Duration = Duration::from_secs(5);
+
+// Identifies what content is about rather than which file shows it (e.g. the command that
+// describes a resource), so that mounts of the same context can share one cache (see the daemon)
+pub type CacheKey = String;
+
+// Content cache that is shared by the mounts of a context
+pub type SharedCache = Arc<Mutex<ContentCache>>;
+
+struct CacheEntry {
+    content: Vec<u8>,
+    fetched_at: Instant,
+}
+
+// Size bounded LRU cache for the content of files (e.g. descriptions of kubernetes resources)
+// Entries are evicted when they are older than the TTL or when the cache uses more
+// memory than allowed, starting with the least recently used one. The content of a file is
+// also dropped when the last handle of the file is released.
+pub struct ContentCache {
+    max_bytes: usize,
+    ttl: Duration,
+    entries: HashMap<CacheKey, CacheEntry>,
+    // Keys ordered from least recently used to most recently used
+    lru: VecDeque<CacheKey>,
+    used_bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+    // Number of open handles per file
+    open_handles: HashMap<CacheKey, usize>,
+    releases: u64,
+    // Number of removed entries and how long they were cached in total
+    removed: u64,
+    total_residency: Duration,
+}
+
+impl ContentCache {
+    pub fn new(max_bytes: usize, ttl: Duration) -> Self {
+        ContentCache {
+            max_bytes,
+            ttl,
+            entries: HashMap::new(),
+            lru: VecDeque::new(),
+            used_bytes: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            expirations: 0,
+            open_handles: HashMap::new(),
+            releases: 0,
+            removed: 0,
+            total_residency: Duration::ZERO,
+        }
+    }
+
+    // Count a handle of a file that was opened
+    pub fn opened(&mut self, key: &str) {
+        *self.open_handles.entry(key.to_string()).or_default() += 1;
+    }
+
+    // Count a handle of a file that was released and drop its content with the last one
+    pub fn released(&mut self, key: &str) {
+        let Some(handles) = self.open_handles.get_mut(key) else {
+            return;
+        };
+        *handles -= 1;
+        if *handles > 0 {
+            return;
+        }
+        self.open_handles.remove(key);
+        if self.entries.contains_key(key) {
+            log::debug!("Dropping content of released file {}", key);
+            self.releases += 1;
+            self.remove(key);
+        }
+    }
+
+    // Return the cached content of a file if it is still up to date
+    pub fn get(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.lookup(key).map(<[u8]>::to_vec)
+    }
+
+    // Return the part of the cached content of a file that a read at offset returns
+    // Only the requested part is copied, so reading a large file in chunks stays cheap.
+    pub fn get_range(&mut self, key: &str, offset: u64, size: u32) -> Option<Vec<u8>> {
+        self.lookup(key)
+            .map(|content| content_range(content, offset, size).to_vec())
+    }
+
+    // Return the size of the cached content of a file
+    pub fn get_size(&mut self, key: &str) -> Option<usize> {
+        self.lookup(key).map(<[u8]>::len)
+    }
+
+    fn lookup(&mut self, key: &str) -> Option<&[u8]> {
+        let expired = match self.entries.get(key) {
+            Some(entry) => entry.fetched_at.elapsed() > self.ttl,
+            None => {
+                self.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            self.expirations += 1;
+            self.misses += 1;
+            self.remove(key);
+            return None;
+        }
+
+        self.hits += 1;
+        self.mark_used(key);
+        self.entries.get(key).map(|entry| entry.content.as_slice())
+    }
+
+    // Return true if the cache contains up to date content of a file
+    // Unlike get(), this does not count as a hit or a miss.
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries
+            .get(key)
+            .is_some_and(|entry| entry.fetched_at.elapsed() <= self.ttl)
+    }
+
+    // Store the content of a file
+    // Content that is larger than the whole cache is not stored at all
+    pub fn insert(&mut self, key: &str, content: Vec<u8>) {
+        self.insert_fetched_at(key, content, Instant::now());
+    }
+
+    // Store the content of a file that was fetched earlier (e.g. in the background)
+    // Content that is already older than the TTL is not stored.
+    pub fn insert_fetched_at(&mut self, key: &str, content: Vec<u8>, fetched_at: Instant) {
+        if fetched_at.elapsed() > self.ttl {
+            return;
+        }
+        self.remove(key);
+        self.collect_garbage();
+        if content.len() > self.max_bytes {
+            log::debug!("Content of {} is too large to be cached", key);
+            return;
+        }
+
+        while self.used_bytes + content.len() > self.max_bytes {
+            if let Some(oldest) = self.lru.pop_front() {
+                log::debug!("Evicting content of {} from the cache", oldest);
+                if let Some(entry) = self.entries.remove(&oldest) {
+                    self.used_bytes -= entry.content.len();
+                    self.record_residency(&entry);
+                }
+                self.evictions += 1;
+            } else {
+                break;
+            }
+        }
+
+        self.used_bytes += content.len();
+        self.lru.push_back(key.to_string());
+        self.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                content,
+                fetched_at,
+            },
+        );
+    }
+
+    // Remove the content of a file from the cache (e.g. because the file was deleted)
+    pub fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.used_bytes -= entry.content.len();
+            self.lru.retain(|cached| cached != key);
+            self.record_residency(&entry);
+        }
+    }
+
+    fn record_residency(&mut self, entry: &CacheEntry) {
+        self.removed += 1;
+        self.total_residency += entry.fetched_at.elapsed();
+    }
+
+    // Remove all entries that are not up to date anymore
+    fn collect_garbage(&mut self) {
+        let ttl = self.ttl;
+        let expired: Vec<CacheKey> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.fetched_at.elapsed() > ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            self.expirations += 1;
+            self.remove(&key);
+        }
+    }
+
+    // Move a key to the end of the LRU queue
+    fn mark_used(&mut self, key: &str) {
+        self.lru.retain(|cached| cached != key);
+        self.lru.push_back(key.to_string());
+    }
+
+    // Reset the counters, the cached content is kept
+    pub fn reset_stats(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+        self.evictions = 0;
+        self.expirations = 0;
+        self.releases = 0;
+        self.removed = 0;
+        self.total_residency = Duration::ZERO;
+    }
+
+    // Human readable statistics about the cache
+    // Residency is how long content stays in the cache: on average until it is removed, and
+    // for the oldest entry that is still cached.
+    pub fn stats(&self) -> String {
+        let open_entries = self
+            .entries
+            .keys()
+            .filter(|key| self.open_handles.contains_key(*key))
+            .count();
+        let mean_residency = match self.removed {
+            0 => Duration::ZERO,
+            removed => self.total_residency / removed as u32,
+        };
+        let oldest_residency = self
+            .entries
+            .values()
+            .map(|entry| entry.fetched_at.elapsed())
+            .max()
+            .unwrap_or_default();
+        format!(
+            "entries {}\nopen_entries {}\nused_bytes {}\nmax_bytes {}\nhits {}\nmisses {}\nevictions {}\nexpirations {}\nreleases {}\nmean_residency_ms {}\noldest_residency_ms {}\n",
+            self.entries.len(),
+            open_entries,
+            self.used_bytes,
+            self.max_bytes,
+            self.hits,
+            self.misses,
+            self.evictions,
+            self.expirations,
+            self.releases,
+            mean_residency.as_millis(),
+            oldest_residency.as_millis()
+        )
+    }
+}
+
+// Return the part of the content that a read of size bytes at offset returns
+// Reads at or beyond the end of the content return no data.
+pub fn content_range(content: &[u8], offset: u64, size: u32) -> &[u8] {
+    let start = usize::try_from(offset).map_or(content.len(), |offset| offset.min(content.len()));
+    let end = start.saturating_add(size as usize).min(content.len());
+    &content[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &[u8] = b"apiVersion: v1\n";
+
+    #[test]
+    fn content_range_within_content() {
+        assert_eq!(content_range(CONTENT, 0, 3), b"api");
+        assert_eq!(content_range(CONTENT, 12, 100), b"v1\n");
+    }
+
+    #[test]
+    fn content_range_at_end_is_empty() {
+        assert!(content_range(CONTENT, CONTENT.len() as u64, 10).is_empty());
+    }
+
+    #[test]
+    fn content_range_beyond_end_is_empty() {
+        assert!(content_range(CONTENT, CONTENT.len() as u64 + 1, 10).is_empty());
+        assert!(content_range(CONTENT, u64::MAX, 10).is_empty());
+    }
+
+    #[test]
+    fn content_range_does_not_overflow() {
+        assert!(content_range(CONTENT, u64::MAX, u32::MAX).is_empty());
+        let last = CONTENT.len() as u64 - 1;
+        assert_eq!(content_range(CONTENT, last, u32::MAX), b"\n");
+    }
+
+    #[test]
+    fn range_and_size_are_taken_from_cached_content() {
+        let mut cache = ContentCache::new(DEFAULT_CACHE_MAX_BYTES, CACHE_TTL);
+        cache.insert("a", CONTENT.to_vec());
+        assert_eq!(cache.get_size("a"), Some(CONTENT.len()));
+        assert_eq!(cache.get_range("a", 3, 7), Some(b"Version".to_vec()));
+        assert_eq!(
+            cache.get_range("a", CONTENT.len() as u64, 10),
+            Some(Vec::new())
+        );
+        assert_eq!(cache.get_range("b", 0, 10), None);
+        assert_eq!((cache.hits, cache.misses), (3, 1));
+    }
+
+    #[test]
+    fn content_larger_than_the_cache_is_not_cached() {
+        let mut cache = ContentCache::new(4, CACHE_TTL);
+        cache.insert("a", CONTENT.to_vec());
+        assert!(!cache.contains("a"));
+        assert_eq!(cache.get_size("a"), None);
+    }
+}
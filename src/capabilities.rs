@@ -0,0 +1,61 @@
+// Verbs of a kind and whether they can be used through the mount
+// Every resource directory has a capabilities file with a line per verb that the API server
+// supports for the kind (from discovery), whether it is allowed (RBAC and --allow-write) and
+// the file operations that use it, so that scripts know ahead of time if rmdir will work.
+//   VERB              ALLOWED    FILE OPERATIONS
+//   get               yes
+//   delete            no         rmdir
+//   create            read-only  mkdir
+
+use crate::k8s_resource::ResourceType;
+use crate::kubectl::{self, K8sError};
+
+pub const CAPABILITIES_FILE_NAME: &str = "capabilities";
+
+// Verbs that change resources, which need a writable mount
+const WRITE_VERBS: [&str; 5] = ["create", "update", "patch", "delete", "deletecollection"];
+
+pub fn report(
+    context: &str,
+    namespace: Option<&str>,
+    resource_type: ResourceType,
+    allow_write: bool,
+) -> Result<String, K8sError> {
+    let resource = resource_type.kubectl_name();
+    let verbs = kubectl::verbs(context, resource)?;
+    let mut report = format!("{:<18}{:<11}{}\n", "VERB", "ALLOWED", "FILE OPERATIONS");
+    for verb in &verbs {
+        let allowed = if !allow_write && WRITE_VERBS.contains(&verb.as_str()) {
+            "read-only"
+        } else {
+            match kubectl::can_i(context, verb, resource, namespace) {
+                Ok(true) => "yes",
+                Ok(false) => "no",
+                Err(error) => {
+                    log::debug!(
+                        "Could not check if allowed to {} {}: {}",
+                        verb,
+                        resource,
+                        error
+                    );
+                    "unknown"
+                }
+            }
+        };
+        // Write operations are described as "<operation> (<verb>)"
+        let suffix = format!("({})", verb);
+        let operations: Vec<&str> = resource_type
+            .write_operations()
+            .iter()
+            .filter_map(|operation| operation.strip_suffix(&suffix))
+            .map(str::trim_end)
+            .collect();
+        report.push_str(&format!(
+            "{:<18}{:<11}{}\n",
+            verb,
+            allowed,
+            operations.join(", ")
+        ));
+    }
+    Ok(report)
+}
@@ -0,0 +1,522 @@
+// Certificates of TLS secrets (type kubernetes.io/tls)
+// Every namespace has a tls/ directory with a directory per TLS secret that contains a cert-info
+// file, and the context directory has an expiring-certs report. Only the fields that are shown
+// are read from the certificates, their signatures are not verified.
+
+use crate::k8s_resource::{format_timestamp, parse_timestamp};
+use crate::kubectl::{self, K8sError};
+use crate::reports::{self, field};
+use serde_json::Value;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
+
+// Name of the directory inside namespace directories that contains the TLS secrets
+pub const TLS_DIRECTORY_NAME: &str = "tls";
+// Name of the file inside the directory of a TLS secret that describes its certificates
+pub const CERT_INFO_FILE_NAME: &str = "cert-info";
+// Name of the report in the context directory that lists the certificates that expire soon
+pub const EXPIRING_CERTS_FILE_NAME: &str = "expiring-certs";
+// Certificates that expire within this many days are listed in expiring-certs by default
+pub const DEFAULT_EXPIRY_WINDOW_DAYS: u64 = 30;
+
+// Field selector that only lists TLS secrets
+pub const TLS_SECRET_SELECTOR: &str = "type=kubernetes.io/tls";
+
+// DER tags
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const UTC_TIME: u8 = 0x17;
+const VERSION: u8 = 0xa0;
+const EXTENSIONS: u8 = 0xa3;
+// Context specific tags of the GeneralNames of the subjectAltName extension
+const SAN_EMAIL: u8 = 0x81;
+const SAN_DNS: u8 = 0x82;
+const SAN_URI: u8 = 0x86;
+const SAN_IP: u8 = 0x87;
+
+// Object identifiers (DER encoded) of the parts of names that are shown
+const NAME_ATTRIBUTES: [(&[u8], &str); 6] = [
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x0a], "O"),
+    (&[0x55, 0x04, 0x0b], "OU"),
+    (&[0x55, 0x04, 0x07], "L"),
+    (&[0x55, 0x04, 0x08], "ST"),
+    (&[0x55, 0x04, 0x06], "C"),
+];
+// Object identifier of the subjectAltName extension
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+// Fields of a certificate that are shown
+struct Certificate {
+    subject: String,
+    issuer: String,
+    serial: String,
+    not_before: Option<SystemTime>,
+    not_after: Option<SystemTime>,
+    sans: Vec<String>,
+}
+
+impl Certificate {
+    // Parse a DER encoded X.509 certificate
+    fn from_der(der: &[u8]) -> Option<Certificate> {
+        let (_, certificate, _) = read(der, SEQUENCE)?;
+        let (_, tbs, _) = read(certificate, SEQUENCE)?;
+        // The version is optional (v1 certificates do not have it)
+        let tbs = match read_any(tbs)? {
+            (VERSION, _, rest) => rest,
+            _ => tbs,
+        };
+        let (_, serial, tbs) = read_any(tbs)?;
+        let (_, _signature, tbs) = read(tbs, SEQUENCE)?;
+        let (_, issuer, tbs) = read(tbs, SEQUENCE)?;
+        let (_, validity, tbs) = read(tbs, SEQUENCE)?;
+        let (_, subject, mut tbs) = read(tbs, SEQUENCE)?;
+        let (not_before_tag, not_before, validity) = read_any(validity)?;
+        let (not_after_tag, not_after, _) = read_any(validity)?;
+
+        // The extensions are the last optional field, after the key and the unique ids
+        let mut sans = Vec::new();
+        while let Some((tag, content, rest)) = read_any(tbs) {
+            if tag == EXTENSIONS {
+                sans = subject_alt_names(content).unwrap_or_default();
+            }
+            tbs = rest;
+        }
+        Some(Certificate {
+            subject: name(subject),
+            issuer: name(issuer),
+            serial: serial.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            not_before: time(not_before_tag, not_before),
+            not_after: time(not_after_tag, not_after),
+            sans,
+        })
+    }
+
+    // Describe the certificate as "key: value" lines
+    fn describe(&self, now: SystemTime) -> String {
+        let timestamp =
+            |time: Option<SystemTime>| time.map_or("<unknown>".to_string(), format_timestamp);
+        format!(
+            "subject: {}\nissuer: {}\nserial: {}\nnot-before: {}\nnot-after: {}\nexpires: {}\nsans: {}\n",
+            self.subject,
+            self.issuer,
+            self.serial,
+            timestamp(self.not_before),
+            timestamp(self.not_after),
+            self.expires(now),
+            self.sans.join(",")
+        )
+    }
+
+    // Describe when the certificate expires relative to now (e.g. "in 45d", "3d ago")
+    fn expires(&self, now: SystemTime) -> String {
+        match self.not_after {
+            Some(not_after) => match not_after.duration_since(now) {
+                Ok(remaining) => format!("in {}", reports::format_age(remaining)),
+                Err(error) => format!("{} ago", reports::format_age(error.duration())),
+            },
+            None => "<unknown>".to_string(),
+        }
+    }
+}
+
+// Parse all certificates of a PEM file (e.g. tls.crt with the chain of the certificate)
+fn parse_pem(pem: &str) -> Vec<Certificate> {
+    let mut certificates = Vec::new();
+    let mut body: Option<String> = None;
+    for line in pem.lines().map(str::trim) {
+        match line {
+            "-----BEGIN CERTIFICATE-----" => body = Some(String::new()),
+            "-----END CERTIFICATE-----" => {
+                if let Some(certificate) = body
+                    .take()
+                    .and_then(|body| base64_decode(&body))
+                    .and_then(|der| Certificate::from_der(&der))
+                {
+                    certificates.push(certificate);
+                }
+            }
+            line => {
+                if let Some(body) = body.as_mut() {
+                    body.push_str(line);
+                }
+            }
+        }
+    }
+    certificates
+}
+
+// Return the certificates of a TLS secret
+fn secret_certificates(secret: &Value) -> Vec<Certificate> {
+    base64_decode(field(secret, "/data/tls.crt"))
+        .map(|pem| parse_pem(&String::from_utf8_lossy(&pem)))
+        .unwrap_or_default()
+}
+
+// Describe the certificates of a TLS secret, the certificate itself first and then its chain
+pub fn cert_info(context: &str, namespace: &str, secret: &str) -> Result<String, K8sError> {
+    let secret = kubectl::get_object(context, Some(namespace), "secrets", secret)?;
+    let certificates = secret_certificates(&secret);
+    if certificates.is_empty() {
+        return Ok("No certificate found in tls.crt\n".to_string());
+    }
+    let now = SystemTime::now();
+    Ok(certificates
+        .iter()
+        .map(|certificate| certificate.describe(now))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+// List the certificates of all TLS secrets that expire within a window (or already expired),
+// the one that expires first at the top
+pub fn expiring(context: &str, window_days: u64) -> Result<String, K8sError> {
+    let secrets = kubectl::get_items(
+        context,
+        None,
+        "secrets",
+        &["--field-selector", TLS_SECRET_SELECTOR],
+    )?;
+    let now = SystemTime::now();
+    // Windows beyond what SystemTime can represent include every certificate
+    let deadline = Duration::from_secs(window_days.saturating_mul(86400));
+    let deadline = now.checked_add(deadline);
+    let mut expiring: Vec<(String, Certificate)> = secrets
+        .iter()
+        .flat_map(|secret| {
+            let name = format!(
+                "{}/{}",
+                field(secret, "/metadata/namespace"),
+                field(secret, "/metadata/name")
+            );
+            secret_certificates(secret)
+                .into_iter()
+                .map(move |certificate| (name.clone(), certificate))
+        })
+        .filter(|(_, certificate)| {
+            certificate
+                .not_after
+                .is_some_and(|not_after| deadline.is_none_or(|deadline| not_after <= deadline))
+        })
+        .collect();
+    expiring.sort_by_key(|(_, certificate)| certificate.not_after);
+
+    let mut report = format!(
+        "Certificates that expire within {} days\n{:<50}{:<22}{:<12}{}\n",
+        window_days, "SECRET", "NOT-AFTER", "EXPIRES", "SUBJECT"
+    );
+    for (secret, certificate) in expiring {
+        report.push_str(&format!(
+            "{:<50}{:<22}{:<12}{}\n",
+            secret,
+            certificate
+                .not_after
+                .map_or(String::new(), format_timestamp),
+            certificate.expires(now),
+            certificate.subject
+        ));
+    }
+    Ok(report)
+}
+
+// Read a DER element with a tag, return its content and the rest of the input
+fn read(input: &[u8], tag: u8) -> Option<(u8, &[u8], &[u8])> {
+    read_any(input).filter(|(read_tag, _, _)| *read_tag == tag)
+}
+
+// Read a DER element, return its tag, its content and the rest of the input
+fn read_any(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, input) = input.split_first()?;
+    let (length, input) = match first {
+        0..=0x7f => (first as usize, input),
+        // Long form: the low bits tell how many bytes the length has
+        0x81..=0x84 => {
+            let count = (first & 0x7f) as usize;
+            let bytes = input.get(..count)?;
+            let length = bytes
+                .iter()
+                .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            (length, &input[count..])
+        }
+        _ => return None,
+    };
+    let content = input.get(..length)?;
+    Some((tag, content, &input[length..]))
+}
+
+// Format a distinguished name like "CN=example.com,O=Example"
+// The attributes of multi-valued parts are joined with "+" (e.g. "CN=web+OU=Platform").
+fn name(mut input: &[u8]) -> String {
+    let mut parts = Vec::new();
+    while let Some((_, set, rest)) = read(input, SET) {
+        let mut attributes = set;
+        let mut part = Vec::new();
+        while let Some((_, attribute, rest)) = read(attributes, SEQUENCE) {
+            if let Some((_, oid, value)) = read(attribute, OBJECT_IDENTIFIER) {
+                let label = NAME_ATTRIBUTES
+                    .iter()
+                    .find(|(known, _)| *known == oid)
+                    .map(|(_, label)| *label);
+                if let (Some(label), Some((_, value, _))) = (label, read_any(value)) {
+                    part.push(format!("{}={}", label, String::from_utf8_lossy(value)));
+                }
+            }
+            attributes = rest;
+        }
+        if !part.is_empty() {
+            parts.push(part.join("+"));
+        }
+        input = rest;
+    }
+    parts.join(",")
+}
+
+// Read the names of the subjectAltName extension from the extensions of a certificate
+fn subject_alt_names(extensions: &[u8]) -> Option<Vec<String>> {
+    let (_, mut extensions, _) = read(extensions, SEQUENCE)?;
+    while let Some((_, extension, rest)) = read(extensions, SEQUENCE) {
+        let (_, oid, extension) = read(extension, OBJECT_IDENTIFIER)?;
+        if oid == SUBJECT_ALT_NAME {
+            // The critical flag is optional, the value is the last element
+            let mut value = extension;
+            let mut content = &[][..];
+            while let Some((_, element, rest)) = read_any(value) {
+                content = element;
+                value = rest;
+            }
+            let (_, mut general_names, _) = read(content, SEQUENCE)?;
+            let mut names = Vec::new();
+            while let Some((tag, general_name, rest)) = read_any(general_names) {
+                let text = String::from_utf8_lossy(general_name);
+                match tag {
+                    SAN_DNS => names.push(format!("DNS:{}", text)),
+                    SAN_EMAIL => names.push(format!("email:{}", text)),
+                    SAN_URI => names.push(format!("URI:{}", text)),
+                    SAN_IP => {
+                        let ip = match general_name.len() {
+                            4 => <[u8; 4]>::try_from(general_name).ok().map(IpAddr::from),
+                            16 => <[u8; 16]>::try_from(general_name).ok().map(IpAddr::from),
+                            _ => None,
+                        };
+                        if let Some(ip) = ip {
+                            names.push(format!("IP:{}", ip));
+                        }
+                    }
+                    _ => (),
+                }
+                general_names = rest;
+            }
+            return Some(names);
+        }
+        extensions = rest;
+    }
+    Some(Vec::new())
+}
+
+// Convert a UTCTime (YYMMDDHHMMSSZ) or GeneralizedTime (YYYYMMDDHHMMSSZ)
+fn time(tag: u8, content: &[u8]) -> Option<SystemTime> {
+    let text = std::str::from_utf8(content).ok()?;
+    let text = text.strip_suffix('Z')?;
+    let full = match tag {
+        // Two digit years from 50 on are in the 20th century
+        UTC_TIME => {
+            let century = if text.get(..2)? >= "50" { "19" } else { "20" };
+            format!("{}{}", century, text)
+        }
+        _ => text.to_string(),
+    };
+    parse_timestamp(&format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        full.get(..4)?,
+        full.get(4..6)?,
+        full.get(6..8)?,
+        full.get(8..10)?,
+        full.get(10..12)?,
+        full.get(12..14)?
+    ))
+}
+
+// Decode standard base64 (with or without padding), None if it is invalid
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in input
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace() && *byte != b'=')
+    {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed v1 certificate (without version and extensions), valid from 2020 to 2030
+    const V1_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBQzCB6gIBCjAKBggqhkjOPQQDAjAuMRswGQYDVQQDDBJsZWdhY3kuZXhhbXBs
+ZS5jb20xDzANBgNVBAoMBkxlZ2FjeTAeFw0yMDAxMDEwMDAwMDBaFw0zMDAxMDEw
+MDAwMDBaMC4xGzAZBgNVBAMMEmxlZ2FjeS5leGFtcGxlLmNvbTEPMA0GA1UECgwG
+TGVnYWN5MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEsNsdyWBqZLxg6IH5d4Ns
+iCS9UqYaItXQOlR2CnKlbMAriodKha4/kTXSddxVNyfd0sRsvK7EwhkA8wBmd0Xu
+3jAKBggqhkjOPQQDAgNIADBFAiEA5oow85hCj0a6jisP+ZfigYbYdmdL7sFcYFB0
+XsBnH+sCIBQKCToxVc2r9eDeNlaHXZVIG2zB6mfyIkOwR5BX+iUw
+-----END CERTIFICATE-----
+";
+    // Certificate with a multi-valued subject and all kinds of subject alternative names,
+    // signed by CA_PEM and valid from 2024-06-01 to 2025-06-01 12:30
+    const SAN_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBozCCAUmgAwIBAgICEjQwCgYIKoZIzj0EAwIwJzETMBEGA1UEAwwKRXhhbXBs
+ZSBDQTEQMA4GA1UECgwHRXhhbXBsZTAeFw0yNDA2MDEwMDAwMDBaFw0yNTA2MDEx
+MjMwMDBaMCwxHTAKBgNVBAMMA3dlYjAPBgNVBAsMCFBsYXRmb3JtMQswCQYDVQQG
+EwJERTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABAd4nGzE96+7ae7gLcRkrqUe
+MaszgUi+yyq14Ub3s0iA8ajPVOrjxh4lu+wIuK8Xa1tovehgccdS02ZufF2zEIuj
+YDBeMFwGA1UdEQRVMFOCD3dlYi5leGFtcGxlLmNvbYcECgAAAYcQIAENuAAAAAAA
+AAAAAAAAAYEPb3BzQGV4YW1wbGUuY29thhdodHRwczovL2V4YW1wbGUuY29tL3dl
+YjAKBggqhkjOPQQDAgNIADBFAiAkLQUgGrUVsNsQdBuBvRiDoEf1mW4Ti8xBcJvd
+GnQi5gIhAPdlNOqIMEH2WynKVvPNPHpEIg/S91oyXBhAlUUaJy1x
+-----END CERTIFICATE-----
+";
+    const CA_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBTjCB9qADAgECAgEBMAoGCCqGSM49BAMCMCcxEzARBgNVBAMMCkV4YW1wbGUg
+Q0ExEDAOBgNVBAoMB0V4YW1wbGUwHhcNMjQwMTAxMDAwMDAwWhcNMzQwMTAxMDAw
+MDAwWjAnMRMwEQYDVQQDDApFeGFtcGxlIENBMRAwDgYDVQQKDAdFeGFtcGxlMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEG/ff9xuD6S4b79AQdXqoPFn44tKDsKGz
+YbUvX+fGgeggcUA6lZstcUH/3acOH3fOfR4XwfFld+OCYI3o3x5FTKMTMBEwDwYD
+VR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNHADBEAiAc0AV1Ey2etevh6yKbWqgp
+yx+PpdXXexA7w9gKieIjKAIgXXt3VG5dHTfXrrD6LSI0BOpmbTVBU9a2v2FR5fUe
+LJg=
+-----END CERTIFICATE-----
+";
+    // Certificate that expired on 2021-01-01
+    const EXPIRED_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBVTCB/KADAgECAgEDMAoGCCqGSM49BAMCMCcxEzARBgNVBAMMCkV4YW1wbGUg
+Q0ExEDAOBgNVBAoMB0V4YW1wbGUwHhcNMTkwMTAxMDAwMDAwWhcNMjEwMTAxMDAw
+MDAwWjAeMRwwGgYDVQQDDBNleHBpcmVkLmV4YW1wbGUuY29tMFkwEwYHKoZIzj0C
+AQYIKoZIzj0DAQcDQgAEXnSVlkrKfgrYjnUuH3cx8LjOUzg/5uYlWpV/GYHYdAx2
+syT/L9A9WaQtsPSjr4k/oK0vxjbPT7I++dB4sM9+36MiMCAwHgYDVR0RBBcwFYIT
+ZXhwaXJlZC5leGFtcGxlLmNvbTAKBggqhkjOPQQDAgNIADBFAiBbZIgbPMHmesY7
+LAV4TYBQHlUPBjyUKVEp+1SrcxkZEQIhALpviALfrLdvDKLaZd9u36KNYk1FOHAs
+pCTspN/QnUmc
+-----END CERTIFICATE-----
+";
+    // Certificate that is valid from 2051 on, with GeneralizedTime instead of UTCTime
+    const FUTURE_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBNTCB26ADAgECAgEEMAoGCCqGSM49BAMCMCcxEzARBgNVBAMMCkV4YW1wbGUg
+Q0ExEDAOBgNVBAoMB0V4YW1wbGUwIhgPMjA1MTAxMDEwMDAwMDBaGA8yMDUyMDEw
+MTAwMDAwMFowHTEbMBkGA1UEAwwSZnV0dXJlLmV4YW1wbGUuY29tMFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAEbyJVU3uO57iKxCNTtcGZeJud60AhHXC6SPXNL3pe
+ASOBBkt0/Os7+87sT3o8j99LtDvSVPDMA4xhLkKF8FPrCzAKBggqhkjOPQQDAgNJ
+ADBGAiEAqL21DhhpkqiKMBQAdIQVNX2MT38i/i5A/3qX2X+y8SQCIQCawV65lznC
+KVncuWDpqOPFejcAf8cx+Q5Dh6EDrGtgUA==
+-----END CERTIFICATE-----
+";
+
+    fn at(timestamp: &str) -> SystemTime {
+        parse_timestamp(timestamp).unwrap()
+    }
+
+    fn parse_one(pem: &str) -> Certificate {
+        let mut certificates = parse_pem(pem);
+        assert_eq!(certificates.len(), 1);
+        certificates.remove(0)
+    }
+
+    #[test]
+    fn v1_certificate() {
+        let certificate = parse_one(V1_PEM);
+        assert_eq!(certificate.subject, "CN=legacy.example.com,O=Legacy");
+        assert_eq!(certificate.issuer, certificate.subject);
+        assert_eq!(certificate.serial, "0a");
+        assert_eq!(certificate.not_before, Some(at("2020-01-01T00:00:00Z")));
+        assert_eq!(certificate.not_after, Some(at("2030-01-01T00:00:00Z")));
+        assert!(certificate.sans.is_empty());
+    }
+
+    #[test]
+    fn subject_alt_names() {
+        let certificate = parse_one(SAN_PEM);
+        assert_eq!(certificate.subject, "CN=web+OU=Platform,C=DE");
+        assert_eq!(certificate.issuer, "CN=Example CA,O=Example");
+        assert_eq!(certificate.serial, "1234");
+        assert_eq!(
+            certificate.sans,
+            [
+                "DNS:web.example.com",
+                "IP:10.0.0.1",
+                "IP:2001:db8::1",
+                "email:ops@example.com",
+                "URI:https://example.com/web"
+            ]
+        );
+    }
+
+    #[test]
+    fn chain() {
+        let chain = format!("{}{}", SAN_PEM, CA_PEM);
+        let certificates = parse_pem(&chain);
+        assert_eq!(certificates.len(), 2);
+        assert_eq!(certificates[0].issuer, certificates[1].subject);
+        assert_eq!(certificates[1].issuer, certificates[1].subject);
+    }
+
+    #[test]
+    fn expired_certificate() {
+        let certificate = parse_one(EXPIRED_PEM);
+        assert_eq!(certificate.not_after, Some(at("2021-01-01T00:00:00Z")));
+        assert_eq!(certificate.expires(at("2021-01-04T00:00:00Z")), "3d ago");
+        let description = certificate.describe(at("2021-01-04T00:00:00Z"));
+        assert!(description.contains("not-after: 2021-01-01T00:00:00Z\n"));
+        assert!(description.contains("sans: DNS:expired.example.com\n"));
+    }
+
+    #[test]
+    fn not_yet_valid_certificate() {
+        let certificate = parse_one(FUTURE_PEM);
+        assert_eq!(certificate.not_before, Some(at("2051-01-01T00:00:00Z")));
+        assert_eq!(certificate.not_after, Some(at("2052-01-01T00:00:00Z")));
+        assert!(certificate.not_before > Some(at("2026-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn invalid_certificates_are_skipped() {
+        let pem = format!(
+            "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n{}",
+            V1_PEM
+        );
+        assert_eq!(parse_pem(&pem).len(), 1);
+        assert!(
+            parse_pem("-----BEGIN CERTIFICATE-----\n!!!\n-----END CERTIFICATE-----\n").is_empty()
+        );
+    }
+
+    #[test]
+    fn base64_with_and_without_padding() {
+        assert_eq!(base64_decode("aGk="), Some(b"hi".to_vec()));
+        assert_eq!(base64_decode("aGk"), Some(b"hi".to_vec()));
+        assert_eq!(base64_decode("aGVs\nbG8="), Some(b"hello".to_vec()));
+        assert_eq!(base64_decode("+/8="), Some(vec![0xfb, 0xff]));
+        assert_eq!(base64_decode("a-b_"), None);
+    }
+}
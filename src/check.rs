@@ -0,0 +1,304 @@
+use crate::kubectl::{self, K8sError};
+use crate::rbac::ALL_NAMESPACES_NAME;
+use crate::version;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+
+// Permissions that are needed to show the filesystem: (verb, resource)
+const READ_PERMISSIONS: [(&str, &str); 3] =
+    [("list", "pods"), ("get", "pods"), ("get", "pods/log")];
+// Permissions that are needed to change the cluster through the filesystem
+const WRITE_PERMISSIONS: [(&str, &str); 3] = [
+    ("create", "namespaces"),
+    ("delete", "namespaces"),
+    ("create", "pods/exec"),
+];
+
+// Resources that the mount shows besides pods, which are checked by "k8sfs doctor"
+const SHOWN_RESOURCES: [&str; 6] = [
+    "services",
+    "configmaps",
+    "deployments.apps",
+    "statefulsets.apps",
+    "events",
+    "nodes",
+];
+
+const FUSE_DEVICE: &str = "/dev/fuse";
+const SETUID_BIT: u32 = 0o4000;
+
+// Result of a single preflight check
+enum Outcome {
+    Ok(String),
+    // The filesystem works, but some files will be missing (e.g. FORBIDDEN markers)
+    Warning(String),
+    Failed(String),
+}
+
+// Report that is printed by "k8sfs check" and "k8sfs doctor"
+struct Report {
+    failed: bool,
+    // Print how to fix checks that did not pass ("k8sfs doctor")
+    remediate: bool,
+}
+
+impl Report {
+    fn print(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Ok(message) => println!("[ok]   {}", message),
+            Outcome::Warning(message) => println!("[warn] {}", message),
+            Outcome::Failed(message) => {
+                self.failed = true;
+                println!("[fail] {}", message);
+            }
+        }
+    }
+
+    // Print an outcome and, if it did not pass, how to fix it
+    fn print_with_fix(&mut self, outcome: Outcome, fix: &str) {
+        let passed = matches!(outcome, Outcome::Ok(_));
+        self.print(outcome);
+        if self.remediate && !passed {
+            println!("       -> {}", fix);
+        }
+    }
+}
+
+// Check that a context can be mounted and print a report
+// Returns false if mounting would not work
+pub fn run(context: Option<&str>, namespaces: &[String], allow_write: bool) -> bool {
+    let mut report = Report {
+        failed: false,
+        remediate: false,
+    };
+    check_cluster(&mut report, context, namespaces, allow_write);
+    !report.failed
+}
+
+// Check everything that a first mount needs, including the environment (FUSE), and print how
+// to fix what is missing
+// Returns false if mounting would not work
+pub fn doctor(context: Option<&str>, namespaces: &[String], allow_write: bool) -> bool {
+    let mut report = Report {
+        failed: false,
+        remediate: true,
+    };
+    let (outcome, fix) = fuse_device();
+    report.print_with_fix(outcome, fix);
+    let (outcome, fix) = fusermount();
+    report.print_with_fix(outcome, fix);
+    if check_cluster(&mut report, context, namespaces, allow_write) {
+        let context = context
+            .map(String::from)
+            .unwrap_or_else(kubectl::current_context);
+        for resource in SHOWN_RESOURCES {
+            // Nodes do not belong to a namespace
+            let scopes = match resource {
+                "nodes" => vec![None],
+                _ => scopes(namespaces),
+            };
+            for namespace in scopes {
+                let outcome = permission(&context, "list", resource, namespace, false);
+                let fix = format!(
+                    "Without it, {} are missing from the mount. Ask a cluster administrator for a role that allows to list {}.",
+                    resource, resource
+                );
+                report.print_with_fix(outcome, &fix);
+            }
+        }
+    }
+    !report.failed
+}
+
+// Check kubectl, the context, the API server and the permissions that a mount needs
+// Returns false if the API server can not be used, so that further checks can not succeed
+fn check_cluster(
+    report: &mut Report,
+    context: Option<&str>,
+    namespaces: &[String],
+    allow_write: bool,
+) -> bool {
+    match kubectl::client_version() {
+        Ok(version) => report.print(Outcome::Ok(format!("kubectl {} is installed", version))),
+        Err(error) => {
+            report.print_with_fix(
+                Outcome::Failed(format!("kubectl can not be executed: {}", error)),
+                "Install kubectl (https://kubernetes.io/docs/tasks/tools/) and make sure that it is in PATH",
+            );
+            return false;
+        }
+    }
+
+    let context = context
+        .map(String::from)
+        .unwrap_or_else(kubectl::current_context);
+    if context.is_empty() {
+        report.print_with_fix(
+            Outcome::Failed(String::from("No current context is set, use --context")),
+            "Select a context with \"kubectl config use-context <name>\" or pass --context <name>",
+        );
+        return false;
+    }
+    match kubectl::kubeconfig(&context) {
+        Ok(_) => report.print(Outcome::Ok(format!("Context {} exists", context))),
+        Err(error) => {
+            report.print_with_fix(
+                Outcome::Failed(format!("Context {} can not be used: {}", context, error)),
+                "List the available contexts with \"kubectl config get-contexts\" and check KUBECONFIG",
+            );
+            return false;
+        }
+    }
+
+    let (outcome, fix) = match kubectl::namespaces(&context) {
+        Ok(found) => (
+            Outcome::Ok(format!(
+                "API server is reachable ({} namespaces)",
+                found.len()
+            )),
+            "",
+        ),
+        Err(K8sError::Forbidden(_)) if !namespaces.is_empty() => (
+            Outcome::Ok(String::from(
+                "API server is reachable (namespaces can not be listed, --namespace is used)",
+            )),
+            "",
+        ),
+        Err(K8sError::Forbidden(error)) => (
+            Outcome::Failed(format!(
+                "Namespaces can not be listed, use --namespace: {}",
+                error
+            )),
+            "Pass the namespaces that you are allowed to access with --namespace <name>",
+        ),
+        Err(error) => (
+            Outcome::Failed(format!("API server is not reachable: {}", error)),
+            "Check the network connection and the server of the context (\"kubectl cluster-info\"), and that the credentials did not expire",
+        ),
+    };
+    let reachable = !matches!(outcome, Outcome::Failed(_));
+    report.print_with_fix(outcome, fix);
+
+    // The versions can only be compared if the API server is reachable
+    if let Ok(versions) = kubectl::versions(&context) {
+        report.print_with_fix(
+            match version::skew_warning(&versions) {
+                Some(warning) => Outcome::Warning(warning),
+                None => Outcome::Ok(String::from(
+                    "kubectl is within the supported version skew of the API server",
+                )),
+            },
+            "Install a kubectl version that is at most one minor version away from the API server",
+        );
+    }
+
+    let mut permissions = vec![(READ_PERMISSIONS.as_slice(), false)];
+    if allow_write {
+        permissions.push((WRITE_PERMISSIONS.as_slice(), true));
+    }
+    for (permissions, required) in permissions {
+        for (verb, resource) in permissions {
+            for namespace in scopes(namespaces) {
+                let outcome = permission(&context, verb, resource, namespace, required);
+                let fix = format!(
+                    "Ask a cluster administrator for a role that allows to {} {}",
+                    verb, resource
+                );
+                report.print_with_fix(outcome, &fix);
+            }
+        }
+    }
+    reachable
+}
+
+// Namespaces that permissions are checked in, None checks them in all namespaces
+fn scopes(namespaces: &[String]) -> Vec<Option<&str>> {
+    if namespaces.is_empty() {
+        vec![None]
+    } else {
+        namespaces
+            .iter()
+            .map(|namespace| Some(namespace.as_str()))
+            .collect()
+    }
+}
+
+// Check that the FUSE device exists and can be opened
+fn fuse_device() -> (Outcome, &'static str) {
+    match OpenOptions::new().read(true).write(true).open(FUSE_DEVICE) {
+        Ok(_) => (
+            Outcome::Ok(format!("{} can be opened", FUSE_DEVICE)),
+            "",
+        ),
+        Err(error) if error.kind() == ErrorKind::NotFound => (
+            Outcome::Failed(format!("{} does not exist", FUSE_DEVICE)),
+            "Load the FUSE kernel module with \"modprobe fuse\" (in containers, pass --device /dev/fuse)",
+        ),
+        Err(error) if error.kind() == ErrorKind::PermissionDenied => (
+            Outcome::Failed(format!("{} can not be opened: {}", FUSE_DEVICE, error)),
+            "Allow your user to read and write /dev/fuse (e.g. by adding it to the fuse group)",
+        ),
+        Err(error) => (
+            Outcome::Failed(format!("{} can not be opened: {}", FUSE_DEVICE, error)),
+            "Check that FUSE is supported by the kernel (grep fuse /proc/filesystems)",
+        ),
+    }
+}
+
+// Check that fusermount is installed and allows unprivileged users to mount
+// Without it only root can mount the filesystem.
+fn fusermount() -> (Outcome, &'static str) {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let found = ["fusermount3", "fusermount"].iter().find_map(|name| {
+        std::env::split_paths(&path)
+            .map(|directory| directory.join(name))
+            .find(|candidate| candidate.is_file())
+    });
+    let Some(found) = found else {
+        return (
+            Outcome::Failed(String::from("fusermount is not installed")),
+            "Install FUSE (e.g. \"apt install fuse3\" or \"dnf install fuse3\")",
+        );
+    };
+    let is_setuid = std::fs::metadata(&found)
+        .is_ok_and(|metadata| metadata.permissions().mode() & SETUID_BIT != 0);
+    // SAFETY: geteuid() always succeeds and has no side effects
+    let is_root = unsafe { libc::geteuid() } == 0;
+    match is_setuid || is_root {
+        true => (Outcome::Ok(format!("{} is installed", found.display())), ""),
+        false => (
+            Outcome::Warning(format!(
+                "{} is not setuid root, mounting as a regular user fails",
+                found.display()
+            )),
+            "Restore the permissions of fusermount with \"chmod u+s\" (as root) or mount as root",
+        ),
+    }
+}
+
+// Check a single permission with "kubectl auth can-i"
+// Missing read permissions only hide parts of the filesystem, so they are warnings.
+fn permission(
+    context: &str,
+    verb: &str,
+    resource: &str,
+    namespace: Option<&str>,
+    required: bool,
+) -> Outcome {
+    let description = format!(
+        "{} {} in {}",
+        verb,
+        resource,
+        namespace.unwrap_or(ALL_NAMESPACES_NAME)
+    );
+    match kubectl::can_i(context, verb, resource, namespace) {
+        Ok(true) => Outcome::Ok(format!("Allowed to {}", description)),
+        Ok(false) if required => Outcome::Failed(format!("Not allowed to {}", description)),
+        Ok(false) => Outcome::Warning(format!("Not allowed to {}", description)),
+        Err(error) => Outcome::Failed(format!(
+            "Could not check if allowed to {}: {}",
+            description, error
+        )),
+    }
+}
@@ -0,0 +1,797 @@
+use crate::cache::DEFAULT_CACHE_MAX_BYTES;
+use crate::certs::DEFAULT_EXPIRY_WINDOW_DAYS;
+use crate::cost::PriceTable;
+use crate::diagnostics;
+use crate::health::{self, HealthRule};
+use crate::ignore::{self, DEFAULT_IGNORE_PATTERNS};
+use crate::in_cluster;
+use crate::k8s_resource::{DEFAULT_DEFINITION_SUFFIX, MANIFEST_FILE_NAME};
+use crate::kubectl;
+use crate::layout::Layout;
+use crate::modes::ModeMap;
+use crate::pagination::DEFAULT_PAGE_SIZE;
+use crate::prefetch::PrefetchPolicy;
+use crate::renderer::Renderer;
+use crate::sidecar;
+use crate::templates::{self, Template};
+use crate::timeline::DEFAULT_TIMELINE_MINUTES;
+use crate::trash::DEFAULT_TRASH_RETENTION;
+use clap::ArgMatches;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Separator used for list values in mount options
+// We can not use "," because mount already uses it to separate the options themselves
+const MOUNT_OPTION_LIST_SEPARATOR: char = ':';
+// Logs are mostly read sequentially and in large chunks, so the kernel should read ahead a lot
+// and send many reads in parallel
+const DEFAULT_MAX_READAHEAD: u32 = 1024 * 1024;
+const DEFAULT_MAX_BACKGROUND: u16 = 64;
+
+// Runtime configuration of the filesystem
+// This is built either from the command line arguments or from the mount options
+// that are passed by mount(8) when k8sfs is used as a mount helper.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub mountpoint: String,
+    // Allow creating / deleting kubernetes resources through the filesystem
+    pub allow_write: bool,
+    // Kubernetes context that should be mounted
+    // If this is not set, the current context of kubectl is used
+    pub context: Option<String>,
+    // Use the service account of the pod k8sfs runs in (see in_cluster)
+    pub in_cluster: bool,
+    // Mount writable even if the context does not match writable_contexts
+    pub force_write: bool,
+    // Glob patterns of contexts that may be mounted writable (e.g. *-dev)
+    // If this is empty, all contexts may be mounted writable
+    pub writable_contexts: Vec<String>,
+    // Which directory listings prefetch the descriptions of their children
+    pub prefetch: PrefetchPolicy,
+    // How the resources of namespaces are laid out
+    pub layout: Layout,
+    // Special mode bits of files per kind
+    pub modes: ModeMap,
+    // File that the stderr of kubectl calls is logged to, None for the default location
+    pub kubectl_log: Option<PathBuf>,
+    // Add proxy directories to services that fetch their HTTP endpoints when read
+    pub service_proxy: bool,
+    // Suffix of definition files (<resource><suffix>)
+    pub definition_suffix: String,
+    // Name definition files manifest.yaml instead of using definition_suffix
+    // This avoids names like app.yaml_definition.yaml for resources whose names end in .yaml
+    pub definition_as_manifest: bool,
+    // Include events in descriptions (definition files), otherwise they are in events files
+    pub show_events: bool,
+    // Move succeeded and evicted pods into the completed directory of their namespace
+    pub hide_completed: bool,
+    // Only count the pods of namespaces and list them once a namespace is accessed
+    pub large_cluster: bool,
+    // Deferred namespaces with more pods than this are listed page by page (0 disables it)
+    pub page_size: u64,
+    // Include credentials in generated kubeconfig files instead of redacting them
+    pub expose_credentials: bool,
+    // Only show these namespaces
+    // If this is empty, all namespaces are shown
+    pub namespaces: Vec<String>,
+    // Unmount and exit after the filesystem was not used for this long
+    pub idle_timeout: Option<Duration>,
+    // How long the manifests of deleted resources are kept in .k8sfs/trash
+    pub trash_retention: Duration,
+    // Certificates that expire within this many days are listed in expiring-certs
+    pub expiring_certs_days: u64,
+    // How far back the timeline file goes
+    pub timeline_minutes: u64,
+    // Maximum amount of memory that cached file content is allowed to use
+    pub cache_max_bytes: usize,
+    // Add the local user and the mount point to the User-Agent of kubectl calls
+    pub identify: bool,
+    // List kubernetes resources from newest to oldest instead of in the order they were added
+    pub sort_by_age: bool,
+    // Finalize terminating namespaces on rmdir without waiting for FORCE_DELETE_AFTER
+    pub force_delete: bool,
+    // Take over fields of other field managers when applying manifests server-side
+    pub force_conflicts: bool,
+    // Refuse changes to resources that Argo CD or Flux manage
+    pub protect_gitops: bool,
+    // Label that Argo CD tracks resources with (e.g. app.kubernetes.io/instance)
+    // Only the tracking annotation is used if it is not set.
+    pub argocd_instance_label: Option<String>,
+    // Queue changes while the API server is unreachable and replay them later
+    pub queue_offline: bool,
+    // Add an events.stream file to the context directory that streams events as JSON lines
+    pub events_stream: bool,
+    // Maximum number of changes (creates, deletes, applies) per hour, None for no limit
+    pub max_changes_per_hour: Option<usize>,
+    // Kernel settings that are negotiated when the filesystem is mounted
+    // Maximum number of bytes that the kernel reads ahead
+    pub max_readahead: u32,
+    // Maximum size of a single write, None for the default of fuser
+    pub max_write: Option<u32>,
+    // Maximum number of requests (e.g. readahead) that the kernel sends in parallel
+    pub max_background: u16,
+    // Maximum size of a single read, None for the default of the kernel
+    pub max_read: Option<u32>,
+    // Glob patterns of names that are never looked up (e.g. .git, *.swp)
+    pub ignore_patterns: Vec<String>,
+    // Extra files per kubernetes resource (see [[renderers]] in the config file)
+    pub renderers: Vec<Renderer>,
+    // Manifests that can be instantiated by creating files in namespace directories
+    pub templates: Vec<Template>,
+    // Rules that assess the health of kubernetes resources (see [[health]] in the config file)
+    pub health_rules: Vec<HealthRule>,
+    // Prices of requested resources for the cost estimate of namespaces (see [prices] in the
+    // config file)
+    pub prices: Option<PriceTable>,
+    // Path of the directory (below the context) that is mounted instead of the whole context
+    // (e.g. ["default"] for ctx/default)
+    pub root_path: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            mountpoint: String::new(),
+            allow_write: false,
+            context: None,
+            in_cluster: false,
+            force_write: false,
+            writable_contexts: Vec::new(),
+            prefetch: PrefetchPolicy::Namespaces,
+            layout: Layout::Kind,
+            modes: ModeMap::default(),
+            kubectl_log: None,
+            service_proxy: false,
+            definition_suffix: DEFAULT_DEFINITION_SUFFIX.to_string(),
+            definition_as_manifest: false,
+            show_events: true,
+            hide_completed: true,
+            large_cluster: false,
+            page_size: DEFAULT_PAGE_SIZE,
+            expose_credentials: false,
+            namespaces: Vec::new(),
+            idle_timeout: None,
+            trash_retention: DEFAULT_TRASH_RETENTION,
+            expiring_certs_days: DEFAULT_EXPIRY_WINDOW_DAYS,
+            timeline_minutes: DEFAULT_TIMELINE_MINUTES,
+            cache_max_bytes: DEFAULT_CACHE_MAX_BYTES,
+            identify: false,
+            sort_by_age: false,
+            force_delete: false,
+            force_conflicts: false,
+            protect_gitops: false,
+            argocd_instance_label: None,
+            queue_offline: false,
+            events_stream: false,
+            max_changes_per_hour: None,
+            max_readahead: DEFAULT_MAX_READAHEAD,
+            max_write: None,
+            max_background: DEFAULT_MAX_BACKGROUND,
+            max_read: None,
+            ignore_patterns: DEFAULT_IGNORE_PATTERNS.map(String::from).to_vec(),
+            renderers: Vec::new(),
+            templates: templates::default_templates(),
+            health_rules: health::default_rules(),
+            prices: None,
+            root_path: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn new(mountpoint: &str) -> Self {
+        Config {
+            mountpoint: mountpoint.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn allow_write(mut self, allow_write: bool) -> Self {
+        self.allow_write = allow_write;
+        self
+    }
+
+    pub fn context(mut self, context: Option<String>) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn in_cluster(mut self, in_cluster: bool) -> Self {
+        self.in_cluster = in_cluster;
+        if in_cluster {
+            self.context = Some(in_cluster::CONTEXT_NAME.to_string());
+        }
+        self
+    }
+
+    pub fn layout(mut self, layout: Option<&String>) -> Self {
+        if let Some(layout) = layout.and_then(|name| Layout::from_name(name)) {
+            self.layout = layout;
+        }
+        self
+    }
+
+    // Mappings are added to the default mappings
+    pub fn modes(mut self, mappings: Vec<String>) -> Self {
+        for mapping in mappings {
+            if let Err(error) = self.modes.add(&mapping) {
+                log::error!("{}, ignoring it", error);
+            }
+        }
+        self
+    }
+
+    pub fn force_write(mut self, force_write: bool) -> Self {
+        self.force_write = force_write;
+        self
+    }
+
+    pub fn prefetch(mut self, prefetch: Option<&String>) -> Self {
+        if let Some(prefetch) = prefetch.and_then(|name| PrefetchPolicy::from_name(name)) {
+            self.prefetch = prefetch;
+        }
+        self
+    }
+
+    pub fn kubectl_log(mut self, kubectl_log: Option<&String>) -> Self {
+        self.kubectl_log = kubectl_log.map(PathBuf::from);
+        self
+    }
+
+    pub fn service_proxy(mut self, service_proxy: bool) -> Self {
+        self.service_proxy = service_proxy;
+        self
+    }
+
+    pub fn definition_suffix(mut self, definition_suffix: Option<&String>) -> Self {
+        if let Some(definition_suffix) = definition_suffix {
+            self.definition_suffix = definition_suffix.to_string();
+        }
+        self
+    }
+
+    pub fn definition_as_manifest(mut self, definition_as_manifest: bool) -> Self {
+        self.definition_as_manifest = definition_as_manifest;
+        self
+    }
+
+    // Name of the definition file inside the directory of a kubernetes resource
+    pub fn definition_file_name(&self, resource_name: &str) -> String {
+        match self.definition_as_manifest {
+            true => MANIFEST_FILE_NAME.to_string(),
+            false => format!("{}{}", resource_name, self.definition_suffix),
+        }
+    }
+
+    pub fn show_events(mut self, show_events: Option<bool>) -> Self {
+        self.show_events = show_events.unwrap_or(true);
+        self
+    }
+
+    pub fn hide_completed(mut self, hide_completed: Option<bool>) -> Self {
+        self.hide_completed = hide_completed.unwrap_or(true);
+        self
+    }
+
+    pub fn large_cluster(mut self, large_cluster: bool) -> Self {
+        self.large_cluster = large_cluster;
+        self
+    }
+
+    pub fn page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn expose_credentials(mut self, expose_credentials: bool) -> Self {
+        self.expose_credentials = expose_credentials;
+        self
+    }
+
+    pub fn namespaces(mut self, namespaces: Vec<String>) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+
+    pub fn idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn trash_retention(mut self, trash_retention: Duration) -> Self {
+        self.trash_retention = trash_retention;
+        self
+    }
+
+    pub fn expiring_certs_days(mut self, expiring_certs_days: u64) -> Self {
+        self.expiring_certs_days = expiring_certs_days;
+        self
+    }
+
+    pub fn timeline_minutes(mut self, timeline_minutes: u64) -> Self {
+        self.timeline_minutes = timeline_minutes;
+        self
+    }
+
+    pub fn cache_max_bytes(mut self, cache_max_bytes: usize) -> Self {
+        self.cache_max_bytes = cache_max_bytes;
+        self
+    }
+
+    pub fn identify(mut self, identify: bool) -> Self {
+        self.identify = identify;
+        self
+    }
+
+    pub fn sort_by_age(mut self, sort_by_age: bool) -> Self {
+        self.sort_by_age = sort_by_age;
+        self
+    }
+
+    pub fn force_delete(mut self, force_delete: bool) -> Self {
+        self.force_delete = force_delete;
+        self
+    }
+
+    pub fn force_conflicts(mut self, force_conflicts: bool) -> Self {
+        self.force_conflicts = force_conflicts;
+        self
+    }
+
+    pub fn protect_gitops(mut self, protect_gitops: bool) -> Self {
+        self.protect_gitops = protect_gitops;
+        self
+    }
+
+    pub fn argocd_instance_label(mut self, argocd_instance_label: Option<&String>) -> Self {
+        self.argocd_instance_label = argocd_instance_label.cloned();
+        self
+    }
+
+    pub fn queue_offline(mut self, queue_offline: bool) -> Self {
+        self.queue_offline = queue_offline;
+        self
+    }
+
+    pub fn events_stream(mut self, events_stream: bool) -> Self {
+        self.events_stream = events_stream;
+        self
+    }
+
+    pub fn max_changes_per_hour(mut self, max_changes_per_hour: Option<usize>) -> Self {
+        self.max_changes_per_hour = max_changes_per_hour;
+        self
+    }
+
+    pub fn max_readahead(mut self, max_readahead: Option<u32>) -> Self {
+        self.max_readahead = max_readahead.unwrap_or(DEFAULT_MAX_READAHEAD);
+        self
+    }
+
+    pub fn max_write(mut self, max_write: Option<u32>) -> Self {
+        self.max_write = max_write;
+        self
+    }
+
+    pub fn max_background(mut self, max_background: Option<u16>) -> Self {
+        self.max_background = max_background.unwrap_or(DEFAULT_MAX_BACKGROUND);
+        self
+    }
+
+    pub fn max_read(mut self, max_read: Option<u32>) -> Self {
+        self.max_read = max_read;
+        self
+    }
+
+    // Patterns are added to the default patterns
+    pub fn ignore_patterns(mut self, ignore_patterns: Vec<String>) -> Self {
+        self.ignore_patterns.extend(ignore_patterns);
+        self
+    }
+
+    // Only mount a subtree of the hierarchy (<context>/<namespace>/<pod>)
+    // Mounts of subtrees are always read-only, because writing (e.g. creating namespaces or
+    // applying manifests) happens outside of the subtree.
+    pub fn root_path(mut self, root_path: Option<&String>) -> Result<Self, String> {
+        let Some(root_path) = root_path else {
+            return Ok(self);
+        };
+        let mut components = root_path
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(String::from);
+        let context = components
+            .next()
+            .ok_or_else(|| format!("Invalid root path {:?}", root_path))?;
+        if self
+            .context
+            .as_ref()
+            .is_some_and(|current| *current != context)
+        {
+            return Err(format!(
+                "Root path {} does not belong to context {}",
+                root_path,
+                self.context.unwrap()
+            ));
+        }
+        self.context = Some(context);
+        self.root_path = components.collect();
+        // Only the namespace of the subtree has to be retrieved from the cluster
+        if let Some(namespace) = self.root_path.first() {
+            self.namespaces = vec![namespace.clone()];
+        }
+        if self.allow_write {
+            log::warn!("Mounting {} read-only", root_path);
+            self.allow_write = false;
+        }
+
+        Ok(self)
+    }
+
+    // Load settings from a TOML config file
+    // Settings that are passed as arguments are not overwritten
+    pub fn config_file(mut self, path: Option<&String>) -> Result<Self, String> {
+        let Some(path) = path else {
+            return Ok(self);
+        };
+        let content = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read config file {}: {}", path, error))?;
+        let config_file = content
+            .parse::<toml::Table>()
+            .map_err(|error| format!("Could not parse config file {}: {}", path, error))?;
+
+        for entry in config_file
+            .get("renderers")
+            .and_then(toml::Value::as_array)
+            .unwrap_or(&Vec::new())
+        {
+            self.renderers.push(Renderer::from_toml(entry)?);
+        }
+        let mut health_rules = Vec::new();
+        for entry in config_file
+            .get("health")
+            .and_then(toml::Value::as_array)
+            .unwrap_or(&Vec::new())
+        {
+            health_rules.push(HealthRule::from_toml(entry)?);
+        }
+        // Configured rules replace the default rules of their kind
+        self.health_rules.retain(|default_rule| {
+            !health_rules
+                .iter()
+                .any(|rule| rule.resource_type == default_rule.resource_type)
+        });
+        self.health_rules.extend(health_rules);
+        for pattern in config_file
+            .get("ignore")
+            .and_then(toml::Value::as_array)
+            .unwrap_or(&Vec::new())
+        {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| format!("Invalid ignore pattern {:?} in {}", pattern, path))?;
+            self.ignore_patterns.push(pattern.to_string());
+        }
+        for pattern in config_file
+            .get("writable_contexts")
+            .and_then(toml::Value::as_array)
+            .unwrap_or(&Vec::new())
+        {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| format!("Invalid writable context {:?} in {}", pattern, path))?;
+            self.writable_contexts.push(pattern.to_string());
+        }
+        if let Some(prices) = config_file.get("prices") {
+            self.prices = Some(PriceTable::from_toml(prices)?);
+        }
+        if let Some(templates) = config_file.get("templates").and_then(toml::Value::as_table) {
+            for (name, entry) in templates.iter() {
+                // Configured templates replace the default template with the same name
+                self.templates.retain(|template| template.name != *name);
+                self.templates.push(Template::from_toml(name, entry)?);
+            }
+        }
+
+        Ok(self)
+    }
+
+    // Refuse writable mounts of contexts that do not match writable_contexts
+    // (e.g. production clusters that were mounted with --allow-write by habit)
+    pub fn check_writable(self) -> Result<Self, String> {
+        if !self.allow_write || self.force_write || self.writable_contexts.is_empty() {
+            return Ok(self);
+        }
+        let context = self
+            .context
+            .clone()
+            .unwrap_or_else(kubectl::current_context);
+        if self
+            .writable_contexts
+            .iter()
+            .any(|pattern| ignore::glob_matches(pattern, &context))
+        {
+            return Ok(self);
+        }
+        Err(format!(
+            "Refusing to mount {} writable, because it does not match writable_contexts ({}). Use --force-write to mount it writable anyway.",
+            context,
+            self.writable_contexts.join(", ")
+        ))
+    }
+
+    // File that the stderr of kubectl calls is logged to (see --kubectl-log)
+    pub fn kubectl_log_file(&self) -> Option<PathBuf> {
+        self.kubectl_log
+            .clone()
+            .or_else(|| diagnostics::default_log_file(&self.mountpoint))
+    }
+
+    // Identity of this mount that is sent to the API server (see --identify)
+    //   <local user>@<mount point>
+    pub fn identity(&self) -> Option<String> {
+        if !self.identify {
+            return None;
+        }
+        let user = std::env::var("USER").unwrap_or_else(|_| {
+            // SAFETY: getuid() always succeeds and has no side effects
+            unsafe { libc::getuid() }.to_string()
+        });
+        Some(format!("{}@{}", user, self.mountpoint))
+    }
+
+    // Build configuration from the arguments of the k8sfs command
+    pub fn from_args(matches: &ArgMatches) -> Result<Self, String> {
+        Config::new(matches.get_one::<String>("mountpoint").unwrap())
+            .allow_write(matches.get_flag("allow-write"))
+            .force_write(matches.get_flag("force-write"))
+            .context(matches.get_one::<String>("context").cloned())
+            .in_cluster(matches.get_flag("in-cluster"))
+            .namespaces(
+                matches
+                    .get_many::<String>("namespace")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+            )
+            .idle_timeout(
+                matches
+                    .get_one::<u64>("idle-timeout")
+                    .map(|seconds| Duration::from_secs(*seconds)),
+            )
+            .trash_retention(
+                matches
+                    .get_one::<u64>("trash-retention")
+                    .map_or(DEFAULT_TRASH_RETENTION, |seconds| {
+                        Duration::from_secs(*seconds)
+                    }),
+            )
+            .expiring_certs_days(
+                matches
+                    .get_one::<u64>("expiring-certs-days")
+                    .copied()
+                    .unwrap_or(DEFAULT_EXPIRY_WINDOW_DAYS),
+            )
+            .timeline_minutes(
+                matches
+                    .get_one::<u64>("timeline-minutes")
+                    .copied()
+                    .unwrap_or(DEFAULT_TIMELINE_MINUTES),
+            )
+            .cache_max_bytes(
+                matches
+                    .get_one::<usize>("cache-max-bytes")
+                    .copied()
+                    .unwrap_or(DEFAULT_CACHE_MAX_BYTES),
+            )
+            .identify(matches.get_flag("identify"))
+            .sort_by_age(matches.get_flag("sort-by-age"))
+            .force_delete(matches.get_flag("force-delete"))
+            .force_conflicts(matches.get_flag("force-conflicts"))
+            .protect_gitops(matches.get_flag("protect-gitops"))
+            .argocd_instance_label(matches.get_one::<String>("argocd-instance-label"))
+            .queue_offline(matches.get_flag("queue-offline"))
+            .events_stream(matches.get_flag("events-stream"))
+            .max_changes_per_hour(matches.get_one::<usize>("max-changes-per-hour").copied())
+            .max_readahead(matches.get_one::<u32>("max-readahead").copied())
+            .max_write(matches.get_one::<u32>("max-write").copied())
+            .max_background(matches.get_one::<u16>("max-background").copied())
+            .max_read(matches.get_one::<u32>("max-read").copied())
+            .ignore_patterns(
+                matches
+                    .get_many::<String>("ignore")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+            )
+            .modes(
+                matches
+                    .get_many::<String>("mode")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+            )
+            .prefetch(matches.get_one::<String>("prefetch"))
+            .layout(matches.get_one::<String>("layout"))
+            .kubectl_log(matches.get_one::<String>("kubectl-log"))
+            .service_proxy(matches.get_flag("service-proxy"))
+            .definition_suffix(matches.get_one::<String>("definition-suffix"))
+            .definition_as_manifest(matches.get_flag("definition-as-manifest"))
+            .show_events(matches.get_one::<bool>("show-events").copied())
+            .hide_completed(matches.get_one::<bool>("hide-completed").copied())
+            .large_cluster(matches.get_flag("large-cluster"))
+            .page_size(
+                matches
+                    .get_one::<u64>("page-size")
+                    .copied()
+                    .unwrap_or(DEFAULT_PAGE_SIZE),
+            )
+            .expose_credentials(matches.get_flag("expose-credentials"))
+            .root_path(matches.get_one::<String>("root-path"))?
+            .config_file(matches.get_one::<String>("config"))?
+            .check_writable()
+    }
+
+    // Build configuration from the arguments that mount(8) passes to mount helpers
+    //   mount.k8sfs <context> <mountpoint> [-o options]
+    // Options that are not known to k8sfs (e.g. noauto, _netdev, user) are only meant for
+    // mount(8) and are ignored.
+    pub fn from_mount_helper_args(matches: &ArgMatches) -> Result<Self, String> {
+        let context = matches.get_one::<String>("context").unwrap();
+        // Using "-" as the source selects the current context of kubectl
+        let context = (context != "-").then(|| context.to_string());
+        let options = matches
+            .get_many::<String>("options")
+            .unwrap_or_default()
+            .flat_map(|options| options.split(','));
+        Config::from_mount_options(
+            matches.get_one::<String>("mountpoint").unwrap(),
+            context,
+            options,
+        )
+    }
+
+    // Build configuration for a sidecar container from environment variables (see sidecar)
+    pub fn from_env() -> Result<Self, String> {
+        let mountpoint = std::env::var(sidecar::MOUNTPOINT_VARIABLE)
+            .unwrap_or_else(|_| sidecar::DEFAULT_MOUNTPOINT.to_string());
+        let options = std::env::var(sidecar::OPTIONS_VARIABLE).unwrap_or_default();
+        let config = Config::from_mount_options(&mountpoint, None, options.split(','))?;
+        let in_cluster = config
+            .context
+            .as_ref()
+            .is_none_or(|context| context == in_cluster::CONTEXT_NAME);
+        Ok(config.in_cluster(in_cluster))
+    }
+
+    // Build configuration from mount options (e.g. "rw", "namespaces=ci")
+    fn from_mount_options<'a>(
+        mountpoint: &str,
+        context: Option<String>,
+        options: impl Iterator<Item = &'a str>,
+    ) -> Result<Self, String> {
+        let mut config = Config::new(mountpoint).context(context);
+        let mut config_file = None;
+        let mut root_path = None;
+        for option in options.filter(|option| !option.is_empty()) {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            match key {
+                "rw" => config.allow_write = true,
+                "ro" => config.allow_write = false,
+                "force_write" => config.force_write = true,
+                "context" => config.context = Some(value.to_string()),
+                "in_cluster" => config = config.in_cluster(true),
+                "namespaces" => config.namespaces.extend(
+                    value
+                        .split(MOUNT_OPTION_LIST_SEPARATOR)
+                        .filter(|namespace| !namespace.is_empty())
+                        .map(String::from),
+                ),
+                "ignore" => config.ignore_patterns.extend(
+                    value
+                        .split(MOUNT_OPTION_LIST_SEPARATOR)
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(String::from),
+                ),
+                "idle_timeout" => match value.parse::<u64>() {
+                    Ok(seconds) => config.idle_timeout = Some(Duration::from_secs(seconds)),
+                    Err(_) => log::error!("Invalid idle_timeout {:?}, ignoring it", value),
+                },
+                "identify" => config.identify = true,
+                "sort_by_age" => config.sort_by_age = true,
+                "force_delete" => config.force_delete = true,
+                "force_conflicts" => config.force_conflicts = true,
+                "protect_gitops" => config.protect_gitops = true,
+                "argocd_instance_label" => config.argocd_instance_label = Some(value.to_string()),
+                "queue_offline" => config.queue_offline = true,
+                "events_stream" => config.events_stream = true,
+                "expose_credentials" => config.expose_credentials = true,
+                "service_proxy" => config.service_proxy = true,
+                "kubectl_log" => config.kubectl_log = Some(PathBuf::from(value)),
+                "modes" => {
+                    for mapping in value
+                        .split(MOUNT_OPTION_LIST_SEPARATOR)
+                        .filter(|mapping| !mapping.is_empty())
+                    {
+                        if let Err(error) = config.modes.add(mapping) {
+                            log::error!("{}, ignoring it", error);
+                        }
+                    }
+                }
+                "layout" => match Layout::from_name(value) {
+                    Some(layout) => config.layout = layout,
+                    None => log::error!("Invalid layout {:?}, ignoring it", value),
+                },
+                "prefetch" => match PrefetchPolicy::from_name(value) {
+                    Some(prefetch) => config.prefetch = prefetch,
+                    None => log::error!("Invalid prefetch {:?}, ignoring it", value),
+                },
+                "definition_suffix" => config.definition_suffix = value.to_string(),
+                "definition_as_manifest" => config.definition_as_manifest = true,
+                "show_events" => match value.parse::<bool>() {
+                    Ok(show_events) => config.show_events = show_events,
+                    Err(_) => log::error!("Invalid show_events {:?}, ignoring it", value),
+                },
+                "large_cluster" => config.large_cluster = true,
+                "page_size" => match value.parse::<u64>() {
+                    Ok(page_size) => config.page_size = page_size,
+                    Err(_) => log::error!("Invalid page_size {:?}, ignoring it", value),
+                },
+                "hide_completed" => match value.parse::<bool>() {
+                    Ok(hide_completed) => config.hide_completed = hide_completed,
+                    Err(_) => log::error!("Invalid hide_completed {:?}, ignoring it", value),
+                },
+                "config" => config_file = Some(value.to_string()),
+                "root_path" => root_path = Some(value.to_string()),
+                "max_changes_per_hour" => match value.parse::<usize>() {
+                    Ok(limit) => config.max_changes_per_hour = Some(limit),
+                    Err(_) => log::error!("Invalid max_changes_per_hour {:?}, ignoring it", value),
+                },
+                "max_readahead" => match value.parse::<u32>() {
+                    Ok(bytes) => config.max_readahead = bytes,
+                    Err(_) => log::error!("Invalid max_readahead {:?}, ignoring it", value),
+                },
+                "max_write" => match value.parse::<u32>() {
+                    Ok(bytes) => config.max_write = Some(bytes),
+                    Err(_) => log::error!("Invalid max_write {:?}, ignoring it", value),
+                },
+                "max_background" => match value.parse::<u16>() {
+                    Ok(count) => config.max_background = count,
+                    Err(_) => log::error!("Invalid max_background {:?}, ignoring it", value),
+                },
+                "max_read" => match value.parse::<u32>() {
+                    Ok(bytes) => config.max_read = Some(bytes),
+                    Err(_) => log::error!("Invalid max_read {:?}, ignoring it", value),
+                },
+                "trash_retention" => match value.parse::<u64>() {
+                    Ok(seconds) => config.trash_retention = Duration::from_secs(seconds),
+                    Err(_) => log::error!("Invalid trash_retention {:?}, ignoring it", value),
+                },
+                "expiring_certs_days" => match value.parse::<u64>() {
+                    Ok(days) => config.expiring_certs_days = days,
+                    Err(_) => log::error!("Invalid expiring_certs_days {:?}, ignoring it", value),
+                },
+                "timeline_minutes" => match value.parse::<u64>() {
+                    Ok(minutes) => config.timeline_minutes = minutes,
+                    Err(_) => log::error!("Invalid timeline_minutes {:?}, ignoring it", value),
+                },
+                "cache_max_bytes" => match value.parse::<usize>() {
+                    Ok(bytes) => config.cache_max_bytes = bytes,
+                    Err(_) => log::error!("Invalid cache_max_bytes {:?}, ignoring it", value),
+                },
+                _ => log::debug!("Ignoring mount option {}", option),
+            }
+        }
+
+        config
+            .root_path(root_path.as_ref())?
+            .config_file(config_file.as_ref())?
+            .check_writable()
+    }
+
+    // Return true if the namespace should be shown in the filesystem
+    pub fn is_namespace_visible(&self, namespace: &str) -> bool {
+        self.namespaces.is_empty() || self.namespaces.iter().any(|n| n == namespace)
+    }
+}
@@ -0,0 +1,261 @@
+use crate::reports::{array, field};
+use serde_json::Value;
+
+// Annotation that kubectl uses to pick the container of "kubectl logs" / "kubectl exec"
+const DEFAULT_CONTAINER_ANNOTATION: &str = "kubectl.kubernetes.io/default-container";
+
+// Names of sidecars that service meshes and other tools commonly inject into pods
+// These are skipped when guessing the default container of a pod
+const KNOWN_SIDECARS: [&str; 8] = [
+    "istio-proxy",
+    "linkerd-proxy",
+    "envoy",
+    "envoy-sidecar",
+    "vault-agent",
+    "cloud-sql-proxy",
+    "cloudsql-proxy",
+    "datadog-agent",
+];
+
+// What a container is used for in its pod
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    // Runs to completion before the other containers are started
+    Init,
+    // Runs next to the application (native sidecars or well known injected containers)
+    Sidecar,
+    // The application itself
+    App,
+}
+
+impl Role {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Init => "init",
+            Role::Sidecar => "sidecar",
+            Role::App => "app",
+        }
+    }
+}
+
+// Return the role of a container of a pod
+pub fn role(pod: &Value, container: &str) -> Role {
+    // Native sidecars are init containers that are restarted for the whole life of the pod
+    if let Some(init_container) = array(pod, "/spec/initContainers")
+        .iter()
+        .find(|init_container| field(init_container, "/name") == container)
+    {
+        if field(init_container, "/restartPolicy") == "Always" {
+            return Role::Sidecar;
+        }
+        return Role::Init;
+    }
+    if KNOWN_SIDECARS.contains(&container) {
+        Role::Sidecar
+    } else {
+        Role::App
+    }
+}
+
+// Return the container that pod level files (e.g. logs) should use
+// The annotation is honored like kubectl does. Without it, the first container that is not
+// a sidecar is used, so that injected proxies do not shadow the application.
+pub fn default_container(pod: &Value) -> Option<&str> {
+    let containers = array(pod, "/spec/containers");
+    let annotated = pod
+        .pointer("/metadata/annotations")
+        .map(|annotations| {
+            field(
+                annotations,
+                &format!("/{}", escape(DEFAULT_CONTAINER_ANNOTATION)),
+            )
+        })
+        .unwrap_or("");
+    if containers
+        .iter()
+        .any(|container| field(container, "/name") == annotated)
+    {
+        return Some(annotated);
+    }
+    if !annotated.is_empty() {
+        log::debug!(
+            "Default container {} of pod {} does not exist",
+            annotated,
+            field(pod, "/metadata/name")
+        );
+    }
+
+    containers
+        .iter()
+        .map(|container| field(container, "/name"))
+        .find(|container| role(pod, container) == Role::App)
+        .or_else(|| {
+            containers
+                .first()
+                .map(|container| field(container, "/name"))
+        })
+}
+
+// Shell script that opens a shell in a container, or runs the command that is passed to it
+// Names are quoted, because context names in a kubeconfig can contain any character.
+pub fn exec_script(context: &str, namespace: &str, pod: &str, container: &str) -> String {
+    format!(
+        "#!/bin/sh\n\
+         [ $# -eq 0 ] && set -- sh\n\
+         exec kubectl --context {} --namespace {} exec -it {} -c {} -- \"$@\"\n",
+        shell_quote(context),
+        shell_quote(namespace),
+        shell_quote(pod),
+        shell_quote(container)
+    )
+}
+
+// Quote a value so that the shell passes it on as a single argument
+//   it's -> 'it'\''s'
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// Condensed, readable summary of the spec of a container
+//   image: nginx:1.25
+//   ports: http 80/TCP
+//   liveness: httpGet HTTP :8080/healthz (delay 10s, period 10s, timeout 1s, failures 3)
+//   securityContext: runAsNonRoot=true capabilities.drop=ALL
+// Fields that are not set are left out.
+pub fn spec_summary(container: &Value) -> String {
+    let mut lines = vec![format!("image: {}", field(container, "/image"))];
+    for (name, pointer) in [("command", "/command"), ("args", "/args")] {
+        let values: Vec<String> = array(container, pointer).iter().map(text).collect();
+        if !values.is_empty() {
+            lines.push(format!("{}: {}", name, values.join(" ")));
+        }
+    }
+    let ports: Vec<String> = array(container, "/ports")
+        .iter()
+        .map(|port| {
+            let number = text(port.pointer("/containerPort").unwrap_or(&Value::Null));
+            let protocol = match field(port, "/protocol") {
+                "" => "TCP",
+                protocol => protocol,
+            };
+            match field(port, "/name") {
+                "" => format!("{}/{}", number, protocol),
+                name => format!("{} {}/{}", name, number, protocol),
+            }
+        })
+        .collect();
+    if !ports.is_empty() {
+        lines.push(format!("ports: {}", ports.join(", ")));
+    }
+    let resources: Vec<String> = ["requests", "limits"]
+        .iter()
+        .filter_map(|kind| {
+            let quantities = container
+                .pointer(&format!("/resources/{}", kind))
+                .and_then(Value::as_object)
+                .filter(|quantities| !quantities.is_empty())?;
+            let quantities: Vec<String> = quantities
+                .iter()
+                .map(|(resource, quantity)| format!("{}={}", resource, text(quantity)))
+                .collect();
+            Some(format!("{} {}", kind, quantities.join(" ")))
+        })
+        .collect();
+    if !resources.is_empty() {
+        lines.push(format!("resources: {}", resources.join(", ")));
+    }
+    for (name, pointer) in [
+        ("startup", "/startupProbe"),
+        ("liveness", "/livenessProbe"),
+        ("readiness", "/readinessProbe"),
+    ] {
+        if let Some(probe) = container.pointer(pointer) {
+            lines.push(format!("{}: {}", name, probe_summary(probe)));
+        }
+    }
+    if let Some(security_context) = container.pointer("/securityContext") {
+        let mut settings = Vec::new();
+        flatten("", security_context, &mut settings);
+        if !settings.is_empty() {
+            lines.push(format!("securityContext: {}", settings.join(" ")));
+        }
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Describe how a probe checks a container and how often
+fn probe_summary(probe: &Value) -> String {
+    let port = |pointer: &str| text(probe.pointer(pointer).unwrap_or(&Value::Null));
+    let check = if let Some(http) = probe.pointer("/httpGet") {
+        let scheme = match field(http, "/scheme") {
+            "" => "HTTP",
+            scheme => scheme,
+        };
+        format!(
+            "httpGet {} :{}{}",
+            scheme,
+            port("/httpGet/port"),
+            field(http, "/path")
+        )
+    } else if probe.pointer("/tcpSocket").is_some() {
+        format!("tcpSocket :{}", port("/tcpSocket/port"))
+    } else if probe.pointer("/grpc").is_some() {
+        format!("grpc :{}", port("/grpc/port"))
+    } else {
+        let command: Vec<String> = array(probe, "/exec/command").iter().map(text).collect();
+        format!("exec {}", command.join(" "))
+    };
+    let timings: Vec<String> = [
+        ("delay", "/initialDelaySeconds", "s"),
+        ("period", "/periodSeconds", "s"),
+        ("timeout", "/timeoutSeconds", "s"),
+        ("failures", "/failureThreshold", ""),
+    ]
+    .iter()
+    .filter_map(|(name, pointer, unit)| {
+        probe
+            .pointer(pointer)
+            .map(|value| format!("{} {}{}", name, text(value), unit))
+    })
+    .collect();
+    if timings.is_empty() {
+        check
+    } else {
+        format!("{} ({})", check, timings.join(", "))
+    }
+}
+
+// Flatten nested settings into key=value pairs (e.g. capabilities.drop=ALL)
+fn flatten(prefix: &str, value: &Value, settings: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, settings);
+            }
+        }
+        Value::Array(values) => {
+            let values: Vec<String> = values.iter().map(text).collect();
+            settings.push(format!("{}={}", prefix, values.join(",")));
+        }
+        value => settings.push(format!("{}={}", prefix, text(value))),
+    }
+}
+
+// Strings without quotes, everything else as JSON
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+// Escape a key so that it can be used in a JSON pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
@@ -0,0 +1,98 @@
+// Cost estimate of the resources that the pods of a namespace request
+// The prices are defined in the config file:
+//   [prices]
+//   cpu_hour = 0.0316         # per vCPU-hour
+//   memory_gib_hour = 0.0042  # per GiB-hour
+//   currency = "USD"
+// Without prices, namespaces do not have a cost-estimate file.
+
+use crate::kubectl::{self, K8sError};
+use crate::reports;
+
+// Name of the file inside namespace directories that contains the estimate
+pub const COST_ESTIMATE_FILE_NAME: &str = "cost-estimate";
+
+// Hours that are used for the monthly cost (365 * 24 / 12)
+const HOURS_PER_MONTH: f64 = 730.0;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceTable {
+    pub cpu_hour: f64,
+    pub memory_gib_hour: f64,
+    pub currency: String,
+}
+
+impl PriceTable {
+    // Parse the [prices] table of the config file
+    pub fn from_toml(entry: &toml::Value) -> Result<PriceTable, String> {
+        // Prices can be written as integers as well (e.g. cpu_hour = 1)
+        let price = |key: &str| {
+            let value = entry.get(key);
+            value
+                .and_then(toml::Value::as_float)
+                .or_else(|| {
+                    value
+                        .and_then(toml::Value::as_integer)
+                        .map(|price| price as f64)
+                })
+                .filter(|price| *price >= 0.0)
+                .ok_or_else(|| format!("Prices are missing a valid \"{}\"", key))
+        };
+        Ok(PriceTable {
+            cpu_hour: price("cpu_hour")?,
+            memory_gib_hour: price("memory_gib_hour")?,
+            currency: entry
+                .get("currency")
+                .and_then(toml::Value::as_str)
+                .unwrap_or("")
+                .to_string(),
+        })
+    }
+
+    // Sum up the requests of the pods of a namespace that are not completed and estimate
+    // what they cost, one "key: value" line per number
+    //   pods: 12
+    //   cpu.requested: 4.500
+    //   memory.requested.gib: 8.000
+    //   cost.hourly: 0.1761 USD
+    //   cost.monthly: 128.55 USD
+    pub fn estimate(&self, context: &str, namespace: &str) -> Result<String, K8sError> {
+        let pods = kubectl::get_items(context, Some(namespace), "pods", &[])?;
+        let pods: Vec<_> = pods
+            .iter()
+            .filter(|pod| !reports::is_completed(pod))
+            .collect();
+        let cpu: f64 = pods
+            .iter()
+            .map(|pod| reports::pod_request(pod, "cpu"))
+            .sum();
+        let memory: f64 = pods
+            .iter()
+            .map(|pod| reports::pod_request(pod, "memory"))
+            .sum::<f64>()
+            / GIB;
+        let hourly = cpu * self.cpu_hour + memory * self.memory_gib_hour;
+        let currency = match self.currency.is_empty() {
+            true => String::new(),
+            false => format!(" {}", self.currency),
+        };
+        Ok(format!(
+            "pods: {}\ncpu.requested: {:.3}\nmemory.requested.gib: {:.3}\n\
+             cost.cpu.hourly: {:.4}{}\ncost.memory.hourly: {:.4}{}\n\
+             cost.hourly: {:.4}{}\ncost.monthly: {:.2}{}\n",
+            pods.len(),
+            cpu,
+            memory,
+            cpu * self.cpu_hour,
+            currency,
+            memory * self.memory_gib_hour,
+            currency,
+            hourly,
+            currency,
+            hourly * HOURS_PER_MONTH,
+            currency
+        ))
+    }
+}
@@ -0,0 +1,360 @@
+use crate::activity::Activity;
+use crate::cache::{ContentCache, SharedCache, CACHE_TTL};
+use crate::config::Config;
+use crate::filesystem::K8sFS;
+use crate::kubectl;
+use crate::watcher::Watcher;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// How often the daemon checks for mounts that were unmounted or reached their idle timeout
+const MOUNT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// How long the daemon waits for the request of a client before it closes the connection
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Parses the arguments of "k8sfs mount" into the configuration of a mount
+pub type ParseMountArgs = fn(&[String]) -> Result<Config, String>;
+
+// Default path of the control socket of the daemon
+//   $XDG_RUNTIME_DIR/k8sfs.sock or /tmp/k8sfs-<uid>.sock
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => Path::new(&runtime_dir).join("k8sfs.sock"),
+        None => {
+            // SAFETY: getuid() always succeeds and has no side effects
+            let uid = unsafe { libc::getuid() };
+            PathBuf::from(format!("/tmp/k8sfs-{}.sock", uid))
+        }
+    }
+}
+
+// A filesystem that is mounted by the daemon
+struct Mount {
+    context: String,
+    idle_timeout: Option<Duration>,
+    activity: Activity,
+    // The filesystem is unmounted when the session is dropped
+    _session: fuser::BackgroundSession,
+}
+
+// Mounts that are managed by the daemon
+// Mounts of the same context share one watcher and one content cache, which are dropped
+// together with the last mount of the context.
+#[derive(Default)]
+struct Daemon {
+    // Key: mount point
+    mounts: BTreeMap<PathBuf, Mount>,
+    // Mount points that are being mounted right now and their context
+    mounting: HashMap<PathBuf, String>,
+    // Key: context
+    watchers: HashMap<String, Watcher>,
+    // Key: context
+    caches: HashMap<String, SharedCache>,
+}
+
+impl Daemon {
+    // Reserve a mount point and return the watcher and the cache of its context
+    fn reserve(
+        &mut self,
+        mountpoint: &Path,
+        context: &str,
+        cache_max_bytes: usize,
+    ) -> Result<(Watcher, SharedCache), String> {
+        if self.mounts.contains_key(mountpoint) || self.mounting.contains_key(mountpoint) {
+            return Err(format!("{} is already mounted", mountpoint.display()));
+        }
+        self.mounting
+            .insert(mountpoint.to_path_buf(), context.to_string());
+        let watcher = self
+            .watchers
+            .entry(context.to_string())
+            .or_insert_with(|| Watcher::start(context))
+            .clone();
+        // The first mount of a context decides how large the cache is
+        let cache = self
+            .caches
+            .entry(context.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(ContentCache::new(cache_max_bytes, CACHE_TTL))))
+            .clone();
+        Ok((watcher, cache))
+    }
+
+    // Stop the watcher and drop the cache of a context if no mount uses them anymore
+    fn release_context(&mut self, context: &str) {
+        let in_use = self
+            .mounts
+            .values()
+            .map(|mount| &mount.context)
+            .chain(self.mounting.values())
+            .any(|used| used == context);
+        if in_use {
+            return;
+        }
+        log::info!("No mount of {} is left, stopping its watcher", context);
+        if let Some(watcher) = self.watchers.remove(context) {
+            watcher.stop();
+        }
+        self.caches.remove(context);
+    }
+
+    fn umount(&mut self, mountpoint: &Path) -> Result<String, String> {
+        let mountpoint = mountpoint
+            .canonicalize()
+            .unwrap_or_else(|_| mountpoint.to_path_buf());
+        match self.mounts.remove(&mountpoint) {
+            Some(mount) => {
+                log::info!("Unmounted {}", mountpoint.display());
+                self.release_context(&mount.context);
+                Ok(format!("Unmounted {}\n", mountpoint.display()))
+            }
+            None => Err(format!("{} is not mounted", mountpoint.display())),
+        }
+    }
+
+    // One line per mount: <mount point> <context> <seconds since last use>
+    fn status(&self) -> String {
+        self.mounts
+            .iter()
+            .map(|(mountpoint, mount)| {
+                format!(
+                    "{} {} idle={}s\n",
+                    mountpoint.display(),
+                    mount.context,
+                    mount.activity.idle_for().as_secs()
+                )
+            })
+            .collect()
+    }
+
+    // Forget mounts that were unmounted by somebody else and unmount idle ones
+    fn check_mounts(&mut self) {
+        let mut released = Vec::new();
+        self.mounts.retain(|mountpoint, mount| {
+            if mount.activity.is_destroyed() {
+                log::info!("{} was unmounted", mountpoint.display());
+                released.push(mount.context.clone());
+                return false;
+            }
+            if let Some(idle_timeout) = mount.idle_timeout {
+                if mount.activity.idle_for() >= idle_timeout {
+                    log::info!(
+                        "{} was not used for {} seconds, unmounting it",
+                        mountpoint.display(),
+                        idle_timeout.as_secs()
+                    );
+                    released.push(mount.context.clone());
+                    return false;
+                }
+            }
+            true
+        });
+        for context in released {
+            self.release_context(&context);
+        }
+    }
+}
+
+// Mount a filesystem in the daemon
+// The daemon is not locked while the filesystem is created and mounted, so a slow mount does not
+// hold up requests of other clients.
+fn mount(daemon: &Mutex<Daemon>, mut config: Config) -> Result<String, String> {
+    // These change how kubectl is called by the whole process, so they can not differ per mount
+    if config.in_cluster {
+        return Err(String::from("--in-cluster is not supported by the daemon"));
+    }
+    if config.kubectl_log.is_some() {
+        return Err(String::from(
+            "--kubectl-log is not supported by the daemon, kubectl calls of all mounts are logged to daemon.log",
+        ));
+    }
+    if config.identify {
+        return Err(String::from("--identify is not supported by the daemon"));
+    }
+    let mountpoint = Path::new(&config.mountpoint)
+        .canonicalize()
+        .map_err(|error| format!("Invalid mount point {}: {}", config.mountpoint, error))?;
+    config.mountpoint = mountpoint.to_string_lossy().to_string();
+    let context = config
+        .context
+        .clone()
+        .unwrap_or_else(kubectl::current_context);
+    let (watcher, cache) =
+        daemon
+            .lock()
+            .unwrap()
+            .reserve(&mountpoint, &context, config.cache_max_bytes)?;
+
+    let idle_timeout = config.idle_timeout;
+    let fs = K8sFS::new(config).with_watcher(watcher).with_cache(cache);
+    let activity = fs.activity();
+    let mount_options = fs.mount_options();
+    let changes = fs.change_notifier();
+    let session = fuser::spawn_mount2(fs, &mountpoint, &mount_options);
+
+    let mut daemon = daemon.lock().unwrap();
+    daemon.mounting.remove(&mountpoint);
+    let session = match session {
+        Ok(session) => session,
+        Err(error) => {
+            daemon.release_context(&context);
+            return Err(format!(
+                "Could not mount {}: {}",
+                mountpoint.display(),
+                error
+            ));
+        }
+    };
+    changes.set_notifier(session.notifier());
+    log::info!("Mounted {} at {}", context, mountpoint.display());
+    daemon.mounts.insert(
+        mountpoint.clone(),
+        Mount {
+            context,
+            idle_timeout,
+            activity,
+            _session: session,
+        },
+    );
+
+    Ok(format!("Mounted {}\n", mountpoint.display()))
+}
+
+// Handle a request of a client
+fn handle(
+    daemon: &Mutex<Daemon>,
+    request: &Value,
+    parse_mount_args: ParseMountArgs,
+) -> Result<String, String> {
+    // Relative paths are relative to the working directory of the client
+    let cwd = Path::new(request.get("cwd").and_then(Value::as_str).unwrap_or("/"));
+    match request.get("command").and_then(Value::as_str) {
+        Some("mount") => {
+            let args: Vec<String> = request
+                .get("args")
+                .and_then(Value::as_array)
+                .map(|args| {
+                    args.iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mut config = parse_mount_args(&args)?;
+            config.mountpoint = cwd.join(&config.mountpoint).to_string_lossy().to_string();
+            mount(daemon, config)
+        }
+        Some("umount") => {
+            let mountpoint = request
+                .get("mountpoint")
+                .and_then(Value::as_str)
+                .ok_or_else(|| String::from("Missing mount point"))?;
+            daemon.lock().unwrap().umount(&cwd.join(mountpoint))
+        }
+        Some("status") => Ok(daemon.lock().unwrap().status()),
+        command => Err(format!("Unknown command {:?}", command)),
+    }
+}
+
+// Run the daemon until it is killed
+// Requests are read from the control socket, one JSON object per connection.
+// Every connection is handled on its own thread, so a client that does not send its request
+// does not hold up others.
+pub fn run(socket_path: &Path, parse_mount_args: ParseMountArgs) -> io::Result<()> {
+    // A socket that nobody listens on is left over from a daemon that did not exit cleanly
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("A daemon is already listening on {}", socket_path.display()),
+            ));
+        }
+        std::fs::remove_file(socket_path)?;
+    }
+    let listener = UnixListener::bind(socket_path)?;
+    log::info!("Listening on {}", socket_path.display());
+
+    let daemon = Arc::new(Mutex::new(Daemon::default()));
+    let checked_daemon = daemon.clone();
+    thread::spawn(move || loop {
+        thread::sleep(MOUNT_CHECK_INTERVAL);
+        checked_daemon.lock().unwrap().check_mounts();
+    });
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                log::error!("Could not accept connection: {}", error);
+                continue;
+            }
+        };
+        let daemon = daemon.clone();
+        thread::spawn(move || handle_connection(stream, &daemon, parse_mount_args));
+    }
+
+    Ok(())
+}
+
+// Read the request of a client and send the response
+fn handle_connection(
+    mut stream: UnixStream,
+    daemon: &Mutex<Daemon>,
+    parse_mount_args: ParseMountArgs,
+) {
+    let mut line = String::new();
+    let read = stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .and_then(|()| BufReader::new(&stream).read_line(&mut line));
+    if let Err(error) = read {
+        log::error!("Could not read request: {}", error);
+        return;
+    }
+    let result = serde_json::from_str::<Value>(&line)
+        .map_err(|error| format!("Invalid request: {}", error))
+        .and_then(|request| handle(daemon, &request, parse_mount_args));
+    let response = match result {
+        Ok(output) => json!({"success": true, "output": output}),
+        Err(error) => json!({"success": false, "output": error}),
+    };
+    if let Err(error) = writeln!(stream, "{}", response) {
+        log::error!("Could not send response: {}", error);
+    }
+}
+
+// Send a request to the daemon and return its output
+pub fn request(socket_path: &Path, mut request: Value) -> Result<String, String> {
+    let mut stream = UnixStream::connect(socket_path).map_err(|error| {
+        format!(
+            "Could not connect to the daemon at {} (is \"k8sfs daemon start\" running?): {}",
+            socket_path.display(),
+            error
+        )
+    })?;
+    if let Ok(cwd) = std::env::current_dir() {
+        request["cwd"] = json!(cwd);
+    }
+    writeln!(stream, "{}", request).map_err(|error| error.to_string())?;
+
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|error| error.to_string())?;
+    let response: Value = serde_json::from_str(&line)
+        .map_err(|error| format!("Invalid response from the daemon: {}", error))?;
+    let output = response
+        .get("output")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    if response.get("success").and_then(Value::as_bool) == Some(true) {
+        Ok(output)
+    } else {
+        Err(output)
+    }
+}
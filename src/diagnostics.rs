@@ -0,0 +1,170 @@
+use crate::k8s_resource::{format_timestamp, ResourceType};
+use crate::usage;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+// Name of the file inside resource directories that contains the last failed kubectl call
+pub const LAST_ERROR_FILE_NAME: &str = ".last-error";
+// Size at which the log file is rotated, the previous log is kept as <file>.1
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+// File that the stderr of all kubectl calls is appended to, None until a mount sets it
+static LOG_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// Last failed kubectl call per resource
+// Key: (kind, namespace, name), the namespace is empty for resources that are not namespaced
+static LAST_ERRORS: Mutex<BTreeMap<(String, String, String), String>> = Mutex::new(BTreeMap::new());
+
+// Default location of the log file of a mount (or the daemon)
+//   $XDG_STATE_HOME/k8sfs/<mount point with "/" replaced by "_">.log
+//   ~/.local/state/k8sfs/...
+pub fn default_log_file(name: &str) -> Option<PathBuf> {
+    let state_dir = match std::env::var_os("XDG_STATE_HOME") {
+        Some(state_dir) => PathBuf::from(state_dir),
+        None => Path::new(&std::env::var_os("HOME")?).join(".local/state"),
+    };
+    let name = name.trim_matches('/').replace('/', "_");
+    Some(state_dir.join("k8sfs").join(format!("{}.log", name)))
+}
+
+pub fn set_log_file(path: PathBuf) {
+    if let Some(directory) = path.parent() {
+        if let Err(error) = fs::create_dir_all(directory) {
+            log::error!("Could not create {}: {}", directory.display(), error);
+        }
+    }
+    log::info!("Logging the errors of kubectl calls to {}", path.display());
+    *LOG_FILE.lock().unwrap() = Some(path);
+}
+
+// Record what a finished kubectl call printed to stderr
+// Failed calls replace the last error of the resource that they were about.
+pub fn record(command: &Command, output: &Output) {
+    if output.stderr.is_empty() {
+        return;
+    }
+    let args: Vec<String> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let entry = format!(
+        "{} kubectl {} ({})\n{}\n",
+        format_timestamp(SystemTime::now()),
+        args.join(" "),
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim_end()
+    );
+    append_to_log(&entry);
+    if output.status.success() {
+        return;
+    }
+    if let Some(key) = resource_of(&args) {
+        LAST_ERRORS.lock().unwrap().insert(key, entry);
+    }
+}
+
+// Return the last failed kubectl call of a resource
+pub fn last_error(resource_type: ResourceType, namespace: Option<&str>, name: &str) -> String {
+    let key = (
+        resource_type.kind().to_string(),
+        namespace.unwrap_or_default().to_string(),
+        name.to_string(),
+    );
+    LAST_ERRORS
+        .lock()
+        .unwrap()
+        .get(&key)
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Record a failed apply as the last error of an object of the manifest
+// kubectl applies a whole manifest at once, so the error can not be attributed to an object
+// from its arguments.
+pub fn record_apply_error(kind: &str, namespace: &str, name: &str, report: &str) {
+    let Some(resource_type) = ResourceType::from_kind(kind) else {
+        return;
+    };
+    let namespace = match resource_type.is_namespaced() {
+        true => namespace.to_string(),
+        false => String::new(),
+    };
+    let entry = format!(
+        "{} kubectl apply --server-side\n{}\n",
+        format_timestamp(SystemTime::now()),
+        report.trim_end()
+    );
+    LAST_ERRORS.lock().unwrap().insert(
+        (
+            resource_type.kind().to_string(),
+            namespace,
+            name.to_string(),
+        ),
+        entry,
+    );
+}
+
+fn append_to_log(entry: &str) {
+    let log_file = LOG_FILE.lock().unwrap();
+    let Some(path) = log_file.as_ref() else {
+        return;
+    };
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= MAX_LOG_SIZE) {
+        let mut rotated = path.clone().into_os_string();
+        rotated.push(".1");
+        if let Err(error) = fs::rename(path, rotated) {
+            log::debug!("Could not rotate {}: {}", path.display(), error);
+        }
+    }
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(entry.as_bytes()));
+    if let Err(error) = written {
+        log::debug!("Could not write to {}: {}", path.display(), error);
+    }
+}
+
+// Return the resource that a kubectl call was about as (kind, namespace, name)
+// Calls that are not about a single resource (e.g. listing the services of a namespace) are
+// attributed to their namespace.
+fn resource_of(args: &[String]) -> Option<(String, String, String)> {
+    let (positional, flags) = usage::split_args(args);
+    let namespace = flags
+        .iter()
+        .find(|(flag, _)| *flag == "--namespace" || *flag == "-n")
+        .map(|(_, namespace)| namespace.to_string());
+    let raw_path = flags
+        .iter()
+        .find(|(flag, _)| *flag == "--raw")
+        .map(|(_, path)| *path);
+
+    let (resource_type, name) = match (positional.as_slice(), raw_path) {
+        // /api/v1/nodes/<node>/... or /api/v1/namespaces/<namespace>/...
+        (_, Some(raw_path)) => {
+            let mut segments = raw_path.split('/').skip_while(|segment| *segment != "v1");
+            match (segments.nth(1), segments.next()) {
+                (Some(resource), Some(name)) => {
+                    (ResourceType::from_kind(resource)?, name.to_string())
+                }
+                _ => return None,
+            }
+        }
+        (["logs" | "exec", pod, ..], None) => (ResourceType::Pod, pod.to_string()),
+        ([_, resource, name, ..], None) if ResourceType::from_kind(resource).is_some() => {
+            (ResourceType::from_kind(resource)?, name.to_string())
+        }
+        _ => (ResourceType::Namespace, namespace.clone()?),
+    };
+    let namespace = match resource_type.is_namespaced() {
+        true => namespace?,
+        false => String::new(),
+    };
+    Some((resource_type.kind().to_string(), namespace, name))
+}
@@ -0,0 +1,155 @@
+// Plan of what draining a node would do, without draining it
+// Pods are evicted like "kubectl drain --ignore-daemonsets" evicts them: DaemonSet and static
+// pods stay, pods without a controller are not recreated and PodDisruptionBudgets can block
+// evictions.
+
+use crate::kubectl::{self, K8sError};
+use crate::reports::{self, array, field};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Name of the file inside node directories that contains the plan
+pub const DRAIN_PLAN_FILE_NAME: &str = "drain-plan";
+
+// Annotation of the mirror pods of static pods, which can not be evicted
+const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+
+// List the pods of a node with what draining the node would do with them
+//   evict      default/web-5d9c7-x2x4q        ReplicaSet/web-5d9c7
+//   blocked    default/db-0                   PodDisruptionBudget db allows 0 more disruptions
+//   unmanaged  default/debug                  no controller, not recreated
+//   skip       kube-system/kube-proxy-x2x4q   DaemonSet/kube-proxy
+pub fn plan(context: &str, node: &str) -> Result<String, K8sError> {
+    let node_selector = format!("spec.nodeName={}", node);
+    let pods = kubectl::get_items(
+        context,
+        None,
+        "pods",
+        &["--field-selector", node_selector.as_str()],
+    )?;
+    let budgets = kubectl::get_items(context, None, "poddisruptionbudgets", &[])?;
+    // Every eviction uses up one of the disruptions that a budget allows
+    let mut allowed: HashMap<(&str, &str), u64> = budgets
+        .iter()
+        .map(|budget| {
+            (
+                (
+                    field(budget, "/metadata/namespace"),
+                    field(budget, "/metadata/name"),
+                ),
+                budget
+                    .pointer("/status/disruptionsAllowed")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0),
+            )
+        })
+        .collect();
+
+    let mut report = format!("{:<11}{:<50}{}\n", "ACTION", "POD", "REASON");
+    for pod in &pods {
+        let namespace = field(pod, "/metadata/namespace");
+        let controller = array(pod, "/metadata/ownerReferences")
+            .iter()
+            .find(|owner| owner.pointer("/controller") == Some(&Value::Bool(true)));
+        let (action, mut reason) = if is_mirror_pod(pod) {
+            ("skip", "static pod".to_string())
+        } else if let Some(controller) =
+            controller.filter(|controller| field(controller, "/kind") == "DaemonSet")
+        {
+            ("skip", format!("DaemonSet/{}", field(controller, "/name")))
+        } else if reports::is_completed(pod) {
+            ("evict", "completed".to_string())
+        } else if let Some(budget) = budgets.iter().find(|budget| {
+            field(budget, "/metadata/namespace") == namespace && selects(budget, pod)
+        }) {
+            let budget_name = field(budget, "/metadata/name");
+            let remaining = allowed.entry((namespace, budget_name)).or_default();
+            if *remaining == 0 {
+                (
+                    "blocked",
+                    format!(
+                        "PodDisruptionBudget {} allows 0 more disruptions",
+                        budget_name
+                    ),
+                )
+            } else {
+                *remaining -= 1;
+                ("evict", format!("PodDisruptionBudget {}", budget_name))
+            }
+        } else {
+            match controller {
+                Some(controller) => (
+                    "evict",
+                    format!(
+                        "{}/{}",
+                        field(controller, "/kind"),
+                        field(controller, "/name")
+                    ),
+                ),
+                None => ("unmanaged", "no controller, not recreated".to_string()),
+            }
+        };
+        if action != "skip" && has_empty_dir(pod) {
+            reason.push_str(", emptyDir data is lost");
+        }
+        report.push_str(&format!(
+            "{:<11}{:<50}{}\n",
+            action,
+            format!("{}/{}", namespace, field(pod, "/metadata/name")),
+            reason
+        ));
+    }
+    Ok(report)
+}
+
+fn is_mirror_pod(pod: &Value) -> bool {
+    pod.pointer("/metadata/annotations")
+        .and_then(|annotations| annotations.get(MIRROR_POD_ANNOTATION))
+        .is_some()
+}
+
+fn has_empty_dir(pod: &Value) -> bool {
+    array(pod, "/spec/volumes")
+        .iter()
+        .any(|volume| volume.get("emptyDir").is_some())
+}
+
+// Return true if the label selector of a PodDisruptionBudget selects a pod
+// An empty selector selects all pods of the namespace, a missing one selects none.
+fn selects(budget: &Value, pod: &Value) -> bool {
+    let Some(selector) = budget
+        .pointer("/spec/selector")
+        .filter(|selector| !selector.is_null())
+    else {
+        return false;
+    };
+    let label = |key: &str| {
+        pod.pointer("/metadata/labels")
+            .and_then(|labels| labels.get(key))
+            .and_then(Value::as_str)
+    };
+    let match_labels = selector
+        .pointer("/matchLabels")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .all(|(key, value)| label(key) == value.as_str());
+    let match_expressions = array(selector, "/matchExpressions")
+        .iter()
+        .all(|expression| {
+            let value = label(field(expression, "/key"));
+            let in_values = || {
+                array(expression, "/values")
+                    .iter()
+                    .any(|candidate| candidate.as_str() == value)
+            };
+            match field(expression, "/operator") {
+                "In" => in_values(),
+                "NotIn" => !in_values(),
+                "Exists" => value.is_some(),
+                "DoesNotExist" => value.is_none(),
+                _ => false,
+            }
+        });
+    match_labels && match_expressions
+}
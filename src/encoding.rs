@@ -0,0 +1,40 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+// Encodings that are applied to the content of a file before it is returned to the user
+// This happens before the content is cached, so encoded content also uses less memory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    // Content is returned as it is
+    Identity,
+    // Content is compressed with gzip
+    Gzip,
+}
+
+impl Encoding {
+    // Extension that is added to the name of files with this encoding
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Encoding::Identity => "",
+            Encoding::Gzip => ".gz",
+        }
+    }
+
+    // Encode the content of a file
+    pub fn encode(&self, content: Vec<u8>) -> Vec<u8> {
+        match self {
+            Encoding::Identity => content,
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                match encoder.write_all(&content).and_then(|_| encoder.finish()) {
+                    Ok(compressed) => compressed,
+                    Err(error) => {
+                        log::error!("Could not compress content: {}", error);
+                        Vec::new()
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::kubectl::{self, Tracked};
+use crate::stream::StreamBuffer;
+use serde_json::Value;
+use std::io::BufReader;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Name of the file in the context directory that streams events (see --events-stream)
+pub const EVENTS_STREAM_FILE_NAME: &str = "events.stream";
+// Amount of events that is kept for readers that fall behind
+const MAX_STREAM_BYTES: usize = 16 * 1024 * 1024;
+// How long we wait before restarting the watch after kubectl exited
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+// Start watching the events of all namespaces in a background thread
+// Every event is appended to the stream as a single line of JSON, so that it can be
+// consumed with "tail -f events.stream | jq".
+pub fn start(context: &str) -> Arc<StreamBuffer> {
+    let stream = Arc::new(StreamBuffer::with_limit(MAX_STREAM_BYTES));
+    let watched_stream = stream.clone();
+    let context = context.to_string();
+    thread::spawn(move || loop {
+        watch_events(&context, &watched_stream);
+        log::debug!("Event watch ended, restarting it in {:?}", RESTART_DELAY);
+        thread::sleep(RESTART_DELAY);
+    });
+
+    stream
+}
+
+// Run "kubectl get events --watch-only" until it exits and append every event it emits
+fn watch_events(context: &str, stream: &StreamBuffer) {
+    let child = kubectl::command()
+        .args(["--context", context, "get", "events", "--all-namespaces"])
+        .args(["--watch-only", "-ojson"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .tracked_spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(error) => {
+            log::error!("Could not start watching events: {}", error);
+            return;
+        }
+    };
+
+    // kubectl prints one (pretty printed) JSON object per event
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    for event in serde_json::Deserializer::from_reader(stdout).into_iter::<Value>() {
+        match event {
+            Ok(event) => stream.append(format!("{}\n", event).as_bytes()),
+            Err(error) => {
+                log::debug!("Could not parse watched event: {}", error);
+                break;
+            }
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -0,0 +1,113 @@
+use crate::kubectl::{self, Tracked};
+use crate::stream::StreamBuffer;
+use crate::watcher::ContainerKey;
+use std::io::{self, Read, Write};
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+// Name of the file inside a container directory that forwards writes to the exec session
+pub const EXEC_STDIN_FILE_NAME: &str = "exec.stdin";
+// Name of the file inside a container directory that contains the output of the exec session
+pub const EXEC_STDOUT_FILE_NAME: &str = "exec.stdout";
+// Older output of a session is dropped once it grows beyond this many bytes
+const MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+// Interactive "kubectl exec" session in a container
+// Commands are written to exec.stdin and their output can be read from exec.stdout.
+pub struct ExecSession {
+    child: Child,
+    // Input is written to the shell by a separate thread, so a shell that does not read its
+    // input does not block the filesystem
+    input: Sender<Vec<u8>>,
+    output: Arc<StreamBuffer>,
+}
+
+impl ExecSession {
+    // Start a shell in a container
+    pub fn start(context: &str, (namespace, pod, container): &ContainerKey) -> io::Result<Self> {
+        log::info!(
+            "Starting exec session in {}/{}/{}",
+            namespace,
+            pod,
+            container
+        );
+        let mut child = kubectl::command()
+            .args(["--context", context, "--namespace", namespace])
+            .args(["exec", "--stdin", pod, "--container", container, "--", "sh"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .tracked_spawn()?;
+
+        let output = Arc::new(StreamBuffer::with_limit(MAX_OUTPUT_BYTES));
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        let stdout_output = output.clone();
+        let stderr_output = output.clone();
+        thread::spawn(move || {
+            forward(stdout, &stdout_output);
+            // The session is over once kubectl closed stdout
+            stdout_output.finish();
+        });
+        thread::spawn(move || forward(stderr, &stderr_output));
+        let (input, receiver) = mpsc::channel();
+        let stdin = child.stdin.take().unwrap();
+        thread::spawn(move || write_input(stdin, receiver.iter()));
+
+        Ok(ExecSession {
+            input,
+            child,
+            output,
+        })
+    }
+
+    // Send input to the shell
+    // Fails once the shell stopped reading its input because kubectl exited.
+    pub fn write(&mut self, input: &[u8]) -> io::Result<()> {
+        self.input
+            .send(input.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "exec session has ended"))
+    }
+
+    pub fn output(&self) -> Arc<StreamBuffer> {
+        self.output.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.output.is_finished()
+    }
+}
+
+impl Drop for ExecSession {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Copy everything from a pipe into the session output
+fn forward(mut pipe: impl Read, output: &StreamBuffer) {
+    let mut buffer = [0; 4096];
+    loop {
+        match pipe.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => output.append(&buffer[..read]),
+            Err(error) => {
+                log::debug!("Could not read output of exec session: {}", error);
+                break;
+            }
+        }
+    }
+}
+
+// Copy the input of the session to the stdin of kubectl until the session ends
+fn write_input(mut stdin: ChildStdin, input: impl Iterator<Item = Vec<u8>>) {
+    for data in input {
+        if let Err(error) = stdin.write_all(&data).and_then(|_| stdin.flush()) {
+            log::debug!("Could not write input of exec session: {}", error);
+            break;
+        }
+    }
+}
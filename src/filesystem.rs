@@ -1,19 +1,132 @@
-use crate::k8s_resource::{ResourceFile, ResourceType};
-use crate::kubectl;
-use fuser::{Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, Request};
+use crate::activity::Activity;
+use crate::admission::{self, ADMISSION_DIRECTORY_NAME};
+use crate::cache::{self, CacheKey, ContentCache, SharedCache, CACHE_TTL};
+use crate::certs::{self, TLS_DIRECTORY_NAME};
+use crate::config::Config;
+use crate::containers;
+use crate::diagnostics::{self, LAST_ERROR_FILE_NAME};
+use crate::encoding::Encoding;
+use crate::events::{self, EVENTS_STREAM_FILE_NAME};
+use crate::exec::{ExecSession, EXEC_STDIN_FILE_NAME, EXEC_STDOUT_FILE_NAME};
+use crate::gitops;
+use crate::handles::ManifestHandles;
+use crate::health::{self, Health};
+use crate::ignore;
+use crate::journal::{self, Entry, Journal};
+use crate::k8s_resource::{
+    parse_timestamp, DefinitionFormat, Description, FileKind, ResourceFile, ResourceType,
+};
+use crate::kubectl::{self, K8sError};
+use crate::layout::{self, Layout};
+use crate::modes::MarkedKind;
+use crate::notify::ChangeNotifier;
+use crate::pagination::PagedListings;
+use crate::pending::{Operation, PendingOps, PENDING_OPS_FILE_NAME};
+use crate::prefetch::{PrefetchPolicy, Prefetcher};
+use crate::priority::PRIORITY_CLASSES_DIRECTORY_NAME;
+use crate::prune::{PruneRule, Pruner, PREVIEW_SUFFIX, PRUNE_FILE_NAME};
+use crate::query::{self, Query, SelectorType, QUERY_DIRECTORY_NAME};
+use crate::quota::ChangeQuota;
+use crate::rbac;
+use crate::renderer::Renderer;
+use crate::reports::{self, MetadataField, Report, FORCE_DELETE_AFTER};
+use crate::rollout::{self, ROLLOUT_STATUS_FILE_NAME};
+use crate::stats::OperationStats;
+use crate::storage::StorageKind;
+use crate::stream::StreamBuffer;
+use crate::templates::{self, Template, TEMPLATES_DIRECTORY_NAME};
+use crate::trash::{Trash, TRASH_DIRECTORY_NAME};
+use crate::traversal::{TraversalDetector, BULK_WINDOW_DURATION};
+use crate::usage;
+use crate::version;
+use crate::watcher::{self, ContainerKey, ListenerId, Watcher};
+use crate::xattr::{self, HASH_XATTR_NAME};
+use crate::yaml;
+use fuser::consts::FOPEN_DIRECT_IO;
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory,
+    ReplyEmpty, ReplyEntry, ReplyLseek, ReplyOpen, ReplyWrite, ReplyXattr, Request, TimeOrNow,
+};
 // https://www2.hs-fulda.de/~klingebiel/c-stdlib/sys.errno.h.htm
-use libc::{ENOBUFS, ENOENT, EPERM};
-use std::cmp::min;
-use std::collections::BTreeMap;
+use libc::{
+    EBUSY, EDQUOT, EEXIST, EINVAL, EIO, ENOBUFS, ENODATA, ENOENT, ENXIO, EPERM, ERANGE, EROFS,
+    ESTALE, O_ACCMODE, O_DIRECT, O_RDONLY, SEEK_CUR, SEEK_DATA, SEEK_END, SEEK_HOLE, SEEK_SET,
+};
+use serde_json::{json, Value};
+use std::cmp::{max, Reverse};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ffi::OsStr;
-use std::io::Read;
-use std::time::Duration;
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 const TTL: Duration = Duration::from_secs(1);
 pub type Inode = u64;
 pub type Offset = i64;
 const ROOT_INODE: Inode = 0;
 const CONTEXT_INODE: Inode = 1;
+// Name of the directory that contains files which are used to control the filesystem
+const CONTROL_DIRECTORY_NAME: &str = ".k8sfs";
+// Name of the directory inside the control directory where manifests can be dropped
+// to apply them to the cluster
+const APPLY_DIRECTORY_NAME: &str = "apply";
+// Suffix of the file that contains the outcome of applying a dropped manifest
+const APPLY_RESULT_SUFFIX: &str = ".result";
+// Suffix of the file that contains the warnings of the API server when a manifest was applied
+const APPLY_WARNINGS_SUFFIX: &str = ".warnings";
+// Name of the file inside the control directory that contains cache statistics
+const CACHE_STATS_FILE_NAME: &str = "cache-stats";
+// Name of the file inside the control directory that contains operation statistics
+const STATS_FILE_NAME: &str = "stats";
+// Name of the file inside the control directory that resets all statistics when written to
+const STATS_RESET_FILE_NAME: &str = "stats-reset";
+// Name of the file inside the control directory that can be read to check that the mount works
+const HEALTH_FILE_NAME: &str = "health";
+// Name of the file inside the control directory that is used to undo changes
+const UNDO_FILE_NAME: &str = "undo";
+// Name of the directory inside a pod directory that contains a directory per container
+const CONTAINERS_DIRECTORY_NAME: &str = "containers";
+// Name of the directory inside a pod directory that contains a directory per init container
+const INIT_CONTAINERS_DIRECTORY_NAME: &str = "init-containers";
+// Name of the file inside a container directory that contains its restart history
+const RESTART_HISTORY_FILE_NAME: &str = "restart-history";
+// Name of the directory in the context directory that contains a directory per node
+const NODES_DIRECTORY_NAME: &str = "nodes";
+// Name of the directory inside a node directory that contains the log files of the node
+const NODE_LOGS_DIRECTORY_NAME: &str = "journal";
+// Name of the directory inside a namespace directory that contains a directory per service
+const SERVICES_DIRECTORY_NAME: &str = "services";
+// Name of the directory inside a service directory that proxies requests to the service
+const PROXY_DIRECTORY_NAME: &str = "proxy";
+// Name of the directory inside a namespace that contains a directory per ConfigMap
+const CONFIGMAPS_DIRECTORY_NAME: &str = "configmaps";
+// Name of the directory inside a namespace that contains the deployments
+const DEPLOYMENTS_DIRECTORY_NAME: &str = "deployments";
+// Name of the directory inside a namespace that contains the StatefulSets
+const STATEFULSETS_DIRECTORY_NAME: &str = "statefulsets";
+// Name of the directory inside a namespace that contains the completed pods (see --hide-completed)
+const COMPLETED_DIRECTORY_NAME: &str = "completed";
+// Directories of a namespace whose entries are exported with the resource they show
+// (directory name, resource)
+const EXPORTED_DIRECTORIES: [(&str, &str); 4] = [
+    (SERVICES_DIRECTORY_NAME, "services"),
+    (DEPLOYMENTS_DIRECTORY_NAME, "deployments"),
+    (STATEFULSETS_DIRECTORY_NAME, "statefulsets"),
+    (CONFIGMAPS_DIRECTORY_NAME, "configmaps"),
+];
+// Name of the symlink inside a pod directory that points to the directory of its deployment
+const DEPLOYMENT_SYMLINK_NAME: &str = "deployment";
+// Name of the hidden directory inside resource directories that contains their metadata
+const METADATA_DIRECTORY_NAME: &str = ".metadata";
+// Name of the file inside metadata directories that removes the finalizers of the resource
+const REMOVE_FINALIZERS_FILE_NAME: &str = "remove-finalizers";
+// Name of the directory in the context directory where IP addresses are looked up
+const BY_IP_DIRECTORY_NAME: &str = "_by-ip";
+// Name of the pod level script that opens a shell in the default container
+const EXEC_FILE_NAME: &str = "exec";
 // Tuple values explanations:
 //   * Resource: Contains the file type and k8s information that is associated with this Inode
 //   * Vec<Inode>: Contains inodes for all children. This depends on the ResourceType.
@@ -22,6 +135,9 @@ const CONTEXT_INODE: Inode = 1;
 //      * Pods will contain all containers as files
 //   * Inode: Parent Inode
 pub type File = (ResourceFile, Vec<Inode>);
+// Receives the filesystem when its FUSE session ends, so that it can be mounted again with
+// its inode table and cache after the connection to the kernel broke (see main::mount)
+pub type Recovery = Arc<Mutex<Option<K8sFS>>>;
 
 // Struct that represents the filesystem
 pub struct K8sFS {
@@ -31,90 +147,3278 @@ pub struct K8sFS {
     // As the name implies, we store the value of the next inode
     // in this field
     next_inode: Inode,
+    config: Config,
+    // Tracks when the filesystem was last used (see --idle-timeout)
+    activity: Activity,
+    // Inode of the control directory (.k8sfs)
+    control_inode: Inode,
+    // Inode of the trash directory (.k8sfs/trash), 0 without --allow-write
+    trash_inode: Inode,
+    // Inode of the context directory
+    // This is only different from CONTEXT_INODE when a subtree is mounted (see --root-path)
+    context_inode: Inode,
+    // Manifests that were written to since they were last applied
+    pending_manifests: HashSet<Inode>,
+    // State of the objects of manifests when they were opened for writing
+    // Applying an edited manifest fails if any of them changed in the meantime.
+    opened_manifests: HashMap<Inode, Vec<Value>>,
+    // Buffers of manifests that are opened for writing, one per file handle
+    manifest_handles: ManifestHandles,
+    // Content of files that is expensive to generate (e.g. descriptions)
+    cache: SharedCache,
+    // Changes that were made through the filesystem and can be undone
+    journal: Journal,
+    // Manifests of resources that were deleted through the filesystem
+    trash: Trash,
+    // Changes that wait for the API server to be reachable again (see --queue-offline)
+    pending_ops: PendingOps,
+    // Rule that was previewed in .k8sfs/prune.preview and the outcome of the last pruning
+    pruner: Pruner,
+    // Limits the number of changes that can be made through the filesystem
+    quota: ChangeQuota,
+    // Counts and latencies of the handled FUSE operations
+    stats: OperationStats,
+    // Tells the kernel about pods that the watcher saw changing
+    changes: ChangeNotifier,
+    // Namespaces whose pods are listed once they are accessed (see --large-cluster)
+    // Value: (namespace, estimated number of pods)
+    deferred_pods: HashMap<Inode, (String, u64)>,
+    // Deferred namespaces that are read page by page, per directory handle (see --page-size)
+    paged_listings: PagedListings,
+    // Service (namespace, service) per cluster IP, collected when the services are listed
+    service_ips: HashMap<String, (String, String)>,
+    // Detects recursive traversals to switch to bulk list calls
+    traversal: TraversalDetector,
+    // Running exec sessions (see exec.stdin / exec.stdout)
+    exec_sessions: HashMap<ContainerKey, ExecSession>,
+    // Events of the cluster (see events.stream), the watch is started when the file is opened
+    events: Option<Arc<StreamBuffer>>,
+    // Followed rollouts per rollout-status file with the number of handles of the file
+    // A rollout is followed again when the file is opened after it was done, and it is no longer
+    // followed once the last handle was released.
+    rollouts: HashMap<Inode, (Arc<StreamBuffer>, usize)>,
+    // Fetches descriptions of listed resources in the background (see --prefetch)
+    prefetcher: Option<Prefetcher>,
+    // Watches the cluster for changes that the API does not retain
+    // This is started once the filesystem is mounted
+    watcher: Option<Watcher>,
+    // Listener that forwards changes of the watcher to the kernel
+    change_listener: Option<ListenerId>,
+    // Set once init built the inode table, a remounted filesystem keeps its table
+    initialized: bool,
+    // Where the filesystem hands itself over when it is destroyed (see with_recovery)
+    recovery: Option<Recovery>,
 }
 
 impl K8sFS {
-    pub fn new() -> Self {
+    pub fn new(config: Config) -> Self {
         K8sFS {
             inode_table: BTreeMap::new(),
             next_inode: 2,
+            cache: Arc::new(Mutex::new(ContentCache::new(
+                config.cache_max_bytes,
+                CACHE_TTL,
+            ))),
+            quota: ChangeQuota::new(config.max_changes_per_hour),
+            config,
+            activity: Activity::new(),
+            control_inode: 0,
+            trash_inode: 0,
+            context_inode: CONTEXT_INODE,
+            pending_manifests: HashSet::new(),
+            opened_manifests: HashMap::new(),
+            manifest_handles: ManifestHandles::default(),
+            journal: Journal::default(),
+            trash: Trash::default(),
+            pending_ops: PendingOps::default(),
+            pruner: Pruner::default(),
+            exec_sessions: HashMap::new(),
+            events: None,
+            rollouts: HashMap::new(),
+            prefetcher: None,
+            traversal: TraversalDetector::default(),
+            changes: ChangeNotifier::default(),
+            deferred_pods: HashMap::new(),
+            paged_listings: PagedListings::default(),
+            service_ips: HashMap::new(),
+            stats: OperationStats::default(),
+            watcher: None,
+            change_listener: None,
+            initialized: false,
+            recovery: None,
         }
     }
 
-    pub fn name(&self) -> String {
+    // Use a watcher that is shared with other mounts of the same context
+    pub fn with_watcher(mut self, watcher: Watcher) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    // Share the content cache with other mounts of the same context (see the daemon)
+    pub fn with_cache(mut self, cache: SharedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    // Hand the filesystem over to recovery when its session ends instead of dropping it
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    // Prepare a filesystem that was handed over to recovery for being mounted again
+    // State that belongs to open files of the old session is dropped, the kernel forgot them.
+    pub fn remount(mut self, recovery: Recovery) -> Self {
+        if !self.pending_manifests.is_empty() {
+            log::warn!(
+                "Discarding {} manifests that were not applied before the connection broke",
+                self.pending_manifests.len()
+            );
+        }
+        self.pending_manifests.clear();
+        self.opened_manifests.clear();
+        self.manifest_handles = ManifestHandles::default();
+        self.paged_listings = PagedListings::default();
+        self.exec_sessions.clear();
+        self.activity = Activity::new();
+        self.with_recovery(recovery)
+    }
+
+    // Options that are passed to FUSE when the filesystem is mounted
+    pub fn mount_options(&self) -> Vec<MountOption> {
+        let mut mount_options = vec![MountOption::FSName(K8sFS::name())];
+        if self.config.allow_write {
+            mount_options.push(MountOption::RW);
+        } else {
+            mount_options.push(MountOption::RO);
+        }
+        if let Some(max_read) = self.config.max_read {
+            mount_options.push(MountOption::CUSTOM(format!("max_read={}", max_read)));
+        }
+        mount_options
+    }
+
+    // Return a handle that has to receive the notifier of the FUSE session once the filesystem
+    // is mounted
+    pub fn change_notifier(&self) -> ChangeNotifier {
+        self.changes.clone()
+    }
+
+    // Return a handle that can be used to check when the filesystem was last used
+    pub fn activity(&self) -> Activity {
+        self.activity.clone()
+    }
+
+    // Write the definitions of all resources to a directory as YAML, in the same layout as the
+    // mount (e.g. <directory>/default/web-0/web-0_definition.yaml)
+    // The tree is only listed once, so the export is a snapshot of the resources at that time.
+    // Fields that are managed by the API server are removed, so that the manifests can be
+    // applied again (see "k8sfs apply-tree"). Objects with owners (e.g. pods of a replica set)
+    // are skipped, they are recreated by their owner. Nodes are skipped as well, they are
+    // registered by their kubelet.
+    // Returns the number of written manifests.
+    pub fn export(&mut self, directory: &Path) -> io::Result<usize> {
+        self.initialize_inode_table();
+        let context = self.context();
+        // Objects without definition files are taken from one list per namespace and kind
+        // instead of being retrieved one by one
+        kubectl::start_bulk_window(BULK_WINDOW_DURATION);
+        let mut exported = 0;
+        for namespace_inode in self.resource_children(self.context_inode, ResourceType::Namespace) {
+            let namespace = self
+                .get_file_by_inode(namespace_inode)
+                .unwrap()
+                .name
+                .clone();
+            let namespace_path = directory.join(&namespace);
+            let mut definitions = vec![(namespace_inode, namespace_path.clone())];
+            // Completed pods are moved to completed/ and are exported there as well
+            let completed_inode = self
+                .get_file_by_name(OsStr::new(COMPLETED_DIRECTORY_NAME), namespace_inode)
+                .map(|file| file.inode);
+            for (parent, path) in [
+                (Some(namespace_inode), namespace_path.clone()),
+                (
+                    completed_inode,
+                    namespace_path.join(COMPLETED_DIRECTORY_NAME),
+                ),
+            ] {
+                let Some(parent) = parent else {
+                    continue;
+                };
+                for pod_inode in self.resource_children(parent, ResourceType::Pod) {
+                    let name = &self.get_file_by_inode(pod_inode).unwrap().name;
+                    definitions.push((pod_inode, path.join(name)));
+                }
+            }
+            for (inode, path) in definitions {
+                let file = self.get_file_by_inode(inode).unwrap();
+                let content = file
+                    .create_formatted_file(inode, file.name.clone(), DefinitionFormat::Json)
+                    .get_desc();
+                match serde_json::from_slice::<Value>(&content) {
+                    Ok(object) => exported += self.export_object(object, &path)?,
+                    Err(error) => log::warn!("Skipping {}: {}", path.display(), error),
+                }
+            }
+
+            // Kinds that have a directory of their own but no definition files
+            for (directory_name, resource) in EXPORTED_DIRECTORIES {
+                let Some(kind_inode) = self
+                    .get_file_by_name(OsStr::new(directory_name), namespace_inode)
+                    .map(|file| file.inode)
+                else {
+                    continue;
+                };
+                let names: Vec<String> = self.inode_table[&kind_inode]
+                    .1
+                    .iter()
+                    .filter_map(|child| self.get_file_by_inode(*child))
+                    .map(|file| file.name.clone())
+                    .collect();
+                for name in names {
+                    let path = namespace_path.join(directory_name).join(&name);
+                    match kubectl::get_object(&context, Some(&namespace), resource, &name) {
+                        Ok(object) => exported += self.export_object(object, &path)?,
+                        Err(reason) => log::warn!("Skipping {}: {}", path.display(), reason),
+                    }
+                }
+            }
+        }
+
+        Ok(exported)
+    }
+
+    // Return the resource directories of a type in a directory
+    fn resource_children(&self, inode: Inode, resource_type: ResourceType) -> Vec<Inode> {
+        self.inode_table[&inode]
+            .1
+            .iter()
+            .copied()
+            .filter(|child| {
+                self.get_file_by_inode(*child).is_some_and(|file| {
+                    *file.kind() == FileKind::Resource && file.resource_type() == resource_type
+                })
+            })
+            .collect()
+    }
+
+    // Write the definition of an object to the directory of the object in an export
+    // Returns the number of written manifests (0 if the object has owners).
+    fn export_object(&self, object: Value, path: &Path) -> io::Result<usize> {
+        if !reports::array(&object, "/metadata/ownerReferences").is_empty() {
+            log::debug!("Skipping {}, it has owners", path.display());
+            return Ok(0);
+        }
+        let name = reports::field(&object, "/metadata/name").to_string();
+        std::fs::create_dir_all(path)?;
+        std::fs::write(
+            path.join(self.config.definition_file_name(&name)),
+            yaml::to_yaml(&journal::clean(object)),
+        )?;
+        Ok(1)
+    }
+
+    pub fn name() -> String {
         String::from("KubernetesFS")
     }
 
-    // Build inode table by connecting to the cluster, gathering information on the running
-    // resources (Namespaces, Pods etc.) and creating files from them.
-    fn initialize_inode_table(&mut self) {
-        log::info!("Initializing inode table");
-        // Init FS root
-        let root = ResourceFile::new(ROOT_INODE, ROOT_INODE, "root", ResourceType::Root, "", "");
-        // Init kubernetes context (which is the kubernetes root)
-        let context = kubectl::current_context();
-        let context_file = ResourceFile::new(
-            CONTEXT_INODE,
-            ROOT_INODE,
-            &context,
-            ResourceType::Context,
+    // Build inode table by connecting to the cluster, gathering information on the running
+    // resources (Namespaces, Pods etc.) and creating files from them.
+    fn initialize_inode_table(&mut self) {
+        log::info!("Initializing inode table");
+        // Init FS root
+        let root = ResourceFile::new(ROOT_INODE, ROOT_INODE, "root", ResourceType::Root, "", "");
+        // Init kubernetes context (which is the kubernetes root)
+        let context = self
+            .config
+            .context
+            .clone()
+            .unwrap_or_else(kubectl::current_context);
+        let context_file = ResourceFile::new(
+            CONTEXT_INODE,
+            ROOT_INODE,
+            &context,
+            ResourceType::Context,
+            &context,
+            "",
+        );
+        // Add root node
+        self.inode_table
+            .insert(root.inode, (root, vec![context_file.inode]));
+        // Add context node
+        self.inode_table
+            .insert(context_file.inode, (context_file, Vec::new()));
+        self.initialize_control_directory();
+        self.initialize_query_directory();
+        self.initialize_rbac_directory();
+        self.initialize_admission_directory(&context);
+        self.initialize_storage_directories(&context);
+        self.initialize_priority_classes_directory(&context);
+        self.build_virtual_file(CONTEXT_INODE, BY_IP_DIRECTORY_NAME, FileKind::ByIp);
+        self.build_report_file(
+            CONTEXT_INODE,
+            Report::Contexts {
+                context: context.clone(),
+            },
+        );
+        self.build_report_file(
+            CONTEXT_INODE,
+            Report::Version {
+                context: context.clone(),
+            },
+        );
+        self.build_report_file(
+            CONTEXT_INODE,
+            Report::NodeInventory {
+                context: context.clone(),
+            },
+        );
+        self.build_report_file(
+            CONTEXT_INODE,
+            Report::ExpiringCerts {
+                context: context.clone(),
+                window_days: self.config.expiring_certs_days,
+            },
+        );
+        self.build_report_file(
+            CONTEXT_INODE,
+            Report::Timeline {
+                context: context.clone(),
+                minutes: self.config.timeline_minutes,
+            },
+        );
+        if self.config.events_stream {
+            self.build_virtual_file(
+                CONTEXT_INODE,
+                EVENTS_STREAM_FILE_NAME,
+                FileKind::EventStream,
+            );
+        }
+        // Init kubernetes namespaces
+        let namespaces = match kubectl::namespaces(&context) {
+            Ok(namespaces) => namespaces,
+            Err(error) => {
+                if !matches!(error, K8sError::Forbidden(_)) {
+                    log::error!("Could not list namespaces: {}", error);
+                }
+                self.build_error_file(CONTEXT_INODE, &error);
+                Vec::new()
+            }
+        };
+        for namespace_object in namespaces {
+            let namespace = reports::field(&namespace_object, "/metadata/name").to_string();
+            if !self.config.is_namespace_visible(&namespace) {
+                log::debug!("Skipping namespace {}", namespace);
+                continue;
+            }
+            let namespace_inode = self.build_resource_file(
+                &namespace,
+                ResourceType::Namespace,
+                CONTEXT_INODE,
+                &context,
+                &namespace,
+                Some(&namespace_object),
+            );
+            self.add_child_to_inode(CONTEXT_INODE, namespace_inode);
+            self.build_namespace_files(namespace_inode, &context, &namespace);
+            // Init kubernetes pods
+            if self.config.large_cluster {
+                self.defer_pods(namespace_inode, &context, &namespace);
+            } else {
+                self.build_namespace_pods(namespace_inode, &context, &namespace);
+            }
+        }
+        self.initialize_nodes_directory(&context);
+    }
+
+    // Build the directories of all pods of a namespace
+    fn build_namespace_pods(&mut self, namespace_inode: Inode, context: &str, namespace: &str) {
+        // Namespaces where we are not allowed to list pods are marked and skipped
+        let pods = match kubectl::pods(context, namespace) {
+            Ok(pods) => pods,
+            Err(error @ K8sError::Forbidden(_)) => {
+                log::info!("Not allowed to list pods in namespace {}", namespace);
+                self.build_error_file(namespace_inode, &error);
+                return;
+            }
+            Err(error) => {
+                log::error!("Could not list pods in namespace {}: {}", namespace, error);
+                self.build_error_file(namespace_inode, &error);
+                Vec::new()
+            }
+        };
+        let completed_inode = self.completed_directory(namespace_inode);
+        // Pods that were listed on their own before (e.g. by a lookup) are skipped
+        let existing = self.pod_names(namespace_inode);
+        for pod in pods {
+            if !existing.contains(reports::field(&pod, "/metadata/name")) {
+                self.build_pod(namespace_inode, completed_inode, context, namespace, &pod);
+            }
+        }
+    }
+
+    // Return the completed/ directory of a namespace and create it if it does not exist yet
+    // Completed pods (e.g. of jobs) are moved out of the way of the running ones, unless
+    // --hide-completed=false is used.
+    fn completed_directory(&mut self, namespace_inode: Inode) -> Option<Inode> {
+        if !self.config.hide_completed {
+            return None;
+        }
+        match self.get_file_by_name(OsStr::new(COMPLETED_DIRECTORY_NAME), namespace_inode) {
+            Some(file) => Some(file.inode),
+            None => Some(self.build_virtual_file(
+                namespace_inode,
+                COMPLETED_DIRECTORY_NAME,
+                FileKind::Directory,
+            )),
+        }
+    }
+
+    // Names of the pods of a namespace that have a directory (including completed ones)
+    fn pod_names(&self, namespace_inode: Inode) -> HashSet<String> {
+        let completed_inode = self
+            .get_file_by_name(OsStr::new(COMPLETED_DIRECTORY_NAME), namespace_inode)
+            .map(|file| file.inode);
+        [Some(namespace_inode), completed_inode]
+            .into_iter()
+            .flatten()
+            .filter_map(|inode| self.inode_table.get(&inode))
+            .flat_map(|(_, children)| children)
+            .filter_map(|child| self.get_file_by_inode(*child))
+            .filter(|file| {
+                file.resource_type() == ResourceType::Pod && *file.kind() == FileKind::Resource
+            })
+            .map(|file| file.name.clone())
+            .collect()
+    }
+
+    // Return true if the pods of a deferred namespace are listed page by page
+    fn is_paged_namespace(&self, namespace_inode: Inode) -> bool {
+        self.deferred_pods
+            .get(&namespace_inode)
+            .is_some_and(|(_, estimate)| {
+                self.config.page_size > 0 && *estimate > self.config.page_size
+            })
+    }
+
+    // Build the directory of a single pod of a namespace that is listed page by page, when it
+    // is looked up before the page that contains it was listed
+    fn build_deferred_pod(&mut self, namespace_inode: Inode, name: &OsStr) {
+        if self.get_file_by_name(name, namespace_inode).is_some() {
+            return;
+        }
+        let Some((namespace, _)) = self.deferred_pods.get(&namespace_inode).cloned() else {
+            return;
+        };
+        let context = self.context();
+        let name = name.to_string_lossy();
+        let pod = match kubectl::get_object(&context, Some(&namespace), "pods", &name) {
+            Ok(pod) => pod,
+            Err(error) => {
+                log::debug!("Could not retrieve pod {}/{}: {}", namespace, name, error);
+                return;
+            }
+        };
+        if self.pod_names(namespace_inode).contains(name.as_ref()) {
+            return;
+        }
+        let completed_inode = self.completed_directory(namespace_inode);
+        self.build_pod(namespace_inode, completed_inode, &context, &namespace, &pod);
+    }
+
+    // List the next page of pods of the namespace that is read through a directory handle
+    // Returns false if there are no more pages.
+    fn list_next_page(&mut self, fh: u64) -> bool {
+        let Some(listing) = self
+            .paged_listings
+            .get(fh)
+            .filter(|listing| !listing.complete)
+        else {
+            return false;
+        };
+        let namespace_inode = listing.namespace_inode;
+        let continue_token = listing.continue_token.clone();
+        // Another listing (e.g. a refresh) listed all pods in the meantime
+        let Some((namespace, _)) = self.deferred_pods.get(&namespace_inode).cloned() else {
+            return false;
+        };
+        let context = self.context();
+        log::debug!("Listing the next page of pods of {}", namespace);
+        let (pods, continue_token) = match kubectl::list_page(
+            &context,
+            &namespace,
+            "pods",
+            self.config.page_size,
+            continue_token.as_deref(),
+        ) {
+            Ok(page) => page,
+            // Continue tokens expire after a few minutes, the listing ends with what it has
+            Err(error) => {
+                log::error!("Could not list the pods of {}: {}", namespace, error);
+                self.paged_listings.get_mut(fh).unwrap().complete = true;
+                return false;
+            }
+        };
+        let completed_inode = self.completed_directory(namespace_inode);
+        let existing = self.pod_names(namespace_inode);
+        for pod in &pods {
+            if !existing.contains(reports::field(pod, "/metadata/name")) {
+                self.build_pod(namespace_inode, completed_inode, &context, &namespace, pod);
+            }
+        }
+        let listing = self.paged_listings.get_mut(fh).unwrap();
+        listing.complete = continue_token.is_none();
+        listing.continue_token = continue_token;
+        // Once all pages were listed, the namespace is like any other
+        if listing.complete {
+            self.deferred_pods.remove(&namespace_inode);
+        }
+        true
+    }
+
+    // Only count the pods of a namespace and list them once the namespace is accessed
+    // (see --large-cluster)
+    fn defer_pods(&mut self, namespace_inode: Inode, context: &str, namespace: &str) {
+        let estimate = kubectl::count_items(context, namespace, "pods").unwrap_or_else(|error| {
+            log::debug!("Could not count the pods in {}: {}", namespace, error);
+            0
+        });
+        self.deferred_pods
+            .insert(namespace_inode, (namespace.to_string(), estimate));
+    }
+
+    // List the pods of a namespace whose pods were deferred, before it is used for the first time
+    fn build_deferred_pods(&mut self, namespace_inode: Inode) {
+        let Some((namespace, _)) = self.deferred_pods.remove(&namespace_inode) else {
+            return;
+        };
+        log::debug!("Listing the deferred pods of {}", namespace);
+        let context = self.context();
+        self.build_namespace_pods(namespace_inode, &context, &namespace);
+    }
+
+    // Build the directory of a pod in its namespace (or in completed/ of the namespace)
+    fn build_pod(
+        &mut self,
+        namespace_inode: Inode,
+        completed_inode: Option<Inode>,
+        context: &str,
+        namespace: &str,
+        pod: &Value,
+    ) {
+        let pod_name = reports::field(pod, "/metadata/name");
+        let parent_inode = match completed_inode {
+            Some(completed_inode) if reports::is_completed(pod) => completed_inode,
+            _ => namespace_inode,
+        };
+        let pod_inode = self.build_resource_file(
+            pod_name,
+            ResourceType::Pod,
+            parent_inode,
+            context,
+            namespace,
+            Some(pod),
+        );
+        self.add_child_to_inode(parent_inode, pod_inode);
+        if health::assess(&self.config.health_rules, ResourceType::Pod, pod) == Health::Degraded {
+            let mode_marker = self.config.modes.bits(MarkedKind::Degraded);
+            let (pod_file, _) = self.inode_table.get_mut(&pod_inode).unwrap();
+            pod_file.set_mode_marker(mode_marker);
+        }
+        self.build_pod_files(pod_inode, context, namespace, pod);
+        // Completed pods are one level deeper than the deployments directory
+        let prefix = match parent_inode == namespace_inode {
+            true => "..",
+            false => "../..",
+        };
+        self.build_deployment_symlink(pod_inode, namespace_inode, prefix, pod);
+        let mut pod_inodes = self.inode_table.get(&pod_inode).unwrap().1.clone();
+        pod_inodes.push(pod_inode);
+        self.changes.register(
+            watcher::object_key(ResourceType::Pod, namespace, pod_name),
+            pod_inodes,
+        );
+    }
+
+    // Add the objects that were created by applying a manifest to the filesystem right away,
+    // so that they are visible without waiting for the inode table to be rebuilt
+    // Objects of kinds that are not shown in the filesystem and objects that already have
+    // a directory are skipped.
+    fn add_applied_objects(&mut self, objects: &[Value]) {
+        let context = self.context();
+        // Objects can be created in namespaces of the same manifest
+        let (namespaces, objects): (Vec<&Value>, Vec<&Value>) = objects
+            .iter()
+            .partition(|object| reports::field(object, "/kind") == "Namespace");
+        for namespace_object in namespaces {
+            let namespace = reports::field(namespace_object, "/metadata/name");
+            if !self.config.is_namespace_visible(namespace)
+                || self
+                    .get_file_by_name(OsStr::new(namespace), self.context_inode)
+                    .is_some()
+            {
+                continue;
+            }
+            log::debug!("Adding applied namespace {}", namespace);
+            let namespace_inode = self.build_resource_file(
+                namespace,
+                ResourceType::Namespace,
+                self.context_inode,
+                &context,
+                namespace,
+                Some(namespace_object),
+            );
+            self.add_child_to_inode(self.context_inode, namespace_inode);
+            self.build_namespace_files(namespace_inode, &context, namespace);
+        }
+        for object in objects {
+            let kind = reports::field(object, "/kind");
+            let name = reports::field(object, "/metadata/name");
+            let namespace = reports::field(object, "/metadata/namespace");
+            let Some(namespace_inode) = self
+                .get_file_by_name(OsStr::new(namespace), self.context_inode)
+                .map(|file| file.inode)
+            else {
+                continue;
+            };
+            let directory = match kind {
+                "Pod" => None,
+                "Service" => Some(SERVICES_DIRECTORY_NAME),
+                "Deployment" => Some(DEPLOYMENTS_DIRECTORY_NAME),
+                "StatefulSet" => Some(STATEFULSETS_DIRECTORY_NAME),
+                "ConfigMap" => Some(CONFIGMAPS_DIRECTORY_NAME),
+                _ => continue,
+            };
+            let parent_inode = match directory {
+                Some(directory) => self
+                    .get_file_by_name(OsStr::new(directory), namespace_inode)
+                    .map(|file| file.inode),
+                None => Some(namespace_inode),
+            };
+            let Some(parent_inode) = parent_inode else {
+                continue;
+            };
+            if self
+                .get_file_by_name(OsStr::new(name), parent_inode)
+                .is_some()
+            {
+                continue;
+            }
+            log::debug!("Adding applied {} {}/{}", kind, namespace, name);
+            match kind {
+                // The pod is part of the listing once the namespace is accessed
+                "Pod" if self.deferred_pods.contains_key(&namespace_inode) => (),
+                "Pod" => {
+                    let completed_inode = self
+                        .get_file_by_name(OsStr::new(COMPLETED_DIRECTORY_NAME), namespace_inode)
+                        .map(|file| file.inode);
+                    self.build_pod(
+                        namespace_inode,
+                        completed_inode,
+                        &context,
+                        namespace,
+                        object,
+                    );
+                }
+                "Service" => {
+                    self.build_service_directory(parent_inode, &context, namespace, object)
+                }
+                "Deployment" => {
+                    self.build_deployment_directory(parent_inode, &context, namespace, object)
+                }
+                "StatefulSet" => self.build_statefulset_directory(parent_inode, namespace, object),
+                _ => self.build_configmap_directory(parent_inode, namespace, object),
+            }
+        }
+    }
+
+    // Build the nodes directory which contains a directory per node of the cluster
+    fn initialize_nodes_directory(&mut self, context: &str) {
+        let nodes_inode =
+            self.build_virtual_file(CONTEXT_INODE, NODES_DIRECTORY_NAME, FileKind::Directory);
+        let nodes = match kubectl::get_items(context, None, "nodes", &[]) {
+            Ok(nodes) => nodes,
+            Err(error) => {
+                if !matches!(error, K8sError::Forbidden(_)) {
+                    log::error!("Could not list nodes: {}", error);
+                }
+                self.build_error_file(nodes_inode, &error);
+                Vec::new()
+            }
+        };
+        for node in nodes {
+            let node_name = reports::field(&node, "/metadata/name");
+            let node_inode = self.build_resource_file(
+                node_name,
+                ResourceType::Node,
+                nodes_inode,
+                context,
+                "",
+                Some(&node),
+            );
+            self.add_child_to_inode(nodes_inode, node_inode);
+            let reports = [
+                Report::NodeConditions {
+                    context: context.to_string(),
+                    node: node_name.to_string(),
+                },
+                Report::Allocatable {
+                    context: context.to_string(),
+                    node: node_name.to_string(),
+                },
+                Report::DrainPlan {
+                    context: context.to_string(),
+                    node: node_name.to_string(),
+                },
+                Report::NodeLog {
+                    context: context.to_string(),
+                    node: node_name.to_string(),
+                    file: None,
+                },
+            ];
+            for report in reports {
+                self.build_report_file(node_inode, report);
+            }
+            self.build_virtual_file(
+                node_inode,
+                NODE_LOGS_DIRECTORY_NAME,
+                FileKind::NodeLogs(node_name.to_string()),
+            );
+            self.build_rendered_files(node_inode, ResourceType::Node, context, None, node_name);
+        }
+    }
+
+    // Build the control directory (.k8sfs) which contains files that do not represent
+    // kubernetes resources, but are used to interact with the filesystem itself
+    fn initialize_control_directory(&mut self) {
+        self.control_inode =
+            self.build_virtual_file(CONTEXT_INODE, CONTROL_DIRECTORY_NAME, FileKind::Directory);
+        self.build_virtual_file(
+            self.control_inode,
+            CACHE_STATS_FILE_NAME,
+            FileKind::CacheStats,
+        );
+        self.build_virtual_file(self.control_inode, STATS_FILE_NAME, FileKind::Stats);
+        self.build_virtual_file(
+            self.control_inode,
+            usage::API_USAGE_FILE_NAME,
+            FileKind::ApiUsage,
+        );
+        self.build_virtual_file(
+            self.control_inode,
+            STATS_RESET_FILE_NAME,
+            FileKind::StatsReset,
+        );
+        // Reading it only succeeds while the filesystem responds (e.g. for liveness probes)
+        self.build_virtual_file(
+            self.control_inode,
+            HEALTH_FILE_NAME,
+            FileKind::Static(b"ok\n".to_vec()),
+        );
+        if self.config.allow_write {
+            self.build_virtual_file(
+                self.control_inode,
+                APPLY_DIRECTORY_NAME,
+                FileKind::DropDirectory,
+            );
+            self.build_virtual_file(self.control_inode, UNDO_FILE_NAME, FileKind::Undo);
+            self.build_virtual_file(self.control_inode, PRUNE_FILE_NAME, FileKind::Prune);
+            if self.config.queue_offline {
+                self.build_virtual_file(
+                    self.control_inode,
+                    PENDING_OPS_FILE_NAME,
+                    FileKind::PendingOps,
+                );
+            }
+            self.trash_inode = self.build_virtual_file(
+                self.control_inode,
+                TRASH_DIRECTORY_NAME,
+                FileKind::Directory,
+            );
+            // The trash survives rebuilding the inode table
+            let trashed: Vec<_> = self
+                .trash
+                .manifests()
+                .map(|trashed| (trashed.name.clone(), trashed.manifest.clone()))
+                .collect();
+            for (name, manifest) in trashed {
+                self.build_virtual_file(self.trash_inode, &name, FileKind::Static(manifest));
+            }
+        }
+    }
+
+    // Keep the manifest of a resource that was deleted through the filesystem in .k8sfs/trash
+    fn trash_object(&mut self, object: Value) {
+        if self.trash_inode == 0 {
+            return;
+        }
+        let trashed = self.trash.add(object);
+        let (name, manifest) = (trashed.name.clone(), trashed.manifest.clone());
+        log::info!(
+            "Keeping the manifest of the deleted resource in trash/{}",
+            name
+        );
+        self.build_virtual_file(self.trash_inode, &name, FileKind::Static(manifest));
+        self.expire_trash();
+    }
+
+    // Remove the manifests that were kept for longer than --trash-retention
+    fn expire_trash(&mut self) {
+        for name in self.trash.expire(self.config.trash_retention) {
+            log::debug!("Removing expired trash/{}", name);
+            if let Some(inode) = self
+                .get_file_by_name(OsStr::new(&name), self.trash_inode)
+                .map(|file| file.inode)
+            {
+                self.clean_up_inode(inode, self.trash_inode);
+            }
+        }
+    }
+
+    // Build the rbac directory with the can-i directory for permission checks
+    fn initialize_rbac_directory(&mut self) {
+        let rbac_inode = self.build_virtual_file(
+            CONTEXT_INODE,
+            rbac::RBAC_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        self.build_virtual_file(
+            rbac_inode,
+            rbac::CAN_I_DIRECTORY_NAME,
+            FileKind::CanI(Vec::new()),
+        );
+    }
+
+    // Build the directory with the webhooks and aggregated APIServices of the cluster
+    fn initialize_admission_directory(&mut self, context: &str) {
+        let admission_inode =
+            self.build_virtual_file(CONTEXT_INODE, ADMISSION_DIRECTORY_NAME, FileKind::Directory);
+        for inventory in admission::Inventory::all() {
+            self.build_report_file(
+                admission_inode,
+                Report::Admission {
+                    context: context.to_string(),
+                    inventory,
+                },
+            );
+        }
+    }
+
+    // Build the directories of the storage classes and CSI drivers with a file per object
+    fn initialize_storage_directories(&mut self, context: &str) {
+        for kind in StorageKind::all() {
+            let directory_inode =
+                self.build_virtual_file(CONTEXT_INODE, kind.directory_name(), FileKind::Directory);
+            self.build_report_file(
+                directory_inode,
+                Report::StorageSummary {
+                    context: context.to_string(),
+                    kind,
+                },
+            );
+            let objects = match kubectl::get_items(context, None, kind.kubectl_name(), &[]) {
+                Ok(objects) => objects,
+                Err(error) => {
+                    log::debug!("Could not list {}: {}", kind.directory_name(), error);
+                    self.build_error_file(directory_inode, &error);
+                    Vec::new()
+                }
+            };
+            for object in objects {
+                self.build_report_file(
+                    directory_inode,
+                    Report::Storage {
+                        context: context.to_string(),
+                        kind,
+                        name: reports::field(&object, "/metadata/name").to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    // Build the directory of the priority classes with a file per class
+    fn initialize_priority_classes_directory(&mut self, context: &str) {
+        let directory_inode = self.build_virtual_file(
+            CONTEXT_INODE,
+            PRIORITY_CLASSES_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        self.build_report_file(
+            directory_inode,
+            Report::PriorityClasses {
+                context: context.to_string(),
+            },
+        );
+        let classes = match kubectl::get_items(context, None, "priorityclasses", &[]) {
+            Ok(classes) => classes,
+            Err(error) => {
+                log::debug!("Could not list priority classes: {}", error);
+                self.build_error_file(directory_inode, &error);
+                Vec::new()
+            }
+        };
+        for class in classes {
+            self.build_report_file(
+                directory_inode,
+                Report::PriorityClass {
+                    context: context.to_string(),
+                    name: reports::field(&class, "/metadata/name").to_string(),
+                },
+            );
+        }
+    }
+
+    // Build the query directory (_query) with a directory for every selector type
+    fn initialize_query_directory(&mut self) {
+        let query_inode =
+            self.build_virtual_file(CONTEXT_INODE, QUERY_DIRECTORY_NAME, FileKind::Directory);
+        for selector_type in SelectorType::all() {
+            self.build_virtual_file(
+                query_inode,
+                selector_type.directory_name(),
+                FileKind::QueryType(selector_type),
+            );
+        }
+    }
+
+    // Replace the content of a query directory with symlinks to all pods that currently
+    // match the query
+    fn refresh_query(&mut self, inode: Inode) {
+        let query = match self
+            .get_file_by_inode(inode)
+            .map(|file| file.kind().clone())
+        {
+            Some(FileKind::Query(query)) => query,
+            _ => return,
+        };
+        log::debug!("Running query {:?}", query);
+        let pods = match kubectl::get_items(
+            &self.context(),
+            None,
+            "pods",
+            &query.kubectl_args().each_ref().map(String::as_str),
+        ) {
+            Ok(pods) => pods,
+            Err(reason) => {
+                log::error!("Could not run query {:?}: {}", query, reason);
+                return;
+            }
+        };
+
+        let children = self.inode_table.get(&inode).unwrap().1.clone();
+        for child in children {
+            self.clean_up_inode(child, inode);
+        }
+        for pod in &pods {
+            let namespace = reports::field(pod, "/metadata/namespace");
+            if !self.config.is_namespace_visible(namespace) {
+                continue;
+            }
+            let name = reports::field(pod, "/metadata/name");
+            let path = match self.config.hide_completed && reports::is_completed(pod) {
+                true => format!("{}/{}", COMPLETED_DIRECTORY_NAME, name),
+                false => name.to_string(),
+            };
+            self.build_virtual_file(
+                inode,
+                &query::symlink_name(namespace, name),
+                FileKind::Symlink(query::symlink_target(namespace, &path)),
+            );
+        }
+    }
+
+    // Build the permission check or the directory for the next part of it that a looked up
+    // name in a can-i directory refers to
+    fn build_can_i_entry(&mut self, parent: Inode, name: &OsStr) -> Option<Inode> {
+        let mut parts = match self.get_file_by_inode(parent)?.kind() {
+            FileKind::CanI(parts) => parts.clone(),
+            _ => return None,
+        };
+        let name = name.to_string_lossy();
+        if !rbac::is_valid_name(&name) {
+            log::debug!("{:?} is not a valid verb, resource or namespace", name);
+            return None;
+        }
+        if parts.len() < 2 {
+            parts.push(name.to_string());
+            return Some(self.build_virtual_file(parent, &name, FileKind::CanI(parts)));
+        }
+
+        let report = Report::CanI {
+            context: self.context(),
+            verb: parts[0].clone(),
+            resource: parts[1].clone(),
+            namespace: Some(name.to_string()).filter(|name| name != rbac::ALL_NAMESPACES_NAME),
+        };
+        Some(self.build_report_file(parent, report))
+    }
+
+    // Build the file with the definition of a resource in the format that the extension of
+    // a looked up name in its directory asks for
+    //   <pod>/<pod>.yaml, <pod>/<pod>.json, <pod>/<pod>.txt (describe)
+    fn build_formatted_definition(&mut self, parent: Inode, name: &OsStr) -> Option<Inode> {
+        let resource = self.get_file_by_inode(parent)?;
+        if *resource.kind() != FileKind::Resource
+            || !ResourceType::kinds().contains(&resource.resource_type())
+        {
+            return None;
+        }
+        let name = name.to_string_lossy();
+        let (stem, extension) = name.rsplit_once('.')?;
+        let format = DefinitionFormat::from_extension(extension)?;
+        if stem != resource.name {
+            return None;
+        }
+        let inode = self.calculate_next_inode();
+        let file =
+            self.get_file_by_inode(parent)?
+                .create_formatted_file(inode, name.to_string(), format);
+        self.inode_table.insert(inode, (file, Vec::new()));
+        self.add_child_to_inode(parent, inode);
+        Some(inode)
+    }
+
+    // Build the symlink to the pod or service with an IP address in the _by-ip directory
+    // The owner of an IP address changes over time, so an existing symlink is replaced.
+    //   _by-ip/10.1.2.3 -> ../<namespace>/<pod>
+    //   _by-ip/10.96.0.10 -> ../<namespace>/services/<service>
+    fn build_by_ip_entry(&mut self, parent: Inode, name: &OsStr) -> Option<Inode> {
+        if *self.get_file_by_inode(parent)?.kind() != FileKind::ByIp {
+            return None;
+        }
+        if let Some(existing) = self.get_file_by_name(name, parent).map(|file| file.inode) {
+            self.clean_up_inode(existing, parent);
+        }
+        let ip = name.to_string_lossy();
+        if ip.parse::<IpAddr>().is_err() {
+            return None;
+        }
+        let target = match self.service_ips.get(ip.as_ref()) {
+            Some((namespace, service)) => {
+                format!("{}/{}/{}", namespace, SERVICES_DIRECTORY_NAME, service)
+            }
+            None => {
+                let (namespace, pod) = self.watcher.as_ref()?.pod_with_ip(&ip)?;
+                self.pod_path(&namespace, &pod)?
+            }
+        };
+        Some(self.build_virtual_file(parent, &ip, FileKind::Symlink(format!("../{}", target))))
+    }
+
+    // Return the path of a pod directory relative to the context directory
+    // Returns None if the pod is not part of the filesystem (yet)
+    fn pod_path(&self, namespace: &str, pod: &str) -> Option<String> {
+        let namespace_inode = self
+            .get_file_by_name(OsStr::new(namespace), self.context_inode)?
+            .inode;
+        if self
+            .get_file_by_name(OsStr::new(pod), namespace_inode)
+            .is_some()
+        {
+            return Some(format!("{}/{}", namespace, pod));
+        }
+        let completed_inode = self
+            .get_file_by_name(OsStr::new(COMPLETED_DIRECTORY_NAME), namespace_inode)?
+            .inode;
+        self.get_file_by_name(OsStr::new(pod), completed_inode)
+            .map(|_| format!("{}/{}/{}", namespace, COMPLETED_DIRECTORY_NAME, pod))
+    }
+
+    // Build the proxy directory of a service with a directory per port
+    //   proxy/<port name or number>/<path>
+    fn build_service_proxy_directory(
+        &mut self,
+        service_inode: Inode,
+        namespace: &str,
+        service: &Value,
+    ) {
+        let proxy_inode =
+            self.build_virtual_file(service_inode, PROXY_DIRECTORY_NAME, FileKind::Directory);
+        let service_name = reports::field(service, "/metadata/name");
+        for port in reports::array(service, "/spec/ports") {
+            let port_name = match reports::field(port, "/name") {
+                "" => port
+                    .pointer("/port")
+                    .and_then(Value::as_u64)
+                    .map(|port| port.to_string())
+                    .unwrap_or_default(),
+                name => name.to_string(),
+            };
+            if port_name.is_empty() {
+                continue;
+            }
+            // The proxy uses plain HTTP unless the scheme is part of the service name
+            let scheme = if port_name.starts_with("https")
+                || port.pointer("/port").and_then(Value::as_u64) == Some(443)
+            {
+                "https:"
+            } else {
+                ""
+            };
+            self.build_virtual_file(
+                proxy_inode,
+                &port_name,
+                FileKind::ServiceProxy {
+                    namespace: namespace.to_string(),
+                    service: format!("{}{}:{}", scheme, service_name, port_name),
+                },
+            );
+        }
+    }
+
+    // Create the file of a proxied path when it is looked up for the first time
+    fn build_service_proxy_entry(&mut self, parent: Inode, name: &OsStr) -> Option<Inode> {
+        let (namespace, service) = match self.get_file_by_inode(parent)?.kind() {
+            FileKind::ServiceProxy { namespace, service } => (namespace.clone(), service.clone()),
+            _ => return None,
+        };
+        let report = Report::ServiceProxy {
+            context: self.context(),
+            namespace,
+            service,
+            file: name.to_string_lossy().to_string(),
+        };
+        Some(self.build_report_file(parent, report))
+    }
+
+    // Create the directory for a query when it is looked up for the first time
+    fn build_query_directory(&mut self, parent: Inode, name: &OsStr) -> Option<Inode> {
+        let selector_type = match self.get_file_by_inode(parent)?.kind() {
+            FileKind::QueryType(selector_type) => *selector_type,
+            _ => return None,
+        };
+        let name = name.to_string_lossy();
+        let query = Query::parse(selector_type, &name)?;
+
+        Some(self.build_virtual_file(parent, &name, FileKind::Query(query)))
+    }
+
+    // Return the children of a directory in the order in which they should be listed
+    // With --sort-by-age kubernetes resources are listed from newest to oldest, after all
+    // files that do not represent a kubernetes resource
+    fn ordered_children(&self, inode: Inode) -> Option<Vec<Inode>> {
+        let mut children = self.inode_table.get(&inode)?.1.clone();
+        if self.config.sort_by_age {
+            // sort_by_key is stable, so files without a timestamp keep the order they were
+            // added in. RFC 3339 timestamps can be compared as strings.
+            children.sort_by_key(|child| {
+                let timestamp = self
+                    .inode_table
+                    .get(child)
+                    .filter(|(file, _)| *file.kind() == FileKind::Resource)
+                    .and_then(|(file, _)| file.creation_timestamp.clone());
+                (timestamp.is_some(), Reverse(timestamp))
+            });
+        }
+
+        Some(children)
+    }
+
+    // Return true if the content of the directory is retrieved when it is accessed
+    // (query results, node logs and kinds of the group-version layout)
+    fn is_lazy_directory(&self, inode: Inode) -> bool {
+        matches!(
+            self.inode_table.get(&inode).map(|(file, _)| file.kind()),
+            Some(FileKind::Query(_) | FileKind::NodeLogs(_) | FileKind::ApiKind(_))
+        )
+    }
+
+    fn refresh_lazy_directory(&mut self, inode: Inode) {
+        match self
+            .get_file_by_inode(inode)
+            .map(|file| file.kind().clone())
+        {
+            Some(FileKind::Query(_)) => self.refresh_query(inode),
+            Some(FileKind::NodeLogs(node)) => self.refresh_node_logs(inode, &node),
+            Some(FileKind::ApiKind(kind)) => self.refresh_api_kind(inode, &kind),
+            _ => (),
+        }
+    }
+
+    // Build the _api/<group>/<version>/<Kind>/ directories of a namespace (see --layout)
+    fn build_api_directories(&mut self, namespace_inode: Inode) {
+        let api_inode = self.build_virtual_file(
+            namespace_inode,
+            layout::API_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        for (group, version, kind) in layout::API_KINDS {
+            let group_inode = match self.get_file_by_name(OsStr::new(group), api_inode) {
+                Some(file) => file.inode,
+                None => self.build_virtual_file(api_inode, group, FileKind::Directory),
+            };
+            let version_inode = match self.get_file_by_name(OsStr::new(version), group_inode) {
+                Some(file) => file.inode,
+                None => self.build_virtual_file(group_inode, version, FileKind::Directory),
+            };
+            self.build_virtual_file(version_inode, kind, FileKind::ApiKind(kind.to_string()));
+        }
+    }
+
+    // Replace the content of a kind directory of the group-version layout with symlinks to the
+    // directories of all resources of the kind in the namespace
+    fn refresh_api_kind(&mut self, inode: Inode, kind: &str) {
+        // <namespace>/_api/<group>/<version>/<Kind>
+        let Some(namespace_inode) = (0..4).try_fold(inode, |inode, _| {
+            self.get_file_by_inode(inode).map(|file| file.parent)
+        }) else {
+            return;
+        };
+        self.build_deferred_pods(namespace_inode);
+        let directories: &[&str] = match kind {
+            "Pod" => &["", COMPLETED_DIRECTORY_NAME],
+            "Service" => &[SERVICES_DIRECTORY_NAME],
+            "ConfigMap" => &[CONFIGMAPS_DIRECTORY_NAME],
+            "Deployment" => &[DEPLOYMENTS_DIRECTORY_NAME],
+            _ => &[],
+        };
+        let mut links = Vec::new();
+        for directory in directories {
+            let parent = match directory.is_empty() {
+                true => Some(namespace_inode),
+                false => self
+                    .get_file_by_name(OsStr::new(directory), namespace_inode)
+                    .map(|file| file.inode),
+            };
+            let Some(parent) = parent else {
+                continue;
+            };
+            for child in self.inode_table.get(&parent).unwrap().1.clone() {
+                let file = self.get_file_by_inode(child).unwrap();
+                // Pods are next to the other files of the namespace
+                let is_resource = match kind {
+                    "Pod" => {
+                        file.resource_type() == ResourceType::Pod
+                            && *file.kind() == FileKind::Resource
+                    }
+                    _ => file.filetype() == FileType::Directory,
+                };
+                if is_resource {
+                    let target = match directory.is_empty() {
+                        true => format!("../../../../{}", file.name),
+                        false => format!("../../../../{}/{}", directory, file.name),
+                    };
+                    links.push((file.name.clone(), target));
+                }
+            }
+        }
+
+        let children = self.inode_table.get(&inode).unwrap().1.clone();
+        for child in children {
+            self.clean_up_inode(child, inode);
+        }
+        for (name, target) in links {
+            self.build_virtual_file(inode, &name, FileKind::Symlink(target));
+        }
+    }
+
+    // Replace the content of a node log directory with the log files the kubelet serves
+    fn refresh_node_logs(&mut self, inode: Inode, node: &str) {
+        let context = self.context();
+        let listing = match kubectl::node_log(&context, node, "") {
+            Ok(listing) => listing,
+            Err(reason) => {
+                log::error!("Could not list the logs of node {}: {}", node, reason);
+                return;
+            }
+        };
+
+        let children = self.inode_table.get(&inode).unwrap().1.clone();
+        for child in children {
+            self.clean_up_inode(child, inode);
+        }
+        for file in reports::node_log_files(&String::from_utf8_lossy(&listing)) {
+            self.build_report_file(
+                inode,
+                Report::NodeLog {
+                    context: context.clone(),
+                    node: node.to_string(),
+                    file: Some(file),
+                },
+            );
+        }
+    }
+
+    // Refresh a file and everything below it from the cluster (touch on the file)
+    // Cached content is dropped, lazy directories are listed again and namespaces and pods that
+    // were created or deleted since the inode table was built are added or removed.
+    fn refresh_subtree(&mut self, inode: Inode) {
+        let Some(file) = self.get_file_by_inode(inode) else {
+            return;
+        };
+        log::info!("Refreshing {} from the cluster", file.name);
+        let (resource_type, kind) = (file.resource_type(), file.kind().clone());
+        self.drop_cached_subtree(inode);
+        match (resource_type, kind) {
+            _ if self.is_lazy_directory(inode) => self.refresh_lazy_directory(inode),
+            (ResourceType::Context, _) => {
+                self.refresh_namespaces();
+                let children = self.inode_table.get(&self.context_inode).unwrap().1.clone();
+                for child in children {
+                    if self.is_namespace_directory(child) {
+                        self.refresh_namespace_pods(child);
+                    }
+                }
+            }
+            (ResourceType::Namespace, FileKind::Resource) => self.refresh_namespace_pods(inode),
+            _ => (),
+        }
+        self.changes.invalidate(inode);
+    }
+
+    fn drop_cached_subtree(&mut self, inode: Inode) {
+        self.uncache(inode);
+        let children = self
+            .inode_table
+            .get(&inode)
+            .map(|(_, children)| children.clone())
+            .unwrap_or_default();
+        for child in children {
+            self.drop_cached_subtree(child);
+        }
+    }
+
+    fn is_namespace_directory(&self, inode: Inode) -> bool {
+        self.get_file_by_inode(inode).is_some_and(|file| {
+            file.resource_type() == ResourceType::Namespace && *file.kind() == FileKind::Resource
+        })
+    }
+
+    // Add namespaces that were created and remove namespaces that were deleted
+    fn refresh_namespaces(&mut self) {
+        let context = self.context();
+        let namespaces = match kubectl::namespaces(&context) {
+            Ok(namespaces) => namespaces,
+            Err(error) => {
+                log::error!("Could not list namespaces: {}", error);
+                return;
+            }
+        };
+        let children = self.inode_table.get(&self.context_inode).unwrap().1.clone();
+        for child in children {
+            if !self.is_namespace_directory(child) {
+                continue;
+            }
+            let name = self.get_file_by_inode(child).unwrap().name.clone();
+            if !namespaces
+                .iter()
+                .any(|namespace| reports::field(namespace, "/metadata/name") == name)
+            {
+                log::debug!("Removing deleted namespace {}", name);
+                self.deferred_pods.remove(&child);
+                self.clean_up_inode(child, self.context_inode);
+            }
+        }
+        self.add_applied_objects(&namespaces);
+    }
+
+    // Add pods that were created and remove pods that were deleted
+    // Pods that completed (or were restarted) are moved between the namespace and completed/.
+    fn refresh_namespace_pods(&mut self, namespace_inode: Inode) {
+        // The pods of deferred namespaces are listed when the namespace is accessed anyway
+        if self.deferred_pods.contains_key(&namespace_inode) {
+            return;
+        }
+        let context = self.context();
+        let namespace = self
+            .get_file_by_inode(namespace_inode)
+            .unwrap()
+            .name
+            .clone();
+        let pods = match kubectl::pods(&context, &namespace) {
+            Ok(pods) => pods,
+            Err(error) => {
+                log::error!("Could not list pods in namespace {}: {}", namespace, error);
+                return;
+            }
+        };
+        let completed_inode = self
+            .get_file_by_name(OsStr::new(COMPLETED_DIRECTORY_NAME), namespace_inode)
+            .map(|file| file.inode);
+        // Directory that a pod belongs in
+        let pod_parent = |pod: &Value| match completed_inode {
+            Some(completed_inode) if reports::is_completed(pod) => completed_inode,
+            _ => namespace_inode,
+        };
+
+        for parent in std::iter::once(namespace_inode).chain(completed_inode) {
+            let children = self.inode_table.get(&parent).unwrap().1.clone();
+            for child in children {
+                let file = self.get_file_by_inode(child).unwrap();
+                if file.resource_type() != ResourceType::Pod || *file.kind() != FileKind::Resource {
+                    continue;
+                }
+                let current = pods.iter().any(|pod| {
+                    reports::field(pod, "/metadata/name") == file.name && pod_parent(pod) == parent
+                });
+                if !current {
+                    log::debug!("Removing pod {}/{}", namespace, file.name);
+                    self.clean_up_inode(child, parent);
+                }
+            }
+        }
+        for pod in &pods {
+            let name = reports::field(pod, "/metadata/name");
+            if self
+                .get_file_by_name(OsStr::new(name), pod_parent(pod))
+                .is_none()
+            {
+                log::debug!("Adding pod {}/{}", namespace, name);
+                self.build_pod(namespace_inode, completed_inode, &context, &namespace, pod);
+            }
+        }
+        if let Some(completed_inode) = completed_inode {
+            self.changes.invalidate(completed_inode);
+        }
+    }
+
+    // Helper method to add kubernetes resources to the inode table
+    // This method also add a "definition" file to the parent along side the resource file
+    // that is created.
+    // The reasoning here is that every directory should have its definition file, which is
+    // basically just a kubectl describe call for the underlying kubernetes resource, next to it.
+    fn build_resource_file(
+        &mut self,
+        name: &str,
+        resource_type: ResourceType,
+        parent_inode: Inode,
+        context: &str,
+        namespace: &str,
+        object: Option<&Value>,
+    ) -> Inode {
+        let inode = self.calculate_next_inode();
+        let mut children = Vec::new();
+        let mut file =
+            ResourceFile::new(inode, parent_inode, name, resource_type, context, namespace);
+        if let Some(object) = object {
+            file.creation_timestamp =
+                Some(reports::field(object, "/metadata/creationTimestamp").to_string());
+            file.modification_timestamp = reports::last_update(object).map(String::from);
+        }
+        if !self.config.show_events {
+            file.hide_events();
+        }
+        let definition_file = file.create_definition_file(
+            self.calculate_next_inode(),
+            self.config.definition_file_name(name),
+        );
+        children.push(definition_file.inode);
+        self.inode_table
+            .insert(definition_file.inode, (definition_file, Vec::new()));
+
+        self.inode_table.insert(inode, (file, children));
+        self.build_metadata_directory(inode, resource_type, context, namespace, name);
+        self.build_virtual_file(
+            inode,
+            LAST_ERROR_FILE_NAME,
+            FileKind::LastError {
+                resource_type,
+                namespace: resource_type.is_namespaced().then(|| namespace.to_string()),
+                name: name.to_string(),
+            },
+        );
+        self.build_report_file(
+            inode,
+            Report::Capabilities {
+                context: context.to_string(),
+                namespace: resource_type.is_namespaced().then(|| namespace.to_string()),
+                resource_type,
+                allow_write: self.config.allow_write,
+            },
+        );
+        self.build_report_file(
+            inode,
+            Report::Ownership {
+                context: context.to_string(),
+                namespace: resource_type.is_namespaced().then(|| namespace.to_string()),
+                resource_type,
+                name: name.to_string(),
+                argocd_instance_label: self.config.argocd_instance_label.clone(),
+            },
+        );
+        if !self.config.show_events {
+            self.build_report_file(
+                inode,
+                Report::Events {
+                    context: context.to_string(),
+                    namespace: resource_type.is_namespaced().then(|| namespace.to_string()),
+                    resource_type,
+                    name: name.to_string(),
+                },
+            );
+        }
+        // The files of the resource itself change with it (pods register their other files too)
+        let (_, children) = self.inode_table.get(&inode).unwrap();
+        let mut inodes: Vec<Inode> = children.clone();
+        for child in children {
+            inodes.extend(self.inode_table.get(child).unwrap().1.iter().copied());
+        }
+        inodes.push(inode);
+        self.changes
+            .register(watcher::object_key(resource_type, namespace, name), inodes);
+
+        inode
+    }
+
+    // Build the hidden .metadata directory of a kubernetes resource with a file per
+    // metadata field (labels, annotations, ...)
+    fn build_metadata_directory(
+        &mut self,
+        resource_inode: Inode,
+        resource_type: ResourceType,
+        context: &str,
+        namespace: &str,
+        name: &str,
+    ) {
+        let metadata_inode =
+            self.build_virtual_file(resource_inode, METADATA_DIRECTORY_NAME, FileKind::Directory);
+        for field in MetadataField::all() {
+            self.build_report_file(
+                metadata_inode,
+                Report::Metadata {
+                    context: context.to_string(),
+                    namespace: resource_type.is_namespaced().then(|| namespace.to_string()),
+                    resource_type,
+                    name: name.to_string(),
+                    field,
+                },
+            );
+        }
+        if self.config.allow_write {
+            self.build_virtual_file(
+                metadata_inode,
+                REMOVE_FINALIZERS_FILE_NAME,
+                FileKind::RemoveFinalizers {
+                    resource_type,
+                    namespace: resource_type.is_namespaced().then(|| namespace.to_string()),
+                    name: name.to_string(),
+                },
+            );
+        }
+    }
+
+    // Helper method to add the files that every namespace directory contains
+    fn build_namespace_files(&mut self, namespace_inode: Inode, context: &str, namespace: &str) {
+        let reports = [
+            Report::Unschedulable {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                newest_first: self.config.sort_by_age,
+            },
+            Report::Health {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                rules: self.config.health_rules.clone(),
+            },
+            Report::Warnings {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+            },
+            Report::Kubeconfig {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                expose_credentials: self.config.expose_credentials,
+            },
+            Report::TerminationStatus {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                force_delete: self.config.force_delete,
+            },
+        ];
+        for report in reports {
+            self.build_report_file(namespace_inode, report);
+        }
+        if let Some(prices) = &self.config.prices {
+            let report = Report::CostEstimate {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                prices: prices.clone(),
+            };
+            self.build_report_file(namespace_inode, report);
+        }
+
+        // Services are only listed to resolve their backends, so failures are not fatal
+        let services = kubectl::get_items(context, Some(namespace), "services", &[])
+            .unwrap_or_else(|error| {
+                log::debug!("Could not list services in {}: {:?}", namespace, error);
+                Vec::new()
+            });
+        let services_inode = self.build_virtual_file(
+            namespace_inode,
+            SERVICES_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        for service in services {
+            self.build_service_directory(services_inode, context, namespace, &service);
+        }
+
+        let deployments = kubectl::get_items(context, Some(namespace), "deployments", &[])
+            .unwrap_or_else(|error| {
+                log::debug!("Could not list deployments in {}: {:?}", namespace, error);
+                Vec::new()
+            });
+        let deployments_inode = self.build_virtual_file(
+            namespace_inode,
+            DEPLOYMENTS_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        for deployment in deployments {
+            self.build_deployment_directory(deployments_inode, context, namespace, &deployment);
+        }
+        let statefulsets = kubectl::get_items(context, Some(namespace), "statefulsets", &[])
+            .unwrap_or_else(|error| {
+                log::debug!("Could not list statefulsets in {}: {:?}", namespace, error);
+                Vec::new()
+            });
+        let statefulsets_inode = self.build_virtual_file(
+            namespace_inode,
+            STATEFULSETS_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        for statefulset in statefulsets {
+            self.build_statefulset_directory(statefulsets_inode, namespace, &statefulset);
+        }
+        let configmaps = kubectl::get_items(context, Some(namespace), "configmaps", &[])
+            .unwrap_or_else(|error| {
+                log::debug!("Could not list configmaps in {}: {:?}", namespace, error);
+                Vec::new()
+            });
+        let configmaps_inode = self.build_virtual_file(
+            namespace_inode,
+            CONFIGMAPS_DIRECTORY_NAME,
+            FileKind::Directory,
+        );
+        for configmap in configmaps {
+            self.build_configmap_directory(configmaps_inode, namespace, &configmap);
+        }
+        self.build_tls_directory(namespace_inode, context, namespace);
+        if self.config.allow_write {
+            let templates_inode = self.build_virtual_file(
+                namespace_inode,
+                TEMPLATES_DIRECTORY_NAME,
+                FileKind::Directory,
+            );
+            for template in self.config.templates.clone() {
+                self.build_virtual_file(
+                    templates_inode,
+                    &template.name,
+                    FileKind::Static(template.manifest.into_bytes()),
+                );
+            }
+        }
+        if self.config.layout == Layout::GroupVersion {
+            self.build_api_directories(namespace_inode);
+        }
+        self.build_rendered_files(
+            namespace_inode,
+            ResourceType::Namespace,
+            context,
+            None,
+            namespace,
+        );
+    }
+
+    // Build the directory of a service in services/ of a namespace
+    fn build_service_directory(
+        &mut self,
+        services_inode: Inode,
+        context: &str,
+        namespace: &str,
+        service: &Value,
+    ) {
+        let service_name = reports::field(service, "/metadata/name");
+        let service_inode =
+            self.build_virtual_file(services_inode, service_name, FileKind::Directory);
+        // Headless services have the cluster IP "None"
+        for ip in reports::array(service, "/spec/clusterIPs") {
+            if let Some(ip) = ip.as_str().filter(|ip| ip.parse::<IpAddr>().is_ok()) {
+                self.service_ips.insert(
+                    ip.to_string(),
+                    (namespace.to_string(), service_name.to_string()),
+                );
+            }
+        }
+        self.build_report_file(
+            service_inode,
+            Report::Backends {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                service: service_name.to_string(),
+            },
+        );
+        if self.config.service_proxy {
+            self.build_service_proxy_directory(service_inode, namespace, service);
+        }
+    }
+
+    // Build the directory of a deployment in deployments/ of a namespace
+    fn build_deployment_directory(
+        &mut self,
+        deployments_inode: Inode,
+        context: &str,
+        namespace: &str,
+        deployment: &Value,
+    ) {
+        let deployment_name = reports::field(deployment, "/metadata/name");
+        let deployment_inode =
+            self.build_virtual_file(deployments_inode, deployment_name, FileKind::Directory);
+        self.build_report_file(
+            deployment_inode,
+            Report::DeploymentHealth {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                deployment: deployment_name.to_string(),
+            },
+        );
+        self.build_virtual_file(
+            deployment_inode,
+            ROLLOUT_STATUS_FILE_NAME,
+            FileKind::RolloutStatus {
+                namespace: namespace.to_string(),
+                resource: String::from("deployments"),
+                name: deployment_name.to_string(),
+            },
+        );
+    }
+
+    // Build the directory of a StatefulSet in statefulsets/ of a namespace
+    fn build_statefulset_directory(
+        &mut self,
+        statefulsets_inode: Inode,
+        namespace: &str,
+        statefulset: &Value,
+    ) {
+        let statefulset_name = reports::field(statefulset, "/metadata/name");
+        let statefulset_inode =
+            self.build_virtual_file(statefulsets_inode, statefulset_name, FileKind::Directory);
+        self.build_virtual_file(
+            statefulset_inode,
+            ROLLOUT_STATUS_FILE_NAME,
+            FileKind::RolloutStatus {
+                namespace: namespace.to_string(),
+                resource: String::from("statefulsets"),
+                name: statefulset_name.to_string(),
+            },
+        );
+    }
+
+    // Build the tls/ directory of a namespace with a directory per TLS secret
+    // Reading secrets is often not allowed, which is marked in the directory.
+    fn build_tls_directory(&mut self, namespace_inode: Inode, context: &str, namespace: &str) {
+        let tls_inode =
+            self.build_virtual_file(namespace_inode, TLS_DIRECTORY_NAME, FileKind::Directory);
+        let secrets = match kubectl::get_items(
+            context,
+            Some(namespace),
+            "secrets",
+            &["--field-selector", certs::TLS_SECRET_SELECTOR],
+        ) {
+            Ok(secrets) => secrets,
+            Err(error) => {
+                log::debug!("Could not list TLS secrets in {}: {}", namespace, error);
+                self.build_error_file(tls_inode, &error);
+                Vec::new()
+            }
+        };
+        for secret in secrets {
+            let secret_name = reports::field(&secret, "/metadata/name");
+            let secret_inode = self.build_virtual_file(tls_inode, secret_name, FileKind::Directory);
+            self.build_report_file(
+                secret_inode,
+                Report::CertInfo {
+                    context: context.to_string(),
+                    namespace: namespace.to_string(),
+                    secret: secret_name.to_string(),
+                },
+            );
+        }
+    }
+
+    // Build the directory of a ConfigMap in configmaps/ of a namespace with a file per key
+    fn build_configmap_directory(
+        &mut self,
+        configmaps_inode: Inode,
+        namespace: &str,
+        configmap: &Value,
+    ) {
+        let configmap_name = reports::field(configmap, "/metadata/name");
+        let immutable = reports::is_immutable(configmap);
+        let configmap_inode = self.build_virtual_file(
+            configmaps_inode,
+            configmap_name,
+            FileKind::ConfigMap {
+                namespace: namespace.to_string(),
+                name: configmap_name.to_string(),
+                immutable,
+            },
+        );
+        let keys = configmap
+            .pointer("/data")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|data| data.keys());
+        for key in keys {
+            self.build_virtual_file(
+                configmap_inode,
+                key,
+                FileKind::ConfigMapKey {
+                    namespace: namespace.to_string(),
+                    configmap: configmap_name.to_string(),
+                    key: key.to_string(),
+                    written: None,
+                    immutable,
+                },
+            );
+        }
+    }
+
+    // Add a symlink from a pod to the directory of the deployment that owns it and a report
+    // of how the pod differs from the pod template of the deployment
+    // The replica set of a deployment is named <deployment>-<pod-template-hash>, so the
+    // deployment is found without retrieving the replica set. prefix leads from the pod
+    // directory to the namespace directory.
+    fn build_deployment_symlink(
+        &mut self,
+        pod_inode: Inode,
+        namespace_inode: Inode,
+        prefix: &str,
+        pod: &Value,
+    ) {
+        let hash = reports::field(pod, "/metadata/labels/pod-template-hash");
+        let deployment = reports::array(pod, "/metadata/ownerReferences")
+            .iter()
+            .filter(|owner| reports::field(owner, "/kind") == "ReplicaSet")
+            .find_map(|owner| {
+                reports::field(owner, "/name")
+                    .strip_suffix(hash)?
+                    .strip_suffix('-')
+            });
+        let Some(deployment) = deployment.filter(|_| !hash.is_empty()) else {
+            return;
+        };
+        // Replica sets that were not created by a deployment do not have a directory
+        let exists = self
+            .get_file_by_name(OsStr::new(DEPLOYMENTS_DIRECTORY_NAME), namespace_inode)
+            .and_then(|deployments| {
+                self.get_file_by_name(OsStr::new(deployment), deployments.inode)
+            })
+            .is_some();
+        if exists {
+            let target = format!("{}/{}/{}", prefix, DEPLOYMENTS_DIRECTORY_NAME, deployment);
+            self.build_virtual_file(
+                pod_inode,
+                DEPLOYMENT_SYMLINK_NAME,
+                FileKind::Symlink(target),
+            );
+            let context = self.context();
+            self.build_report_file(
+                pod_inode,
+                Report::TemplateDrift {
+                    context,
+                    namespace: reports::field(pod, "/metadata/namespace").to_string(),
+                    pod: reports::field(pod, "/metadata/name").to_string(),
+                    deployment: deployment.to_string(),
+                },
+            );
+        }
+    }
+
+    // Helper method to add the files that every pod directory contains
+    fn build_pod_files(&mut self, pod_inode: Inode, context: &str, namespace: &str, pod: &Value) {
+        let pod_name = reports::field(pod, "/metadata/name");
+        let reports = [
+            Report::Problems {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+            },
+            Report::Status {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+                rules: self.config.health_rules.clone(),
+            },
+            Report::Services {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+            },
+            Report::Owner {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+            },
+            Report::Scheduling {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+            },
+        ];
+        for report in reports {
+            self.build_report_file(pod_inode, report);
+        }
+        self.build_rendered_files(
+            pod_inode,
+            ResourceType::Pod,
+            context,
+            Some(namespace),
+            pod_name,
+        );
+
+        // Pod level files use the default container, like kubectl does
+        if let Some(container_name) = containers::default_container(pod) {
+            self.build_report_file(
+                pod_inode,
+                Report::Logs {
+                    context: context.to_string(),
+                    namespace: namespace.to_string(),
+                    pod: pod_name.to_string(),
+                    container: container_name.to_string(),
+                },
+            );
+            self.build_virtual_file(
+                pod_inode,
+                EXEC_FILE_NAME,
+                FileKind::Script(
+                    containers::exec_script(context, namespace, pod_name, container_name)
+                        .into_bytes(),
+                ),
+            );
+        }
+
+        let containers_inode =
+            self.build_virtual_file(pod_inode, CONTAINERS_DIRECTORY_NAME, FileKind::Directory);
+        self.build_report_file(
+            containers_inode,
+            Report::Containers {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+            },
+        );
+        for container in reports::array(pod, "/spec/containers") {
+            let container_name = reports::field(container, "/name");
+            self.build_container_directory(
+                containers_inode,
+                context,
+                namespace,
+                pod_name,
+                container_name,
+                false,
+            );
+        }
+
+        // Init containers have their own directory, so that their failures are not mixed up
+        // with the ones of the containers that never started because of them
+        let init_containers = reports::array(pod, "/spec/initContainers");
+        if !init_containers.is_empty() {
+            let init_containers_inode = self.build_virtual_file(
+                pod_inode,
+                INIT_CONTAINERS_DIRECTORY_NAME,
+                FileKind::Directory,
+            );
+            for container in init_containers {
+                let container_name = reports::field(container, "/name");
+                self.build_container_directory(
+                    init_containers_inode,
+                    context,
+                    namespace,
+                    pod_name,
+                    container_name,
+                    true,
+                );
+            }
+        }
+    }
+
+    // Build the directory of a container of a pod with its logs, status, spec and restarts
+    fn build_container_directory(
+        &mut self,
+        parent_inode: Inode,
+        context: &str,
+        namespace: &str,
+        pod_name: &str,
+        container_name: &str,
+        init: bool,
+    ) {
+        let container_inode =
+            self.build_virtual_file(parent_inode, container_name, FileKind::Directory);
+        for encoding in [Encoding::Identity, Encoding::Gzip] {
+            let report = Report::Logs {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+                container: container_name.to_string(),
+            };
+            let logs_inode = self.build_virtual_file(
+                container_inode,
+                &format!("{}{}", report.file_name(), encoding.extension()),
+                FileKind::Report(report),
+            );
+            self.inode_table
+                .get_mut(&logs_inode)
+                .unwrap()
+                .0
+                .set_encoding(encoding);
+        }
+        self.build_report_file(
+            container_inode,
+            Report::ContainerStatus {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+                container: container_name.to_string(),
+            },
+        );
+        self.build_report_file(
+            container_inode,
+            Report::ContainerSpec {
+                context: context.to_string(),
+                namespace: namespace.to_string(),
+                pod: pod_name.to_string(),
+                container: container_name.to_string(),
+            },
+        );
+        let key = (
+            namespace.to_string(),
+            pod_name.to_string(),
+            container_name.to_string(),
+        );
+        self.build_virtual_file(
+            container_inode,
+            RESTART_HISTORY_FILE_NAME,
+            FileKind::RestartHistory(key.clone()),
+        );
+        // Exec sessions are only offered for app containers
+        if self.config.allow_write && !init {
+            self.build_virtual_file(
+                container_inode,
+                EXEC_STDIN_FILE_NAME,
+                FileKind::ExecStdin(key.clone()),
+            );
+            self.build_virtual_file(
+                container_inode,
+                EXEC_STDOUT_FILE_NAME,
+                FileKind::ExecStdout(key),
+            );
+        }
+    }
+
+    // Helper method to add a file for every renderer of the config file that applies to
+    // the resource type
+    fn build_rendered_files(
+        &mut self,
+        parent_inode: Inode,
+        resource_type: ResourceType,
+        context: &str,
+        namespace: Option<&str>,
+        name: &str,
+    ) {
+        let renderers: Vec<Renderer> = self
+            .config
+            .renderers
+            .iter()
+            .filter(|renderer| renderer.resource_type == resource_type)
+            .cloned()
+            .collect();
+        for renderer in renderers {
+            self.build_report_file(
+                parent_inode,
+                Report::Render {
+                    context: context.to_string(),
+                    namespace: namespace.map(String::from),
+                    name: name.to_string(),
+                    renderer,
+                },
+            );
+        }
+    }
+
+    // Helper method to add a report file that is named after the report
+    fn build_report_file(&mut self, parent_inode: Inode, report: Report) -> Inode {
+        let name = report.file_name().to_string();
+        self.build_virtual_file(parent_inode, &name, FileKind::Report(report))
+    }
+
+    // Helper method to add a marker file to a directory whose content could not be listed
+    // FORBIDDEN for missing RBAC permissions, FAILED for everything else, so that an empty
+    // directory is not mistaken for one without resources
+    fn build_error_file(&mut self, parent_inode: Inode, error: &K8sError) -> Inode {
+        let inode = self.calculate_next_inode();
+        let file = self
+            .inode_table
+            .get(&parent_inode)
+            .unwrap()
+            .0
+            .create_error_file(inode, error);
+        self.inode_table.insert(inode, (file, Vec::new()));
+        self.add_child_to_inode(parent_inode, inode);
+
+        inode
+    }
+
+    // Helper method to add a file that does not represent a kubernetes resource to the inode table
+    fn build_virtual_file(&mut self, parent_inode: Inode, name: &str, kind: FileKind) -> Inode {
+        let inode = self.calculate_next_inode();
+        let marked_kind = match kind {
+            FileKind::Report(Report::Logs { .. }) => Some(MarkedKind::Logs),
+            FileKind::ServiceProxy { .. } => Some(MarkedKind::ServicePorts),
+            _ => None,
+        };
+        let mut file = ResourceFile::new_virtual(inode, parent_inode, name, kind);
+        if let Some(marked_kind) = marked_kind {
+            file.set_mode_marker(self.config.modes.bits(marked_kind));
+        }
+        self.inode_table.insert(inode, (file, Vec::new()));
+        self.add_child_to_inode(parent_inode, inode);
+
+        inode
+    }
+
+    // Return the template, the resource name and the namespace if a file with the name
+    // instantiates a template in the directory
+    //   <namespace>/nginx.deployment => (deployment, nginx, <namespace>)
+    fn find_template(&self, parent: Inode, name: &str) -> Option<(Template, String, String)> {
+        let namespace = match self.get_file_by_inode(parent) {
+            Some(file)
+                if *file.kind() == FileKind::Resource
+                    && file.resource_type() == ResourceType::Namespace =>
+            {
+                file.name.clone()
+            }
+            _ => return None,
+        };
+        self.config.templates.iter().find_map(|template| {
+            template.resource_name(name).map(|resource_name| {
+                (
+                    template.clone(),
+                    resource_name.to_string(),
+                    namespace.clone(),
+                )
+            })
+        })
+    }
+
+    // Instantiate a template from a file "<name>.<template>" in a namespace directory
+    // Files that were written to (e.g. a copy of .templates/<template>) are used instead of
+    // the template. The outcome of applying the manifest becomes the content of the file.
+    fn instantiate_template(&mut self, inode: Inode) -> Result<(), libc::c_int> {
+        let (name, parent, content) = match self.get_file_by_inode(inode) {
+            Some(file) => (file.name.clone(), file.parent, file.get_desc()),
+            None => return Err(ENOENT),
+        };
+        let Some((template, resource_name, namespace)) = self.find_template(parent, &name) else {
+            return Err(ENOENT);
+        };
+        if !self.quota.try_acquire() {
+            return Err(EDQUOT);
+        }
+        let manifest = if content.iter().all(u8::is_ascii_whitespace) {
+            template.render(&resource_name, &namespace)
+        } else {
+            templates::fill(
+                &String::from_utf8_lossy(&content),
+                &resource_name,
+                &namespace,
+            )
+        };
+
+        log::info!(
+            "Creating {} in {} from a template",
+            resource_name,
+            namespace
+        );
+        let outcome = self.journaled_apply(
+            manifest.as_bytes(),
+            &format!("create {}/{}", namespace, name),
+        );
+        if !outcome.success {
+            log::error!("Could not create {} from a template", name);
+        }
+        self.store_warnings(parent, &name, &outcome.warnings);
+        let conflict = outcome.conflict;
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            file.seal(outcome.report.into_bytes());
+        }
+        match conflict {
+            true => Err(EBUSY),
+            false => Ok(()),
+        }
+    }
+
+    // Apply a manifest that was written through the filesystem
+    fn apply_written_manifest(&mut self, inode: Inode) -> Result<(), libc::c_int> {
+        let kind = self.get_file_by_inode(inode).map(|file| file.kind());
+        if let Some(FileKind::ConfigMapKey { .. }) = kind {
+            self.write_configmap_key(inode)
+        } else if self.is_drop_directory(
+            self.get_file_by_inode(inode)
+                .map_or(inode, |file| file.parent),
+        ) {
+            self.apply_manifest(inode)
+        } else {
+            self.instantiate_template(inode)
+        }
+    }
+
+    // Return true if a file belongs to an immutable ConfigMap
+    // Changing such files fails with EROFS instead of being rejected by the API server.
+    fn is_immutable(&self, inode: Inode) -> bool {
+        self.get_file_by_inode(inode).is_some_and(|file| {
+            matches!(
+                file.kind(),
+                FileKind::ConfigMap {
+                    immutable: true,
+                    ..
+                } | FileKind::ConfigMapKey {
+                    immutable: true,
+                    ..
+                }
+            )
+        })
+    }
+
+    // Return true if a file changes a resource that Argo CD or Flux manages and such resources
+    // are protected (see --protect-gitops)
+    // ConfigMap keys are only checked when they start being edited.
+    fn is_gitops_managed(&self, inode: Inode) -> bool {
+        if !self.config.protect_gitops {
+            return false;
+        }
+        let (namespace, resource, name) =
+            match self.get_file_by_inode(inode).map(|file| file.kind()) {
+                Some(FileKind::ConfigMap {
+                    namespace, name, ..
+                }) => (Some(namespace.as_str()), "configmaps", name),
+                Some(FileKind::ConfigMapKey {
+                    namespace,
+                    configmap,
+                    written: None,
+                    ..
+                }) => (Some(namespace.as_str()), "configmaps", configmap),
+                Some(FileKind::RemoveFinalizers {
+                    resource_type,
+                    namespace,
+                    name,
+                }) => (namespace.as_deref(), resource_type.kubectl_name(), name),
+                _ => return false,
+            };
+        match kubectl::get_object(&self.context(), namespace, resource, name) {
+            Ok(object) => self.is_gitops_managed_object(&object),
+            Err(_) => false,
+        }
+    }
+
+    // Return true if an object is managed by Argo CD or Flux and must not be changed
+    fn is_gitops_managed_object(&self, object: &Value) -> bool {
+        if !self.config.protect_gitops {
+            return false;
+        }
+        let Some(manager) = self.gitops_manager(object) else {
+            return false;
+        };
+        log::error!(
+            "Not changing {} {}, it is managed by {} (see --protect-gitops)",
+            reports::field(object, "/kind"),
+            reports::field(object, "/metadata/name"),
+            manager
+        );
+        true
+    }
+
+    // Return the GitOps tool that manages an object
+    fn gitops_manager(&self, object: &Value) -> Option<String> {
+        gitops::manager(object, self.config.argocd_instance_label.as_deref())
+    }
+
+    // Keep the current value of a ConfigMap key in memory, so that it can be written to
+    fn start_editing_configmap_key(&mut self, inode: Inode) {
+        let current = match self.get_file_by_inode(inode).map(|file| file.kind()) {
+            Some(FileKind::ConfigMapKey { written: None, .. }) => self.file_content(inode),
+            _ => return,
+        };
+        self.uncache(inode);
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            file.start_editing(current.unwrap_or_default());
+        }
+    }
+
+    // Write the content of a ConfigMap key file to the cluster
+    fn write_configmap_key(&mut self, inode: Inode) -> Result<(), libc::c_int> {
+        let Some(FileKind::ConfigMapKey {
+            namespace,
+            configmap,
+            key,
+            written: Some(content),
+            ..
+        }) = self
+            .get_file_by_inode(inode)
+            .map(|file| file.kind().clone())
+        else {
+            return Ok(());
+        };
+        // Binary values would have to be moved to binaryData
+        let Ok(value) = String::from_utf8(content) else {
+            log::error!("{} of ConfigMap {} is not UTF-8", key, configmap);
+            return Err(EINVAL);
+        };
+        self.patch_configmap_key(&namespace, &configmap, &key, Some(&value))?;
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            file.finish_editing();
+        }
+        Ok(())
+    }
+
+    // Set a key of a ConfigMap (or remove it with None) with a JSON merge patch
+    // The other keys are not touched, so concurrent changes to them are not overwritten.
+    fn patch_configmap_key(
+        &mut self,
+        namespace: &str,
+        configmap: &str,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<(), libc::c_int> {
+        if !self.quota.try_acquire() {
+            return Err(EDQUOT);
+        }
+        let context = self.context();
+        let path = format!("configmaps/{}/{}", namespace, configmap);
+        let previous = kubectl::get_object(&context, Some(namespace), "configmaps", configmap);
+        let patch = json!({ "data": { key: value } });
+        let outcome = kubectl::merge_patch(
             &context,
-            "",
+            Some(namespace),
+            "configmaps",
+            configmap,
+            &patch.to_string(),
         );
-        // Add root node
-        self.inode_table
-            .insert(root.inode, (root, vec![context_file.inode]));
-        // Add context node
-        self.inode_table
-            .insert(context_file.inode, (context_file, Vec::new()));
-        // Init kubernetes namespaces
-        for namespace in kubectl::namespaces(&context) {
-            let namespace_inode = self.build_resource_file(
-                &namespace,
-                ResourceType::Namespace,
-                CONTEXT_INODE,
-                &context,
-                &namespace,
+        if !outcome.success {
+            log::error!(
+                "Could not change {} of {}: {}",
+                key,
+                path,
+                outcome.report.trim()
             );
-            self.add_child_to_inode(CONTEXT_INODE, namespace_inode);
-            // Init kubernetes pods
-            for pod in kubectl::pods(&context, &namespace) {
-                let pod_inode = self.build_resource_file(
-                    &pod,
-                    ResourceType::Pod,
-                    namespace_inode,
-                    &context,
-                    &namespace,
+            return Err(EIO);
+        }
+        let operation = if value.is_some() { "set" } else { "remove" };
+        match previous {
+            Ok(previous) => self.journal.record(Entry::new(
+                &format!("{} {} {}", operation, path, key),
+                vec![previous],
+                Vec::new(),
+            )),
+            Err(error) => log::error!("Could not journal changing {}: {:?}", path, error),
+        }
+        Ok(())
+    }
+
+    // Remove the finalizers of a kubernetes resource (see remove-finalizers)
+    // This is logged as a warning, because it skips the cleanup that the finalizers guard.
+    fn remove_finalizers(
+        &mut self,
+        uid: u32,
+        resource_type: ResourceType,
+        namespace: Option<&str>,
+        name: &str,
+    ) -> bool {
+        let context = self.context();
+        let resource = resource_type.kubectl_name();
+        let path = match namespace {
+            Some(namespace) => format!("{}/{}/{}", resource, namespace, name),
+            None => format!("{}/{}", resource, name),
+        };
+        let previous = kubectl::get_object(&context, namespace, resource, name);
+        let finalizers = previous
+            .as_ref()
+            .map(|object| {
+                reports::array(object, "/metadata/finalizers")
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        log::warn!(
+            "User {} removes the finalizers [{}] of {} in context {}",
+            uid,
+            finalizers,
+            path,
+            context
+        );
+
+        let outcome = kubectl::remove_finalizers(&context, namespace, resource, name);
+        if !outcome.success {
+            log::error!(
+                "Could not remove the finalizers of {}: {}",
+                path,
+                outcome.report.trim()
+            );
+            return false;
+        }
+        match previous {
+            Ok(previous) => self.journal.record(Entry::new(
+                &format!("remove-finalizers {}", path),
+                vec![previous],
+                Vec::new(),
+            )),
+            Err(error) => log::error!("Could not journal removing finalizers: {:?}", error),
+        }
+        true
+    }
+
+    // Negotiate the configured readahead, write size and parallelism with the kernel
+    // Values that the kernel does not support are replaced by the nearest supported value.
+    fn configure_kernel(&self, kernel_config: &mut fuser::KernelConfig) {
+        let max_readahead = self.config.max_readahead;
+        if let Err(nearest) = kernel_config.set_max_readahead(max_readahead) {
+            log::warn!(
+                "max_readahead {} is not supported, using {}",
+                max_readahead,
+                nearest
+            );
+            let _ = kernel_config.set_max_readahead(nearest);
+        }
+        if let Some(max_write) = self.config.max_write {
+            if let Err(nearest) = kernel_config.set_max_write(max_write) {
+                log::warn!(
+                    "max_write {} is not supported, using {}",
+                    max_write,
+                    nearest
                 );
-                self.add_child_to_inode(namespace_inode, pod_inode);
+                let _ = kernel_config.set_max_write(nearest);
             }
         }
+        let max_background = self.config.max_background;
+        if let Err(nearest) = kernel_config.set_max_background(max_background) {
+            log::warn!(
+                "max_background {} is not supported, using {}",
+                max_background,
+                nearest
+            );
+            let _ = kernel_config.set_max_background(nearest);
+        }
     }
 
-    // Helper method to add kubernetes resources to the inode table
-    // This method also add a "definition" file to the parent along side the resource file
-    // that is created.
-    // The reasoning here is that every directory should have its definition file, which is
-    // basically just a kubectl describe call for the underlying kubernetes resource, next to it.
-    fn build_resource_file(
+    // Make the user who created a file its owner and honor the requested mode
+    fn set_creator(&mut self, inode: Inode, req: &Request<'_>, mode: u32, umask: u32) {
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            file.set_creator(req.uid(), req.gid(), mode, umask);
+        }
+    }
+
+    // Return the exec session of a container to write to it and start one if there is none
+    // or the previous one finished
+    // Only writes start sessions, so that reading exec.stdout (e.g. grep -r) does not start
+    // shells in every container and the remaining output of a finished session can be read.
+    fn exec_session(&mut self, key: &ContainerKey) -> Option<&mut ExecSession> {
+        let running = self
+            .exec_sessions
+            .get(key)
+            .is_some_and(|session| !session.is_finished());
+        if !running {
+            match ExecSession::start(&self.context(), key) {
+                Ok(session) => {
+                    self.exec_sessions.insert(key.clone(), session);
+                }
+                Err(error) => {
+                    log::error!("Could not start exec session: {}", error);
+                    return None;
+                }
+            }
+        }
+        self.exec_sessions.get_mut(key)
+    }
+
+    // Stop following a rollout once the last handle of its rollout-status file was released
+    fn release_rollout(&mut self, inode: Inode) {
+        let Some((rollout, handles)) = self.rollouts.get_mut(&inode) else {
+            return;
+        };
+        *handles = handles.saturating_sub(1);
+        if *handles == 0 {
+            // Finishing the stream stops the thread that polls the rollout
+            rollout.finish();
+            self.rollouts.remove(&inode);
+        }
+    }
+
+    // Return the event stream and start watching events if nobody did so far
+    fn event_stream(&mut self) -> Arc<StreamBuffer> {
+        if self.events.is_none() {
+            self.events = Some(events::start(&self.context()));
+        }
+        self.events.clone().unwrap()
+    }
+
+    // Undo the number of journaled changes that was written to the undo file
+    // Nothing (or only a newline) undoes the last change
+    fn undo(&mut self, data: &[u8], reply: ReplyWrite) {
+        let input = String::from_utf8_lossy(data);
+        let count = match input.trim() {
+            "" => Ok(1),
+            count => count.parse::<usize>(),
+        };
+        match count {
+            Ok(count) => {
+                let context = self.context();
+                if !self
+                    .journal
+                    .undo(count, &context, self.config.force_conflicts)
+                {
+                    log::error!("Could not undo all changes, see .k8sfs/undo");
+                }
+                reply.written(data.len() as u32);
+            }
+            Err(_) => {
+                log::error!("Invalid number of changes to undo {:?}", input.trim());
+                reply.error(EINVAL);
+            }
+        }
+    }
+
+    // Cancel the queued change whose number was written to pending-ops, "retry" replays the
+    // queue right away
+    fn pending_ops_control(&mut self, data: &[u8], reply: ReplyWrite) {
+        let input = String::from_utf8_lossy(data);
+        if input.trim() == "retry" {
+            self.replay_pending_ops(true);
+            reply.written(data.len() as u32);
+            return;
+        }
+        let cancelled = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| self.pending_ops.cancel(number));
+        match cancelled {
+            Some(_) => reply.written(data.len() as u32),
+            None => {
+                log::error!("There is no queued change {:?}", input.trim());
+                reply.error(EINVAL);
+            }
+        }
+    }
+
+    // Make the queued changes in order, until the API server turns out to be still unreachable
+    // This is attempted every RETRY_INTERVAL while the filesystem is used, or right away if
+    // forced.
+    fn replay_pending_ops(&mut self, force: bool) {
+        if self.pending_ops.is_empty() || !(force || self.pending_ops.is_due()) {
+            return;
+        }
+        let context = self.context();
+        let mut report = String::new();
+        while let Some((operation, queued)) = self.pending_ops.next() {
+            let description = operation.description();
+            log::info!("Replaying queued {}", description);
+            let outcome = match &operation {
+                Operation::Apply {
+                    name,
+                    manifest,
+                    opened,
+                } => {
+                    // The objects might have changed while the API server was unreachable
+                    let outcome = match self.check_manifest(manifest, name, opened.as_deref()) {
+                        Ok(()) => self.journaled_apply(manifest, &description),
+                        Err((report, _)) => kubectl::ApplyOutcome::new(false, report),
+                    };
+                    // The result file of the manifest tells what happened to it in the end
+                    let apply_inode = self
+                        .get_file_by_name(OsStr::new(APPLY_DIRECTORY_NAME), self.control_inode)
+                        .map(|file| file.inode);
+                    if let Some(apply_inode) =
+                        apply_inode.filter(|_| !kubectl::is_unreachable(&outcome.report))
+                    {
+                        self.set_sibling_content(
+                            apply_inode,
+                            name,
+                            APPLY_RESULT_SUFFIX,
+                            outcome.report.clone().into_bytes(),
+                        );
+                    }
+                    outcome
+                }
+                Operation::DeleteNamespace(namespace) => {
+                    let previous = kubectl::get_object(&context, None, "namespaces", namespace);
+                    match kubectl::delete_namespace(namespace, &context) {
+                        Ok(()) => {
+                            if let Ok(previous) = previous {
+                                self.journal.record(Entry::new(
+                                    &description,
+                                    vec![previous.clone()],
+                                    Vec::new(),
+                                ));
+                                self.trash_object(previous);
+                            }
+                            kubectl::ApplyOutcome::new(
+                                true,
+                                format!("namespace/{} deleted\n", namespace),
+                            )
+                        }
+                        Err(error) => kubectl::ApplyOutcome::new(false, format!("{}\n", error)),
+                    }
+                }
+            };
+            if kubectl::is_unreachable(&outcome.report) {
+                log::warn!("The API server is still unreachable");
+                report.push_str("The API server is still unreachable\n");
+                self.pending_ops.requeue((operation, queued));
+                break;
+            }
+            if !outcome.success {
+                log::error!(
+                    "Could not replay {}: {}",
+                    description,
+                    outcome.report.trim()
+                );
+            }
+            report.push_str(&format!("{}\n{}", description, outcome.report));
+        }
+        self.pending_ops.finish_replay(report);
+    }
+
+    // Preview the rule that was written to the prune file, or delete the previewed resources
+    // when "confirm" was written
+    fn prune(&mut self, data: &[u8], reply: ReplyWrite) {
+        let input = String::from_utf8_lossy(data);
+        let context = self.context();
+        if input.trim() != "confirm" {
+            let rule = match PruneRule::parse(input.trim()) {
+                Ok(rule) => rule,
+                Err(error) => {
+                    log::error!("{}", error);
+                    reply.error(EINVAL);
+                    return;
+                }
+            };
+            let objects = match rule.matches(&context) {
+                Ok(objects) => objects,
+                Err(error) => {
+                    log::error!("Could not preview {}: {}", rule, error);
+                    reply.error(error.errno());
+                    return;
+                }
+            };
+            // Resources that GitOps manages would be recreated by the next sync
+            let (managed, objects): (Vec<Value>, Vec<Value>) = objects
+                .into_iter()
+                .partition(|object| self.is_gitops_managed_object(object));
+            let skipped: Vec<String> = managed
+                .iter()
+                .map(|object| {
+                    format!(
+                        "{}/{}, it is managed by {}",
+                        reports::field(object, "/kind"),
+                        reports::field(object, "/metadata/name"),
+                        self.gitops_manager(object).unwrap_or_default()
+                    )
+                })
+                .collect();
+            let preview = self.pruner.preview(rule, objects, &skipped);
+            self.set_sibling_content(
+                self.control_inode,
+                PRUNE_FILE_NAME,
+                PREVIEW_SUFFIX,
+                preview.into_bytes(),
+            );
+            reply.written(data.len() as u32);
+            return;
+        }
+
+        let Some((rule, objects)) = self.pruner.take_preview() else {
+            log::error!("Nothing to prune, write a rule to preview first");
+            reply.error(EINVAL);
+            return;
+        };
+        if !objects.is_empty() && !self.quota.try_acquire() {
+            reply.error(EDQUOT);
+            return;
+        }
+        log::info!("Pruning {} ({} resources)", rule, objects.len());
+        let deleted = match objects.is_empty() {
+            true => Ok("Nothing to delete\n".to_string()),
+            false => kubectl::delete(&journal::object_list(&objects), &context),
+        };
+        match &deleted {
+            Ok(_) => {
+                self.journal.record(Entry::new(
+                    &format!("prune {}", rule),
+                    objects.clone(),
+                    Vec::new(),
+                ));
+                for object in objects {
+                    self.trash_object(object);
+                }
+            }
+            Err(error) => log::error!("Could not prune {}: {}", rule, error),
+        }
+        // Pods (e.g. of deleted jobs) disappear from the namespace right away
+        if let Some(namespace_inode) = self
+            .get_file_by_name(OsStr::new(rule.namespace()), self.context_inode)
+            .map(|file| file.inode)
+        {
+            self.refresh_namespace_pods(namespace_inode);
+        }
+        self.set_sibling_content(
+            self.control_inode,
+            PRUNE_FILE_NAME,
+            PREVIEW_SUFFIX,
+            Vec::new(),
+        );
+        match deleted {
+            Ok(report) => {
+                self.pruner.finish(format!("{}\n{}", rule, report));
+                reply.written(data.len() as u32);
+            }
+            Err(error) => {
+                self.pruner.finish(format!("{}\n{}\n", rule, error));
+                reply.error(error.errno());
+            }
+        }
+    }
+
+    // Remember the state of the objects of a manifest that is opened for writing
+    // This is only done for manifests that already contain objects (i.e. that are edited) and
+    // only once until the manifest is applied.
+    fn remember_opened_manifest(&mut self, inode: Inode) {
+        if self.opened_manifests.contains_key(&inode) {
+            return;
+        }
+        let manifest = match self.get_file_by_inode(inode).map(|file| file.kind()) {
+            Some(FileKind::Manifest(manifest)) if !manifest.is_empty() => manifest.clone(),
+            _ => return,
+        };
+        match kubectl::get_manifest_objects(&manifest, &self.context()) {
+            Ok(objects) => {
+                self.opened_manifests.insert(inode, objects);
+            }
+            Err(error) => log::debug!("Could not retrieve objects of {}: {:?}", inode, error),
+        }
+    }
+
+    // Give a manifest that is opened for writing its own buffer and return its file handle
+    // Other files are opened without a buffer (file handle 0).
+    fn open_manifest_handle(&mut self, inode: Inode) -> u64 {
+        let content = match self.get_file_by_inode(inode).map(|file| file.kind()) {
+            Some(FileKind::Manifest(content)) => content.clone(),
+            _ => return 0,
+        };
+        // Manifests that were truncated right before they were opened (O_TRUNC) and new
+        // template instances are applied even if nothing is written to them
+        let pending = self.pending_manifests.remove(&inode);
+        self.remember_opened_manifest(inode);
+        let objects = self.opened_manifests.remove(&inode).unwrap_or_default();
+        let fh = self.manifest_handles.open(inode, content, objects);
+        self.manifest_handles.get_mut(fh).unwrap().written = pending;
+        fh
+    }
+
+    // Apply the buffer of a file handle to the cluster
+    // Fails with ESTALE if the manifest was applied from another buffer since this one was opened
+    fn apply_manifest_handle(&mut self, fh: u64) -> Result<(), libc::c_int> {
+        let Some(handle) = self.manifest_handles.get_mut(fh) else {
+            return Ok(());
+        };
+        handle.written = false;
+        let (inode, content, objects) =
+            (handle.inode, handle.content.clone(), handle.objects.clone());
+        let Some((name, parent)) = self
+            .get_file_by_inode(inode)
+            .map(|file| (file.name.clone(), file.parent))
+        else {
+            return Err(ENOENT);
+        };
+        if self.manifest_handles.is_stale(fh) {
+            log::error!("Not applying {}, it was applied by another writer", name);
+            if self.is_drop_directory(parent) {
+                let report = format!(
+                    "Not applied, {} was applied by another writer since it was opened\n",
+                    name
+                );
+                self.set_sibling_content(parent, &name, APPLY_RESULT_SUFFIX, report.into_bytes());
+            }
+            return Err(ESTALE);
+        }
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            // The buffer is no larger than the file can be
+            let _ = file.truncate(0).and_then(|()| file.write(0, &content));
+        }
+        if !objects.is_empty() {
+            self.opened_manifests.insert(inode, objects);
+        }
+        self.pending_manifests.remove(&inode);
+        let result = self.apply_written_manifest(inode);
+        if result.is_ok() {
+            self.manifest_handles.applied(fh);
+        }
+        result
+    }
+
+    // Apply a manifest and record the previous state of its objects in the journal
+    fn journaled_apply(&mut self, manifest: &[u8], description: &str) -> kubectl::ApplyOutcome {
+        let context = self.context();
+        let previous = kubectl::get_manifest_objects(manifest, &context).unwrap_or_else(|error| {
+            log::error!("Could not journal {}: {:?}", description, error);
+            Vec::new()
+        });
+        let outcome = kubectl::apply(manifest, &context, self.config.force_conflicts);
+        if outcome.conflict {
+            log::error!("{}: fields are owned by another field manager", description);
+            for object in &previous {
+                diagnostics::record_apply_error(
+                    reports::field(object, "/kind"),
+                    reports::field(object, "/metadata/namespace"),
+                    reports::field(object, "/metadata/name"),
+                    &outcome.report,
+                );
+            }
+        }
+        let current = kubectl::get_manifest_objects(manifest, &context).unwrap_or_default();
+        self.add_applied_objects(&current);
+        // Failed applies are not journaled, they would push changes out of the journal
+        if outcome.success {
+            self.journal
+                .record(Entry::new(description, previous, current));
+        }
+
+        outcome
+    }
+
+    // Apply a manifest that was dropped into the apply directory and store the outcome
+    // in a result file next to it
+    // Fails with ESTALE if an object was changed by somebody else since the manifest was opened
+    fn apply_manifest(&mut self, inode: Inode) -> Result<(), libc::c_int> {
+        let (manifest, name, parent) = match self.get_file_by_inode(inode) {
+            Some(file) => (file.get_desc(), file.name.clone(), file.parent),
+            None => return Err(ENOENT),
+        };
+        let opened = self.opened_manifests.remove(&inode);
+        let check = self.check_manifest(&manifest, &name, opened.as_deref());
+
+        let (outcome, warnings, result) = if let Err((report, error)) = check {
+            if self.config.queue_offline && kubectl::is_unreachable(&report) {
+                self.queue_manifest(&name, manifest, opened)
+            } else {
+                log::error!("Not applying {}: {}", name, report.trim_end());
+                (report, Vec::new(), Err(error))
+            }
+        } else if !self.quota.try_acquire() {
+            let report = String::from("Not applied, too many changes within the last hour\n");
+            (report, Vec::new(), Err(EDQUOT))
+        } else if self.config.queue_offline && !self.pending_ops.is_empty() {
+            // Changes are made in the order they were made through the filesystem
+            self.queue_manifest(&name, manifest, opened)
+        } else {
+            log::info!("Applying {}", name);
+            let outcome = self.journaled_apply(&manifest, &format!("apply {}", name));
+            if self.config.queue_offline && kubectl::is_unreachable(&outcome.report) {
+                self.queue_manifest(&name, manifest, opened)
+            } else {
+                if !outcome.success {
+                    log::error!("Could not apply {}", name);
+                }
+                let result = match outcome.conflict {
+                    true => Err(EBUSY),
+                    false => Ok(()),
+                };
+                (outcome.report, outcome.warnings, result)
+            }
+        };
+
+        self.set_sibling_content(parent, &name, APPLY_RESULT_SUFFIX, outcome.into_bytes());
+        self.store_warnings(parent, &name, &warnings);
+
+        result
+    }
+
+    // Check the objects of a manifest as they are in the cluster right before it is applied
+    // (again when a queued manifest is replayed): they must not have changed since the manifest
+    // was opened for writing, be immutable or be managed by GitOps. The manifest is not applied
+    // unchecked if they can not be retrieved.
+    // Returns the content of the result file and the error if it must not be applied.
+    fn check_manifest(
+        &self,
+        manifest: &[u8],
+        name: &str,
+        opened: Option<&[Value]>,
+    ) -> Result<(), (String, libc::c_int)> {
+        let current_objects =
+            kubectl::get_manifest_objects(manifest, &self.context()).map_err(|error| {
+                let report = format!("Not applied, could not check its objects: {}\n", error);
+                (report, EIO)
+            })?;
+        let describe = |object: &Value| {
+            format!(
+                "{}/{}",
+                reports::field(object, "/kind"),
+                reports::field(object, "/metadata/name")
+            )
+        };
+
+        let stale_objects: String = current_objects
+            .iter()
+            .filter(|current| {
+                opened.unwrap_or_default().iter().any(|opened| {
+                    journal::same_object(opened, current)
+                        && reports::field(opened, "/metadata/resourceVersion")
+                            != reports::field(current, "/metadata/resourceVersion")
+                })
+            })
+            .map(|current| {
+                format!(
+                    "{} was changed since {} was opened\n",
+                    describe(current),
+                    name
+                )
+            })
+            .collect();
+        if !stale_objects.is_empty() {
+            return Err((stale_objects, ESTALE));
+        }
+
+        let immutable_objects: String = current_objects
+            .iter()
+            .filter(|current| reports::is_immutable(current))
+            .map(|current| format!("{} is immutable\n", describe(current)))
+            .collect();
+        if !immutable_objects.is_empty() {
+            return Err((immutable_objects, EROFS));
+        }
+
+        let managed_objects: String = current_objects
+            .iter()
+            .filter(|_| self.config.protect_gitops)
+            .filter_map(|current| {
+                Some(format!(
+                    "{} is managed by {} (see --protect-gitops)\n",
+                    describe(current),
+                    self.gitops_manager(current)?
+                ))
+            })
+            .collect();
+        if !managed_objects.is_empty() {
+            return Err((managed_objects, EROFS));
+        }
+        Ok(())
+    }
+
+    // Queue a manifest that can not be applied while the API server is unreachable
+    // The objects it had when it was opened are kept to check it again before it is replayed.
+    // Returns the content of the result file, the warnings and the result of the apply.
+    fn queue_manifest(
         &mut self,
         name: &str,
-        resource_type: ResourceType,
-        parent_inode: Inode,
-        context: &str,
-        namespace: &str,
-    ) -> Inode {
-        let inode = self.calculate_next_inode();
-        let mut children = Vec::new();
-        let file = ResourceFile::new(inode, parent_inode, name, resource_type, context, namespace);
-        let definition_file = file.create_definition_file(self.calculate_next_inode());
-        children.push(definition_file.inode);
-        self.inode_table
-            .insert(definition_file.inode, (definition_file, Vec::new()));
+        manifest: Vec<u8>,
+        opened: Option<Vec<Value>>,
+    ) -> (String, Vec<String>, Result<(), libc::c_int>) {
+        self.pending_ops.queue(Operation::Apply {
+            name: name.to_string(),
+            manifest,
+            opened,
+        });
+        let report = format!(
+            "Queued, the API server is unreachable (see {}/{})\n",
+            CONTROL_DIRECTORY_NAME, PENDING_OPS_FILE_NAME
+        );
+        (report, Vec::new(), Ok(()))
+    }
 
-        self.inode_table.insert(inode, (file, children));
+    // Write the warnings of the API server to a file next to the applied file and log them
+    // An existing warnings file is emptied if there were no warnings this time.
+    fn store_warnings(&mut self, parent: Inode, name: &str, warnings: &[String]) {
+        for warning in warnings {
+            log::warn!("Applying {}: {}", name, warning);
+        }
+        let warnings_name = format!("{}{}", name, APPLY_WARNINGS_SUFFIX);
+        let exists = self
+            .get_file_by_name(OsStr::new(&warnings_name), parent)
+            .is_some();
+        if exists || !warnings.is_empty() {
+            let content: String = warnings
+                .iter()
+                .map(|warning| format!("{}\n", warning))
+                .collect();
+            self.set_sibling_content(parent, name, APPLY_WARNINGS_SUFFIX, content.into_bytes());
+        }
+    }
 
-        inode
+    // Set the content of the file "<name><suffix>" in the directory, creating it if necessary
+    fn set_sibling_content(&mut self, parent: Inode, name: &str, suffix: &str, content: Vec<u8>) {
+        let sibling_name = format!("{}{}", name, suffix);
+        let sibling_inode = self
+            .get_file_by_name(OsStr::new(&sibling_name), parent)
+            .map(|file| file.inode);
+        let sibling_inode = match sibling_inode {
+            Some(sibling_inode) => sibling_inode,
+            None => self.build_virtual_file(parent, &sibling_name, FileKind::Static(Vec::new())),
+        };
+        if let Some((sibling_file, _)) = self.inode_table.get_mut(&sibling_inode) {
+            sibling_file.set_content(content);
+        }
+    }
+
+    // Return the content of a regular file
+    // Content that is expensive to generate is served from the cache if possible
+    fn file_content(&mut self, inode: Inode) -> Option<Vec<u8>> {
+        self.sync_cache();
+        let (file, _) = self.inode_table.get(&inode)?;
+        let cache_key = file.cache_key();
+        if let Some(key) = &cache_key {
+            if let Some(content) = self.cache.lock().unwrap().get(key) {
+                return Some(content);
+            }
+        }
+
+        let content = match file.kind() {
+            FileKind::CacheStats => self.cache.lock().unwrap().stats().into_bytes(),
+            FileKind::Stats => format!(
+                "# Operations\n{}\n# Cache\n{}\n# API\nerrors {}\n",
+                self.stats.report(),
+                self.cache.lock().unwrap().stats(),
+                kubectl::api_errors()
+            )
+            .into_bytes(),
+            FileKind::ApiUsage => usage::report().into_bytes(),
+            FileKind::StatsReset | FileKind::RemoveFinalizers { .. } => Vec::new(),
+            FileKind::Undo => self.journal.summary().into_bytes(),
+            FileKind::PendingOps => self.pending_ops.summary().into_bytes(),
+            FileKind::Prune => self.pruner.summary().into_bytes(),
+            FileKind::LastError {
+                resource_type,
+                namespace,
+                name,
+            } => diagnostics::last_error(*resource_type, namespace.as_deref(), name).into_bytes(),
+            FileKind::RestartHistory(key) => self
+                .watcher
+                .as_ref()
+                .map(|watcher| watcher.restart_history(key))
+                .unwrap_or_default()
+                .into_bytes(),
+            FileKind::ConfigMapKey {
+                written: Some(content),
+                ..
+            } => content.clone(),
+            FileKind::ConfigMapKey {
+                namespace,
+                configmap,
+                key,
+                written: None,
+                ..
+            } => {
+                match kubectl::get_object(&self.context(), Some(namespace), "configmaps", configmap)
+                {
+                    Ok(object) => reports::field(&object, &format!("/data/{}", key))
+                        .as_bytes()
+                        .to_vec(),
+                    Err(error) => {
+                        log::error!("Could not get ConfigMap {}: {:?}", configmap, error);
+                        Vec::new()
+                    }
+                }
+            }
+            _ => file.get_desc(),
+        };
+        let content = file.encoding().encode(content);
+        if let Some(key) = cache_key {
+            self.cache.lock().unwrap().insert(&key, content.clone());
+        }
+
+        Some(content)
+    }
+
+    // Drop content of files that changed and store descriptions that were prefetched
+    fn sync_cache(&mut self) {
+        for stale in self.changes.take_stale() {
+            self.uncache(stale);
+        }
+        let prefetched = self
+            .prefetcher
+            .as_mut()
+            .map(Prefetcher::take_results)
+            .unwrap_or_default();
+        for prefetched in prefetched {
+            // Resources can disappear while their description is fetched
+            if let Some(key) = self.cache_key(prefetched.inode) {
+                self.cache.lock().unwrap().insert_fetched_at(
+                    &key,
+                    prefetched.content,
+                    prefetched.fetched_at,
+                );
+            }
+        }
+    }
+
+    // Return the key that the content of a file is stored under in the content cache
+    fn cache_key(&self, inode: Inode) -> Option<CacheKey> {
+        self.get_file_by_inode(inode)?.cache_key()
+    }
+
+    // Keep the cached content of a file while it is open
+    fn cache_opened(&mut self, inode: Inode) {
+        if let Some(key) = self.cache_key(inode) {
+            self.cache.lock().unwrap().opened(&key);
+        }
+    }
+
+    // Drop the cached content of a file
+    fn uncache(&mut self, inode: Inode) {
+        if let Some(key) = self.cache_key(inode) {
+            self.cache.lock().unwrap().remove(&key);
+        }
+    }
+
+    // Return the part of the content of a file that a read at offset returns
+    // Cached content is only copied as far as it is read.
+    fn read_content(&mut self, inode: Inode, offset: u64, size: u32) -> Option<Vec<u8>> {
+        self.sync_cache();
+        if let Some(key) = self.cache_key(inode) {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.contains(&key) {
+                return cache.get_range(&key, offset, size);
+            }
+        }
+        let content = self.file_content(inode)?;
+        Some(cache::content_range(&content, offset, size).to_vec())
+    }
+
+    // Return the size of the content of a file
+    // The size of cached content is used as is, so a getattr that is followed by a read
+    // does not fetch the content twice.
+    fn content_size(&mut self, inode: Inode) -> Option<u64> {
+        self.sync_cache();
+        if let Some(key) = self.cache_key(inode) {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.contains(&key) {
+                return cache.get_size(&key).map(|size| size as u64);
+            }
+        }
+        self.file_content(inode).map(|content| content.len() as u64)
+    }
+
+    // Return the attributes of a file
+    // The file size of regular files is determined from their (cached) content
+    fn file_attrs(&mut self, inode: Inode) -> Option<FileAttr> {
+        let file = self.get_file_by_inode(inode)?;
+        if file.filetype() != FileType::RegularFile {
+            let mut attrs = file.fileattrs();
+            attrs.mtime = self.modification_time(inode);
+            if attrs.kind == FileType::Directory {
+                attrs.nlink = self.directory_link_count(inode);
+            }
+            return Some(attrs);
+        }
+        // Exec sessions and rollouts are streams without a size, they are read with direct IO
+        if matches!(
+            file.kind(),
+            FileKind::ExecStdin(_) | FileKind::ExecStdout(_) | FileKind::RolloutStatus { .. }
+        ) {
+            return Some(file.fileattrs_with_size(0));
+        }
+        // The size of the event stream grows with every event, so "tail -f" starts reading
+        // at the most recent events
+        if *file.kind() == FileKind::EventStream {
+            let size = self.events.as_ref().map_or(0, |events| events.end_offset());
+            return Some(file.fileattrs_with_size(size as u64));
+        }
+        let size = self.content_size(inode)?;
+
+        let mut attrs = self.get_file_by_inode(inode)?.fileattrs_with_size(size);
+        attrs.mtime = self.modification_time(inode);
+        Some(attrs)
+    }
+
+    // Return the number of hard links of a directory
+    // Like on real filesystems, this is one for the entry in the parent, one for "." and one
+    // for the ".." entry of every subdirectory.
+    fn directory_link_count(&self, inode: Inode) -> u32 {
+        let subdirectories = self
+            .inode_table
+            .get(&inode)
+            .map(|(_, children)| {
+                children
+                    .iter()
+                    .filter_map(|child| self.get_file_by_inode(*child))
+                    .filter(|child| child.filetype() == FileType::Directory)
+                    .count()
+            })
+            .unwrap_or(0);
+        // Pods are directories as well, their estimated number is used until they are listed
+        let deferred = self
+            .deferred_pods
+            .get(&inode)
+            .map_or(0, |(_, estimate)| *estimate);
+        2u32.saturating_add(u32::try_from(subdirectories).unwrap_or(u32::MAX))
+            .saturating_add(u32::try_from(deferred).unwrap_or(u32::MAX))
+    }
+
+    // Make the directory at the configured root path the root of the filesystem
+    // The kernel always uses CONTEXT_INODE for the mount point, so the directory swaps its
+    // inode with the context directory.
+    fn mount_root_path(&mut self) -> Result<(), libc::c_int> {
+        let mut root_inode = CONTEXT_INODE;
+        for component in &self.config.root_path {
+            root_inode = match self.get_file_by_name(OsStr::new(component), root_inode) {
+                Some(file) if file.filetype() == FileType::Directory => file.inode,
+                _ => {
+                    log::error!("Root path {:?} does not exist", self.config.root_path);
+                    return Err(ENOENT);
+                }
+            };
+        }
+        log::info!("Mounting {:?} as the root", self.config.root_path);
+
+        let swap = |inode: Inode| match inode {
+            CONTEXT_INODE => root_inode,
+            inode if inode == root_inode => CONTEXT_INODE,
+            inode => inode,
+        };
+        let inode_table = std::mem::take(&mut self.inode_table);
+        for (inode, (mut file, children)) in inode_table {
+            file.inode = swap(file.inode);
+            file.parent = swap(file.parent);
+            let children = children.into_iter().map(swap).collect();
+            self.inode_table.insert(swap(inode), (file, children));
+        }
+        self.control_inode = swap(self.control_inode);
+        self.context_inode = root_inode;
+
+        Ok(())
+    }
+
+    // Return the time of the most recent change of a file, which for directories includes
+    // the changes of the kubernetes resources they contain
+    // This makes "ls -lt" show where the most recent activity happened.
+    fn modification_time(&self, inode: Inode) -> SystemTime {
+        let Some((file, children)) = self.inode_table.get(&inode) else {
+            return SystemTime::UNIX_EPOCH;
+        };
+        // Files of pods that the watcher saw changing are newer than the last fetched state
+        let fetched_time = file.fileattrs_with_size(0).mtime;
+        let own_time = self
+            .changes
+            .modification_time(inode)
+            .map_or(fetched_time, |changed| max(changed, fetched_time));
+        children
+            .iter()
+            .filter(|child| {
+                self.get_file_by_inode(**child)
+                    .map(|child| *child.kind() == FileKind::Resource)
+                    .unwrap_or(false)
+            })
+            .map(|child| self.modification_time(*child))
+            .fold(own_time, max)
+    }
+
+    // Return the name of the kubernetes context that is mounted
+    fn context(&self) -> String {
+        self.inode_table
+            .get(&self.context_inode)
+            .map(|(file, _)| file.name.clone())
+            .unwrap_or_default()
     }
 
     // Helper method to add the inode of a "child" to the children Vec of the parent
@@ -180,11 +3484,11 @@ impl K8sFS {
         file
     }
 
-    // Delete a file from the inode table
-    // This method also makes sure that the file is from its parent
+    // Delete a file and all of its children from the inode table
+    // This method also makes sure that the file is removed from its parent
     fn clean_up_inode(&mut self, inode: Inode, parent: Inode) {
         log::debug!("Deleting file with inode {}", inode);
-        self.inode_table.remove(&inode);
+        self.remove_inode_recursively(inode);
         if let Some((_, parent_children)) = self.inode_table.get_mut(&parent) {
             if let Some(index) = parent_children.iter().position(|&x| x == inode) {
                 parent_children.remove(index);
@@ -199,34 +3503,208 @@ impl K8sFS {
             log::error!("Parent with inode {} could not be found!!!", parent);
         }
     }
+
+    // Return true if files that are written to the directory should be applied to the cluster
+    fn is_drop_directory(&self, inode: Inode) -> bool {
+        self.inode_table
+            .get(&inode)
+            .map(|(file, _)| *file.kind() == FileKind::DropDirectory)
+            .unwrap_or(false)
+    }
+
+    // Fetch the descriptions of the resources in a directory that is listed in the background,
+    // because they are likely to be opened next (see --prefetch)
+    fn prefetch_descriptions(&mut self, inode: Inode) {
+        let Some((directory, children)) = self.inode_table.get(&inode) else {
+            return;
+        };
+        let enabled = match self.config.prefetch {
+            PrefetchPolicy::Off => false,
+            PrefetchPolicy::Namespaces => {
+                *directory.kind() == FileKind::Resource
+                    && directory.resource_type() == ResourceType::Namespace
+            }
+            PrefetchPolicy::All => true,
+        };
+        if !enabled {
+            return;
+        }
+
+        // Definition files of the directory itself and of the resources in it
+        let descriptions: Vec<(Inode, Description)> = children
+            .iter()
+            .filter_map(|child| self.inode_table.get(child))
+            .flat_map(|(child, grandchildren)| {
+                let definitions = if *child.kind() == FileKind::Resource {
+                    grandchildren.as_slice()
+                } else {
+                    &[]
+                };
+                std::iter::once(&child.inode).chain(definitions)
+            })
+            .filter(|inode| {
+                self.cache_key(**inode)
+                    .is_some_and(|key| !self.cache.lock().unwrap().contains(&key))
+            })
+            .filter_map(|inode| {
+                let description = self.inode_table.get(inode)?.0.description()?;
+                Some((*inode, description))
+            })
+            .collect();
+        let Some(prefetcher) = self.prefetcher.as_mut() else {
+            return;
+        };
+        for (inode, description) in descriptions {
+            if !prefetcher.request(inode, description) {
+                log::debug!("Prefetch queue is full, skipping the remaining descriptions");
+                break;
+            }
+        }
+    }
+
+    // Helper method to remove a file and all of its children from the inode table
+    fn remove_inode_recursively(&mut self, inode: Inode) {
+        self.uncache(inode);
+        if let Some((_, children)) = self.inode_table.remove(&inode) {
+            for child in children {
+                self.remove_inode_recursively(child);
+            }
+        }
+    }
 }
 
 impl Filesystem for K8sFS {
     fn init(
         &mut self,
         _req: &Request<'_>,
-        _config: &mut fuser::KernelConfig,
+        kernel_config: &mut fuser::KernelConfig,
     ) -> Result<(), libc::c_int> {
+        self.configure_kernel(kernel_config);
+        if self.initialized {
+            log::info!("Remounted with the previous inode table and cache");
+            return Ok(());
+        }
         self.initialize_inode_table();
+        if !self.config.root_path.is_empty() {
+            self.mount_root_path()?;
+        }
+        let context = self.context();
+        version::warn_about_skew(&context);
+        let watcher = self.watcher.get_or_insert_with(|| Watcher::start(&context));
+        let changes = self.changes.clone();
+        self.change_listener = Some(watcher.on_change(move |key, time| changes.changed(key, time)));
+        if self.config.prefetch != PrefetchPolicy::Off {
+            self.prefetcher = Some(Prefetcher::start());
+        }
+        self.initialized = true;
         Ok(())
     }
 
+    fn destroy(&mut self) {
+        self.activity.mark_destroyed();
+        if let Some(recovery) = self.recovery.take() {
+            let placeholder = K8sFS::new(self.config.clone());
+            *recovery.lock().unwrap() = Some(std::mem::replace(self, placeholder));
+            return;
+        }
+        // The watcher can be shared with other mounts of the daemon, which keep using it
+        if let (Some(watcher), Some(listener)) = (&self.watcher, self.change_listener.take()) {
+            watcher.remove_listener(listener);
+        }
+    }
+
     fn lookup(&mut self, _req: &Request<'_>, parent: Inode, name: &OsStr, reply: ReplyEntry) {
+        self.activity.touch();
+        let _timer = self.stats.time("lookup");
+        if ignore::is_ignored(&self.config.ignore_patterns, &name.to_string_lossy()) {
+            reply.error(ENOENT);
+            return;
+        }
         log::debug!(r#"Searching for file with the name "{:?}""#, name);
 
         // We could check access here or do other checks
 
-        if let Some(file) = self.get_file_by_name(name, parent) {
-            reply.entry(&TTL, &file.fileattrs(), 0);
+        // Query and node log directories are filled lazily, so they have to be populated
+        // before we can search for a file in them
+        if self.is_lazy_directory(parent) && self.inode_table.get(&parent).unwrap().1.is_empty() {
+            self.refresh_lazy_directory(parent);
+        }
+        // Only the looked up pod is retrieved from namespaces that are listed page by page
+        if self.is_paged_namespace(parent) {
+            self.build_deferred_pod(parent, name);
+        } else {
+            self.build_deferred_pods(parent);
+        }
+        if parent == self.trash_inode {
+            self.expire_trash();
+        }
+        let inode = self
+            .build_by_ip_entry(parent, name)
+            .or_else(|| self.get_file_by_name(name, parent).map(|file| file.inode))
+            .or_else(|| self.build_formatted_definition(parent, name))
+            .or_else(|| self.build_query_directory(parent, name))
+            .or_else(|| self.build_can_i_entry(parent, name))
+            .or_else(|| self.build_service_proxy_entry(parent, name));
+        if let Some(attrs) = inode.and_then(|inode| self.file_attrs(inode)) {
+            reply.entry(&TTL, &attrs, 0);
         } else {
             reply.error(ENOENT);
         }
     }
+    fn getxattr(
+        &mut self,
+        _req: &Request<'_>,
+        inode: Inode,
+        name: &OsStr,
+        size: u32,
+        reply: ReplyXattr,
+    ) {
+        self.activity.touch();
+        let _timer = self.stats.time("getxattr");
+        let is_definition = self
+            .get_file_by_inode(inode)
+            .is_some_and(|file| *file.kind() == FileKind::Definition);
+        if !is_definition || name != HASH_XATTR_NAME {
+            reply.error(ENODATA);
+            return;
+        }
+        let resource_version = self
+            .get_file_by_inode(inode)
+            .and_then(ResourceFile::resource_version);
+        let (Some(content), Some(resource_version)) = (self.file_content(inode), resource_version)
+        else {
+            reply.error(EIO);
+            return;
+        };
+        reply_xattr(
+            xattr::hash_value(&content, &resource_version).as_bytes(),
+            size,
+            reply,
+        );
+    }
+
+    fn listxattr(&mut self, _req: &Request<'_>, inode: Inode, size: u32, reply: ReplyXattr) {
+        self.activity.touch();
+        let _timer = self.stats.time("listxattr");
+        let names = match self.get_file_by_inode(inode).map(|file| file.kind()) {
+            Some(FileKind::Definition) => xattr::name_list(&[HASH_XATTR_NAME]),
+            Some(_) => Vec::new(),
+            None => {
+                reply.error(ENOENT);
+                return;
+            }
+        };
+        reply_xattr(&names, size, reply);
+    }
+
     fn getattr(&mut self, _req: &Request, inode: Inode, reply: ReplyAttr) {
+        self.activity.touch();
+        let _timer = self.stats.time("getattr");
         log::debug!("Getting attributes for file with inode {}", inode);
+        self.replay_pending_ops(false);
 
-        if let Some(file) = self.get_file_by_inode(inode) {
-            reply.attr(&TTL, &file.fileattrs());
+        if let Some(attrs) = self.file_attrs(inode) {
+            reply.attr(&TTL, &attrs);
         } else {
             reply.error(ENOENT);
         }
@@ -234,45 +3712,59 @@ impl Filesystem for K8sFS {
 
     fn mkdir(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &OsStr,
-        _mode: u32,
-        _umask: u32,
+        mode: u32,
+        umask: u32,
         reply: ReplyEntry,
     ) {
-        if parent == CONTEXT_INODE {
+        self.activity.touch();
+        let _timer = self.stats.time("mkdir");
+        if parent == self.context_inode {
+            if !self.quota.try_acquire() {
+                reply.error(EDQUOT);
+                return;
+            }
             let context = &self
                 .inode_table
-                .get(&CONTEXT_INODE)
+                .get(&self.context_inode)
                 .unwrap()
                 .0
                 .name
                 .to_string();
-            if !kubectl::create_namespace(&name.to_string_lossy(), context) {
-                // TODO: Find a better error code
-                reply.error(EPERM);
-                return;
-            }
-            // Create namespace
+            let namespace = match kubectl::create_namespace(&name.to_string_lossy(), context) {
+                Ok(namespace) => namespace,
+                Err(error) => {
+                    log::error!("Could not create namespace {:?}: {}", name, error);
+                    reply.error(error.errno());
+                    return;
+                }
+            };
+            let created = json!({
+                "apiVersion": "v1",
+                "kind": "Namespace",
+                "metadata": { "name": namespace },
+            });
+            self.journal.record(Entry::new(
+                &format!("mkdir {}", namespace),
+                Vec::new(),
+                vec![created],
+            ));
+            // Add the namespace that was returned by the API server right away, so that
+            // it is visible without waiting for the inode table to be rebuilt
             let namespace_inode = self.build_resource_file(
-                &name.to_string_lossy(),
+                &namespace,
                 ResourceType::Namespace,
-                CONTEXT_INODE,
+                self.context_inode,
                 context,
-                &name.to_string_lossy(),
-            );
-            self.add_child_to_inode(CONTEXT_INODE, namespace_inode);
-            reply.entry(
-                &TTL,
-                &self
-                    .inode_table
-                    .get(&namespace_inode)
-                    .unwrap()
-                    .0
-                    .fileattrs(),
-                0,
+                &namespace,
+                None,
             );
+            self.add_child_to_inode(self.context_inode, namespace_inode);
+            self.set_creator(namespace_inode, req, mode, umask);
+            self.build_namespace_files(namespace_inode, context, &namespace);
+            reply.entry(&TTL, &self.file_attrs(namespace_inode).unwrap(), 0);
         } else {
             log::error!("Directories are only allowed to be created under the root directory.");
             reply.error(EPERM);
@@ -280,34 +3772,177 @@ impl Filesystem for K8sFS {
     }
 
     // TODO: Delete a pod
-    // fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: fuser::ReplyEmpty) {
-    // }
-
-    fn rmdir(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
-        if parent == CONTEXT_INODE {
-            let mut inode_to_delete = 0;
-            let mut inode_to_delete_parent = 0;
-            if let Some(file) = self.get_file_by_name(name, parent) {
-                if !file.delete() {
-                    // TODO: Find a better error code
-                    reply.error(EPERM);
-                    return;
+    // Currently only files in the apply and trash directories can be deleted
+    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        self.activity.touch();
+        let _timer = self.stats.time("unlink");
+        if self.is_immutable(parent) || self.is_gitops_managed(parent) {
+            reply.error(EROFS);
+            return;
+        }
+        if let Some(FileKind::ConfigMap {
+            namespace,
+            name: configmap,
+            ..
+        }) = self
+            .get_file_by_inode(parent)
+            .map(|file| file.kind().clone())
+        {
+            let Some(inode) = self.get_file_by_name(name, parent).map(|file| file.inode) else {
+                reply.error(ENOENT);
+                return;
+            };
+            let key = name.to_string_lossy();
+            match self.patch_configmap_key(&namespace, &configmap, &key, None) {
+                Ok(()) => {
+                    self.pending_manifests.remove(&inode);
+                    self.clean_up_inode(inode, parent);
+                    reply.ok();
                 }
-
-                inode_to_delete = file.inode;
-                inode_to_delete_parent = file.parent;
-            } else {
-                log::debug!("File '{}' was already deleted", name.to_string_lossy());
-            }
-
-            if inode_to_delete > 0 && parent > 0 {
-                self.clean_up_inode(inode_to_delete, inode_to_delete_parent);
+                Err(error) => reply.error(error),
             }
+            return;
+        }
+        if parent == self.trash_inode {
+            let Some(inode) = self.get_file_by_name(name, parent).map(|file| file.inode) else {
+                reply.error(ENOENT);
+                return;
+            };
+            self.trash.remove(&name.to_string_lossy());
+            self.clean_up_inode(inode, parent);
+            reply.ok();
+            return;
+        }
+        if !self.is_drop_directory(parent) {
+            log::error!("Only files in the apply and trash directories can be deleted.");
+            reply.error(EPERM);
+            return;
+        }
 
+        if let Some(inode) = self.get_file_by_name(name, parent).map(|file| file.inode) {
+            self.pending_manifests.remove(&inode);
+            self.clean_up_inode(inode, parent);
             reply.ok();
         } else {
+            reply.error(ENOENT);
+        }
+    }
+
+    fn rmdir(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        self.activity.touch();
+        let _timer = self.stats.time("rmdir");
+        if parent != self.context_inode {
             log::error!("Directories are only allowed to be deleted under the root directory.");
             reply.error(EPERM);
+            return;
+        }
+        let Some(file) = self.get_file_by_name(name, parent) else {
+            log::debug!("File '{}' was already deleted", name.to_string_lossy());
+            reply.ok();
+            return;
+        };
+        let (inode, file_parent, namespace) = (file.inode, file.parent, file.name.clone());
+        let context = self.context();
+        let previous = kubectl::get_object(&context, None, "namespaces", &namespace);
+        if previous
+            .as_ref()
+            .is_ok_and(|previous| self.is_gitops_managed_object(previous))
+        {
+            reply.error(EROFS);
+            return;
+        }
+
+        // rmdir of a namespace that is already terminating finalizes it
+        let terminating_since = previous.as_ref().ok().and_then(|previous| {
+            parse_timestamp(reports::field(previous, "/metadata/deletionTimestamp"))
+        });
+        if let Some(terminating_since) = terminating_since {
+            let terminating_for = terminating_since.elapsed().unwrap_or_default();
+            if !self.config.force_delete && terminating_for < FORCE_DELETE_AFTER {
+                log::info!(
+                    "Namespace {} is terminating for {}s, it is finalized by rmdir after {}s",
+                    namespace,
+                    terminating_for.as_secs(),
+                    FORCE_DELETE_AFTER.as_secs()
+                );
+                reply.error(EBUSY);
+                return;
+            }
+            if !self.quota.try_acquire() {
+                reply.error(EDQUOT);
+                return;
+            }
+            log::warn!(
+                "Finalizing namespace {} which is terminating for {}s",
+                namespace,
+                terminating_for.as_secs()
+            );
+            let outcome = kubectl::finalize_namespace(&context, &namespace);
+            if !outcome.success {
+                log::error!(
+                    "Could not finalize namespace {}: {}",
+                    namespace,
+                    outcome.report.trim()
+                );
+                reply.error(EIO);
+                return;
+            }
+            self.clean_up_inode(inode, file_parent);
+            reply.ok();
+            return;
+        }
+
+        if !self.quota.try_acquire() {
+            reply.error(EDQUOT);
+            return;
+        }
+        // Namespaces are removed from the filesystem right away if the deletion is queued
+        if self.config.queue_offline && !self.pending_ops.is_empty() {
+            self.pending_ops
+                .queue(Operation::DeleteNamespace(namespace));
+            self.clean_up_inode(inode, file_parent);
+            reply.ok();
+            return;
+        }
+        if let Err(error) = self.get_file_by_inode(inode).unwrap().delete() {
+            if self.config.queue_offline && kubectl::is_unreachable(&error.to_string()) {
+                self.pending_ops
+                    .queue(Operation::DeleteNamespace(namespace));
+                self.clean_up_inode(inode, file_parent);
+                reply.ok();
+                return;
+            }
+            log::error!("Could not delete namespace {}: {}", namespace, error);
+            reply.error(error.errno());
+            return;
+        }
+        match previous {
+            Ok(previous) => {
+                self.journal.record(Entry::new(
+                    &format!("rmdir {}", namespace),
+                    vec![previous.clone()],
+                    Vec::new(),
+                ));
+                self.trash_object(previous);
+            }
+            Err(error) => log::error!("Could not journal deletion: {:?}", error),
+        }
+        // Namespaces with content keep terminating in the background and stay visible with
+        // their .termination-status until they are gone
+        if kubectl::get_object(&context, None, "namespaces", &namespace).is_err() {
+            self.clean_up_inode(inode, file_parent);
+        }
+
+        reply.ok();
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, inode: Inode, reply: ReplyData) {
+        self.activity.touch();
+        let _timer = self.stats.time("readlink");
+        match self.get_file_by_inode(inode).map(|file| file.kind()) {
+            Some(FileKind::Symlink(target)) => reply.data(target.as_bytes()),
+            Some(_) => reply.error(EINVAL),
+            None => reply.error(ENOENT),
         }
     }
 
@@ -328,67 +3963,491 @@ impl Filesystem for K8sFS {
         &mut self,
         _req: &Request<'_>,
         inode: Inode,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         size: u32,
         _flags: i32,
         _lock_owner: Option<u64>,
         reply: ReplyData,
     ) {
+        self.activity.touch();
+        let _timer = self.stats.time("read");
         log::debug!("Trying to read {}", inode);
 
-        if let Some(file) = self.get_file_by_inode(inode) {
-            // We must not read more than size
-            // We should either read size or the file size if it is actually smaller
-            let read_size = min(size as u64, file.size().saturating_sub(offset as u64));
-            reply.data(
-                file.get_desc()[offset as usize..]
-                    .take(read_size)
-                    .into_inner(),
-            );
+        if let Some(handle) = self.manifest_handles.get(fh) {
+            reply.data(cache::content_range(
+                &handle.content,
+                offset.max(0) as u64,
+                size,
+            ));
+            return;
+        }
+        let stream = match self
+            .get_file_by_inode(inode)
+            .map(|file| file.kind().clone())
+        {
+            Some(FileKind::ExecStdout(key)) => {
+                // Without a session there is no output
+                let Some(session) = self.exec_sessions.get(&key) else {
+                    reply.data(&[]);
+                    return;
+                };
+                Some(Some(session.output()))
+            }
+            Some(FileKind::EventStream) => Some(Some(self.event_stream())),
+            Some(FileKind::RolloutStatus { .. }) => Some(
+                self.rollouts
+                    .get(&inode)
+                    .map(|(rollout, _)| rollout.clone()),
+            ),
+            _ => None,
+        };
+        if let Some(output) = stream {
+            let Some(output) = output else {
+                reply.error(EIO);
+                return;
+            };
+            let (offset, size) = (offset as usize, size as usize);
+            let available = output.is_available(offset);
+            // Streams that ended with an error (e.g. failed rollouts) fail at the end
+            let respond = move |reply: ReplyData| {
+                let data = output.read(offset, size);
+                if data.is_empty() && output.has_failed_at(offset) {
+                    reply.error(EIO);
+                } else {
+                    reply.data(&data);
+                }
+            };
+            if available {
+                respond(reply);
+            } else {
+                // Waiting in the session loop would block every other operation (e.g. the
+                // write to exec.stdin that produces the output), so the reply is sent from
+                // another thread
+                thread::spawn(move || respond(reply));
+            }
+            return;
+        }
+
+        // Reads at or beyond the end of the file return no data
+        if let Some(data) = self.read_content(inode, offset.max(0) as u64, size) {
+            reply.data(&data);
         } else {
             reply.error(ENOENT);
         }
     }
 
+    fn open(&mut self, _req: &Request<'_>, inode: Inode, flags: i32, reply: ReplyOpen) {
+        self.activity.touch();
+        let _timer = self.stats.time("open");
+        let fh = match flags & O_ACCMODE != O_RDONLY {
+            true => self.open_manifest_handle(inode),
+            false => 0,
+        };
+        // Opening a file with O_DIRECT fetches its content again instead of using the cache
+        // (e.g. "dd if=status iflag=direct" for up-to-the-second data)
+        let bypass_cache = flags & O_DIRECT != 0;
+        if bypass_cache {
+            log::debug!("Bypassing the cache for {}", inode);
+            self.uncache(inode);
+        }
+        match self.get_file_by_inode(inode).map(|file| file.kind()) {
+            // The size of streams is unknown, so the kernel must not rely on it
+            Some(FileKind::ExecStdin(_) | FileKind::ExecStdout(_)) => {
+                reply.opened(0, FOPEN_DIRECT_IO)
+            }
+            Some(FileKind::RolloutStatus {
+                namespace,
+                resource,
+                name,
+            }) => {
+                let handles = match self.rollouts.get(&inode) {
+                    Some((rollout, handles)) if !rollout.is_finished() => {
+                        (rollout.clone(), handles + 1)
+                    }
+                    // Handles that are still open read the new rollout as well
+                    previous => {
+                        let rollout = rollout::start(&self.context(), namespace, resource, name);
+                        (rollout, previous.map_or(0, |(_, handles)| *handles) + 1)
+                    }
+                };
+                self.rollouts.insert(inode, handles);
+                reply.opened(0, FOPEN_DIRECT_IO)
+            }
+            Some(FileKind::EventStream) => {
+                self.event_stream();
+                reply.opened(0, FOPEN_DIRECT_IO)
+            }
+            // The kernel must not serve the content from its page cache either
+            // Writers of the same manifest do not share a page cache, every one has a buffer.
+            Some(_) if bypass_cache || fh != 0 => {
+                self.cache_opened(inode);
+                reply.opened(fh, FOPEN_DIRECT_IO)
+            }
+            Some(_) => {
+                self.cache_opened(inode);
+                reply.opened(0, 0)
+            }
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn lseek(
+        &mut self,
+        _req: &Request<'_>,
+        inode: Inode,
+        _fh: u64,
+        offset: i64,
+        whence: i32,
+        reply: ReplyLseek,
+    ) {
+        self.activity.touch();
+        let _timer = self.stats.time("lseek");
+        // The length is known from the (cached) content, so seeking to the end does not
+        // require reading the whole file first
+        let Some(size) = self.file_attrs(inode).map(|attrs| attrs.size as i64) else {
+            reply.error(ENOENT);
+            return;
+        };
+
+        // Files do not have holes, all data is in one block from 0 to size
+        let new_offset = match whence {
+            SEEK_SET | SEEK_CUR => offset,
+            SEEK_END => size + offset,
+            SEEK_DATA if offset < size => offset,
+            SEEK_HOLE if offset < size => size,
+            SEEK_DATA | SEEK_HOLE => {
+                reply.error(ENXIO);
+                return;
+            }
+            _ => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+        if new_offset < 0 {
+            reply.error(EINVAL);
+        } else {
+            reply.offset(new_offset);
+        }
+    }
+
     // TODO: Allow updating a pods (basically kubectl edit)
-    // fn write(
-    //     &mut self,
-    //     _req: &Request<'_>,
-    //     ino: u64,
-    //     fh: u64,
-    //     offset: i64,
-    //     data: &[u8],
-    //     write_flags: u32,
-    //     flags: i32,
-    //     lock_owner: Option<u64>,
-    //     reply: ReplyWrite,
-    // ) {
-    // }
+    // Currently only manifests in the apply directory can be written to
+    fn write(
+        &mut self,
+        req: &Request<'_>,
+        inode: Inode,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        self.activity.touch();
+        let _timer = self.stats.time("write");
+        log::debug!("Trying to write {} bytes to {}", data.len(), inode);
+        if self.is_immutable(inode) || self.is_gitops_managed(inode) {
+            reply.error(EROFS);
+            return;
+        }
+        self.start_editing_configmap_key(inode);
+        match self.manifest_handles.write(fh, offset as usize, data) {
+            Ok(true) => {
+                reply.written(data.len() as u32);
+                return;
+            }
+            Ok(false) => (),
+            Err(error) => {
+                reply.error(error);
+                return;
+            }
+        }
+
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            if *file.kind() == FileKind::Undo {
+                self.undo(data, reply);
+                return;
+            }
+            if *file.kind() == FileKind::PendingOps {
+                self.pending_ops_control(data, reply);
+                return;
+            }
+            if *file.kind() == FileKind::Prune {
+                self.prune(data, reply);
+                return;
+            }
+            if let FileKind::RemoveFinalizers {
+                resource_type,
+                namespace,
+                name,
+            } = file.kind().clone()
+            {
+                if !self.quota.try_acquire() {
+                    reply.error(EDQUOT);
+                } else if self.remove_finalizers(
+                    req.uid(),
+                    resource_type,
+                    namespace.as_deref(),
+                    &name,
+                ) {
+                    reply.written(data.len() as u32);
+                } else {
+                    reply.error(EIO);
+                }
+                return;
+            }
+            if *file.kind() == FileKind::StatsReset {
+                log::info!("Resetting statistics");
+                self.stats.reset();
+                self.cache.lock().unwrap().reset_stats();
+                kubectl::reset_api_errors();
+                usage::reset();
+                reply.written(data.len() as u32);
+                return;
+            }
+            if let FileKind::ExecStdin(key) = file.kind().clone() {
+                match self.exec_session(&key).map(|session| session.write(data)) {
+                    Some(Ok(())) => reply.written(data.len() as u32),
+                    Some(Err(error)) => {
+                        log::error!("Could not write to exec session: {}", error);
+                        reply.error(EIO);
+                    }
+                    None => reply.error(EIO),
+                }
+                return;
+            }
+            match file.write(offset as usize, data) {
+                Ok(()) => {
+                    self.pending_manifests.insert(inode);
+                    reply.written(data.len() as u32);
+                }
+                Err(error) => reply.error(error),
+            }
+        } else {
+            reply.error(ENOENT);
+        }
+    }
+
+    // Only used to truncate manifests in the apply directory (e.g. when they are overwritten)
+    fn setattr(
+        &mut self,
+        _req: &Request<'_>,
+        inode: Inode,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        atime: Option<TimeOrNow>,
+        mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        fh: Option<u64>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        self.activity.touch();
+        let _timer = self.stats.time("setattr");
+        // Touching a file refreshes it and everything below it from the cluster
+        if size.is_none() && (atime.is_some() || mtime.is_some()) {
+            self.refresh_subtree(inode);
+        }
+        // Opening an existing manifest with O_TRUNC truncates it before it is opened
+        if size.is_some() {
+            if self.is_immutable(inode) || self.is_gitops_managed(inode) {
+                reply.error(EROFS);
+                return;
+            }
+            self.remember_opened_manifest(inode);
+            self.start_editing_configmap_key(inode);
+        }
+        // ftruncate of an opened manifest only changes its buffer
+        let truncated_buffer = match fh.zip(size) {
+            Some((fh, size)) => match self.manifest_handles.truncate(fh, size as usize) {
+                Ok(truncated) => truncated,
+                Err(error) => {
+                    reply.error(error);
+                    return;
+                }
+            },
+            None => false,
+        };
+        if let Some((file, _)) = self.inode_table.get_mut(&inode) {
+            // Opening control files (e.g. undo or exec.stdin) for writing truncates them,
+            // which is a no-op
+            let truncatable = !matches!(
+                file.kind(),
+                FileKind::Undo
+                    | FileKind::PendingOps
+                    | FileKind::Prune
+                    | FileKind::StatsReset
+                    | FileKind::RemoveFinalizers { .. }
+                    | FileKind::ExecStdin(_)
+            );
+            if let Some(size) = size.filter(|_| truncatable && !truncated_buffer) {
+                if let Err(error) = file.truncate(size as usize) {
+                    reply.error(error);
+                    return;
+                }
+                self.pending_manifests.insert(inode);
+            }
+            reply.attr(&TTL, &file.fileattrs());
+        } else {
+            reply.error(ENOENT);
+        }
+    }
+
+    // Manifests are applied once the file is closed, because we can not know if the
+    // user finished writing before that
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        inode: Inode,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        // Manifests are usually applied on flush already
+        if self
+            .manifest_handles
+            .get(fh)
+            .is_some_and(|handle| handle.written)
+        {
+            let _ = self.apply_manifest_handle(fh);
+        } else if self.pending_manifests.remove(&inode) {
+            let _ = self.apply_written_manifest(inode);
+        }
+        self.manifest_handles.close(fh);
+        self.opened_manifests.remove(&inode);
+        if let Some(key) = self.cache_key(inode) {
+            self.cache.lock().unwrap().released(&key);
+        }
+        self.release_rollout(inode);
+        reply.ok();
+    }
+
+    fn flush(
+        &mut self,
+        _req: &Request<'_>,
+        inode: Inode,
+        fh: u64,
+        _lock_owner: u64,
+        reply: ReplyEmpty,
+    ) {
+        // Errors of flush are returned by close(2), so this is where conflicts are reported
+        if self
+            .manifest_handles
+            .get(fh)
+            .is_some_and(|handle| handle.written)
+        {
+            if let Err(error) = self.apply_manifest_handle(fh) {
+                reply.error(error);
+                return;
+            }
+        } else if self.pending_manifests.remove(&inode) {
+            if let Err(error) = self.apply_written_manifest(inode) {
+                reply.error(error);
+                return;
+            }
+        }
+        reply.ok();
+    }
+
+    fn opendir(&mut self, _req: &Request<'_>, inode: Inode, _flags: i32, reply: ReplyOpen) {
+        self.activity.touch();
+        let fh = match self.is_paged_namespace(inode) {
+            true => self.paged_listings.open(inode),
+            false => 0,
+        };
+        reply.opened(fh, 0);
+    }
+
+    fn releasedir(
+        &mut self,
+        _req: &Request<'_>,
+        _inode: Inode,
+        fh: u64,
+        _flags: i32,
+        reply: ReplyEmpty,
+    ) {
+        self.paged_listings.close(fh);
+        reply.ok();
+    }
 
     fn readdir(
         &mut self,
         _req: &Request<'_>,
         inode: Inode,
-        _fh: u64,
+        fh: u64,
         offset: Offset,
         mut reply: ReplyDirectory,
     ) {
+        self.activity.touch();
+        let _timer = self.stats.time("readdir");
         log::debug!("Listing directory for {}", inode);
+        self.replay_pending_ops(false);
+        let traversal = offset == 0 && self.traversal.record_readdir();
+        if traversal {
+            kubectl::start_bulk_window(BULK_WINDOW_DURATION);
+        }
+        // Query results are only updated when the directory is listed from the beginning
+        if offset == 0 && self.is_lazy_directory(inode) {
+            self.refresh_lazy_directory(inode);
+        }
+        // Namespaces that are listed page by page list the next page when the entries that
+        // are already there were returned
+        let paged = self.paged_listings.get(fh).is_some();
+        if !paged {
+            self.build_deferred_pods(inode);
+        }
+        if inode == self.trash_inode {
+            self.expire_trash();
+        }
+        // Recursive traversals (e.g. "grep -r") read everything anyway
+        if offset == 0 && !traversal {
+            self.prefetch_descriptions(inode);
+        }
         // Boolean value that tracks whether the reply buffer is full or not
         let mut buffer_full = false;
 
-        if let Some((_, children)) = self.inode_table.get(&inode) {
+        let mut next_index = offset as usize;
+        loop {
+            // Pages are appended, sorting them would change the offsets of returned entries
+            let children = match paged {
+                true => self
+                    .inode_table
+                    .get(&inode)
+                    .map(|(_, children)| children.clone()),
+                false => self.ordered_children(inode),
+            };
+            let Some(children) = children else {
+                log::error!("Could not find {} in the inode table", inode);
+                break;
+            };
             // See https://github.com/cberner/fuser/issues/267#issuecomment-1794405706
-            for (index, child_inode) in children.iter().enumerate().skip(offset as usize) {
+            for (index, child_inode) in children.iter().enumerate().skip(next_index) {
                 if let Some((child_resource, _)) = self.inode_table.get(child_inode) {
                     log::debug!("Adding {} to reply buffer", child_resource.name);
                     if reply.add(
                         child_resource.inode,
-                        offset + index as i64 + 1,
+                        match paged {
+                            true => index as i64 + 1,
+                            false => offset + index as i64 + 1,
+                        },
                         child_resource.filetype(),
                         OsStr::new(&child_resource.name),
                     ) {
+                        // The kernel asks for the rest of a paged listing with the offset of
+                        // the last entry that fit
+                        if paged {
+                            break;
+                        }
                         log::error!(
                             "Reply buffer is full!!\nCould not add {}.\nThis should never happen!!",
                             child_resource.name
@@ -399,9 +4458,11 @@ impl Filesystem for K8sFS {
                 } else {
                     log::error!("Could not find {} in the inode table", child_inode);
                 }
+                next_index = index + 1;
+            }
+            if next_index < children.len() || !paged || !self.list_next_page(fh) {
+                break;
             }
-        } else {
-            log::error!("Could not find {} in the inode table", inode);
         }
 
         if buffer_full {
@@ -412,15 +4473,106 @@ impl Filesystem for K8sFS {
     }
 
     // TODO: Allow creating pods
-    // fn create(
-    //     &mut self,
-    //     _req: &Request<'_>,
-    //     parent: u64,
-    //     name: &OsStr,
-    //     mode: u32,
-    //     umask: u32,
-    //     flags: i32,
-    //     reply: ReplyCreate,
-    // ) {
-    // }
+    // Currently files can only be created in the apply directory
+    fn create(
+        &mut self,
+        req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        _flags: i32,
+        reply: ReplyCreate,
+    ) {
+        self.activity.touch();
+        let _timer = self.stats.time("create");
+        if self.get_file_by_name(name, parent).is_some() {
+            reply.error(EEXIST);
+            return;
+        }
+        // Files that instantiate a template are applied when they are closed, so that the
+        // content of a copied template can be written to them first
+        let instantiates_template = self.config.allow_write
+            && self
+                .find_template(parent, &name.to_string_lossy())
+                .is_some();
+        if self.is_immutable(parent) || self.is_gitops_managed(parent) {
+            reply.error(EROFS);
+            return;
+        }
+        if let Some(FileKind::ConfigMap {
+            namespace,
+            name: configmap,
+            ..
+        }) = self
+            .get_file_by_inode(parent)
+            .map(|file| file.kind().clone())
+        {
+            let key = name.to_string_lossy();
+            if !is_valid_configmap_key(&key) {
+                reply.error(EINVAL);
+                return;
+            }
+            // The key is added once the file is flushed, even if nothing was written to it
+            let inode = self.build_virtual_file(
+                parent,
+                &key,
+                FileKind::ConfigMapKey {
+                    namespace,
+                    configmap,
+                    key: key.to_string(),
+                    written: Some(Vec::new()),
+                    immutable: false,
+                },
+            );
+            self.pending_manifests.insert(inode);
+            self.set_creator(inode, req, mode, umask);
+            let attrs = self.get_file_by_inode(inode).unwrap().fileattrs();
+            reply.created(&TTL, &attrs, 0, 0, 0);
+            return;
+        }
+        if !instantiates_template && !self.is_drop_directory(parent) {
+            log::error!(
+                "Files are only allowed to be created in the apply directory, in ConfigMap directories or from templates."
+            );
+            reply.error(EPERM);
+            return;
+        }
+
+        let inode = self.build_virtual_file(
+            parent,
+            &name.to_string_lossy(),
+            FileKind::Manifest(Vec::new()),
+        );
+        if instantiates_template {
+            self.pending_manifests.insert(inode);
+        }
+        self.set_creator(inode, req, mode, umask);
+        let fh = self.open_manifest_handle(inode);
+        let attrs = self.get_file_by_inode(inode).unwrap().fileattrs();
+        reply.created(&TTL, &attrs, 0, fh, FOPEN_DIRECT_IO);
+    }
+}
+
+// Return true if a name can be used as the key of a ConfigMap
+// Keys consist of alphanumeric characters, "-", "_" and "." (but are not "." or "..")
+fn is_valid_configmap_key(key: &str) -> bool {
+    !key.is_empty()
+        && key != "."
+        && key != ".."
+        && key
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_.".contains(character))
+}
+
+// Reply with the value of an extended attribute
+// A size of 0 asks for the size of the value, a smaller buffer than the value fails with ERANGE.
+fn reply_xattr(value: &[u8], size: u32, reply: ReplyXattr) {
+    if size == 0 {
+        reply.size(value.len() as u32);
+    } else if (size as usize) < value.len() {
+        reply.error(ERANGE);
+    } else {
+        reply.data(value);
+    }
 }
@@ -0,0 +1,206 @@
+// Which GitOps tool (Argo CD, Flux) or Helm release manages a kubernetes resource
+// Changes that are made through the mount to such resources are reverted by the next sync, so
+// every resource directory has an ownership file that tells who manages it.
+
+use crate::kubectl;
+use crate::reports::{array, field};
+use serde_json::Value;
+
+// Name of the file inside resource directories that contains the ownership
+pub const OWNERSHIP_FILE_NAME: &str = "ownership";
+
+// Annotation that Argo CD uses to track resources with annotation based tracking
+// Value: <application>:<group>/<kind>:<namespace>/<name>, the application can be prefixed
+// with "<namespace>_" for applications outside of the Argo CD namespace
+const ARGOCD_TRACKING_ANNOTATION: &str = "argocd.argoproj.io/tracking-id";
+// Annotations of resources that change how Argo CD syncs them
+const ARGOCD_ANNOTATIONS: [&str; 4] = [
+    "argocd.argoproj.io/sync-options",
+    "argocd.argoproj.io/compare-options",
+    "argocd.argoproj.io/sync-wave",
+    "argocd.argoproj.io/hook",
+];
+// Annotations of resources that change how Flux reconciles them
+const FLUX_ANNOTATIONS: [&str; 2] = [
+    "kustomize.toolkit.fluxcd.io/reconcile",
+    "kustomize.toolkit.fluxcd.io/ssa",
+];
+
+// Flux sources whose labels mark the objects they applied (kind, resource, label prefix)
+const FLUX_SOURCES: [(&str, &str, &str); 2] = [
+    (
+        "kustomization",
+        "kustomizations.kustomize.toolkit.fluxcd.io",
+        "kustomize.toolkit.fluxcd.io",
+    ),
+    (
+        "helmrelease",
+        "helmreleases.helm.toolkit.fluxcd.io",
+        "helm.toolkit.fluxcd.io",
+    ),
+];
+
+// Describe who manages an object, one "key: value" line per fact
+//   argocd.application: argocd/web
+//   argocd.sync: Synced
+//   flux.kustomization: flux-system/apps
+//   flux.ready: True (Applied revision: main@sha1:1a2b3c)
+// Argo CD tracks objects by the app.kubernetes.io/instance label by default, but Helm charts and
+// Kustomize bases set it as well, so an instance label is only used if it is configured (see
+// --argocd-instance-label).
+pub fn ownership(context: &str, object: &Value, instance_label: Option<&str>) -> String {
+    let mut lines = Vec::new();
+    if let Some(controller) = controller(object) {
+        lines.push(format!(
+            "controller: {}/{}",
+            field(controller, "/kind"),
+            field(controller, "/name")
+        ));
+    }
+
+    // Argo CD
+    let application = argocd_application_name(object, instance_label);
+    if !application.is_empty() {
+        lines.extend(argocd_application(context, application));
+        for name in ARGOCD_ANNOTATIONS {
+            if !annotation(object, name).is_empty() {
+                lines.push(format!("{}: {}", name, annotation(object, name)));
+            }
+        }
+    }
+
+    // Flux
+    for (kind, resource, namespace, name) in flux_sources(object) {
+        lines.push(format!("flux.{}: {}/{}", kind, namespace, name));
+        match kubectl::get_object(context, Some(namespace), resource, name) {
+            Ok(source) => {
+                if let Some(ready) = array(&source, "/status/conditions")
+                    .iter()
+                    .find(|condition| field(condition, "/type") == "Ready")
+                {
+                    lines.push(format!(
+                        "flux.ready: {} ({})",
+                        field(ready, "/status"),
+                        field(ready, "/message")
+                    ));
+                }
+                if source.pointer("/spec/suspend") == Some(&Value::Bool(true)) {
+                    lines.push("flux.suspended: true".to_string());
+                }
+            }
+            Err(error) => log::debug!("Could not retrieve {} {}: {}", kind, name, error),
+        }
+    }
+    for name in FLUX_ANNOTATIONS {
+        if !annotation(object, name).is_empty() {
+            lines.push(format!("{}: {}", name, annotation(object, name)));
+        }
+    }
+
+    // Helm releases that were installed without a GitOps tool
+    if label(object, "app.kubernetes.io/managed-by") == "Helm" {
+        lines.push(format!(
+            "helm.release: {}/{}",
+            annotation(object, "meta.helm.sh/release-namespace"),
+            annotation(object, "meta.helm.sh/release-name")
+        ));
+    }
+
+    if lines.is_empty() {
+        return "Not managed by a controller, Argo CD, Flux or Helm\n".to_string();
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Return the GitOps tool that manages an object (e.g. "Argo CD application web"), None if the
+// object is not managed by Argo CD or Flux
+pub fn manager(object: &Value, instance_label: Option<&str>) -> Option<String> {
+    let application = argocd_application_name(object, instance_label);
+    if !application.is_empty() {
+        return Some(format!("Argo CD application {}", application));
+    }
+    flux_sources(object)
+        .first()
+        .map(|(kind, _, namespace, name)| format!("Flux {} {}/{}", kind, namespace, name))
+}
+
+// Return the controller of an object
+// Objects with a controller are recreated by it, GitOps labels are usually inherited.
+fn controller(object: &Value) -> Option<&Value> {
+    array(object, "/metadata/ownerReferences")
+        .iter()
+        .find(|owner| owner.pointer("/controller") == Some(&Value::Bool(true)))
+}
+
+// Return the name of the Argo CD application that tracks an object or "" if there is none
+fn argocd_application_name<'a>(object: &'a Value, instance_label: Option<&str>) -> &'a str {
+    match (
+        annotation(object, ARGOCD_TRACKING_ANNOTATION),
+        instance_label,
+    ) {
+        ("", Some(instance_label)) if controller(object).is_none() => label(object, instance_label),
+        ("", _) => "",
+        (tracking_id, _) => tracking_id.split(':').next().unwrap_or(""),
+    }
+}
+
+// Return the Flux sources that applied an object (kind, resource, namespace, name)
+fn flux_sources(object: &Value) -> Vec<(&'static str, &'static str, &str, &str)> {
+    FLUX_SOURCES
+        .iter()
+        .filter_map(|(kind, resource, prefix)| {
+            let name = label(object, &format!("{}/name", prefix));
+            let namespace = label(object, &format!("{}/namespace", prefix));
+            (!name.is_empty()).then_some((*kind, *resource, namespace, name))
+        })
+        .collect()
+}
+
+fn annotation<'a>(object: &'a Value, name: &str) -> &'a str {
+    object
+        .pointer("/metadata/annotations")
+        .and_then(|annotations| annotations.get(name))
+        .and_then(Value::as_str)
+        .unwrap_or("")
+}
+
+fn label<'a>(object: &'a Value, name: &str) -> &'a str {
+    object
+        .pointer("/metadata/labels")
+        .and_then(|labels| labels.get(name))
+        .and_then(Value::as_str)
+        .unwrap_or("")
+}
+
+// Describe the Argo CD application of a resource and its sync and health status
+// The status is left out if applications cannot be read (e.g. Argo CD runs in another cluster).
+fn argocd_application(context: &str, application: &str) -> Vec<String> {
+    let (namespace, name) = match application.split_once('_') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, application),
+    };
+    let applications = kubectl::get_items(context, None, "applications.argoproj.io", &[])
+        .unwrap_or_else(|error| {
+            log::debug!("Could not list Argo CD applications: {}", error);
+            Vec::new()
+        });
+    let Some(found) = applications.iter().find(|found| {
+        field(found, "/metadata/name") == name
+            && namespace.is_none_or(|namespace| field(found, "/metadata/namespace") == namespace)
+    }) else {
+        return vec![format!("argocd.application: {}", application)];
+    };
+    let mut lines = vec![
+        format!(
+            "argocd.application: {}/{}",
+            field(found, "/metadata/namespace"),
+            name
+        ),
+        format!("argocd.sync: {}", field(found, "/status/sync/status")),
+        format!("argocd.health: {}", field(found, "/status/health/status")),
+    ];
+    if found.pointer("/spec/syncPolicy/automated").is_some() {
+        lines.push("argocd.auto-sync: true".to_string());
+    }
+    lines
+}
@@ -0,0 +1,108 @@
+// Buffers of manifests that are opened for writing, one per file handle
+// Every open works on its own snapshot of the manifest, so processes that write the same manifest
+// at the same time do not interleave their writes. The first buffer that is flushed is applied,
+// flushing the others afterwards fails with ESTALE.
+
+use crate::filesystem::Inode;
+use crate::k8s_resource::written_size;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub struct ManifestHandle {
+    pub inode: Inode,
+    pub content: Vec<u8>,
+    // State of the objects of the manifest when it was opened
+    pub objects: Vec<Value>,
+    // Set when the buffer changed since it was opened or last applied
+    pub written: bool,
+    // Number of times the manifest was applied when the buffer was opened or last applied
+    generation: u64,
+}
+
+#[derive(Default)]
+pub struct ManifestHandles {
+    handles: HashMap<u64, ManifestHandle>,
+    // File handle 0 is used for files without a buffer
+    last_fh: u64,
+    // Number of times each manifest was applied from a buffer
+    generations: HashMap<Inode, u64>,
+}
+
+impl ManifestHandles {
+    // Start a buffer with the current content of a manifest and return its file handle
+    pub fn open(&mut self, inode: Inode, content: Vec<u8>, objects: Vec<Value>) -> u64 {
+        self.last_fh += 1;
+        let handle = ManifestHandle {
+            inode,
+            content,
+            objects,
+            written: false,
+            generation: self.generation(inode),
+        };
+        self.handles.insert(self.last_fh, handle);
+        self.last_fh
+    }
+
+    pub fn get(&self, fh: u64) -> Option<&ManifestHandle> {
+        self.handles.get(&fh)
+    }
+
+    pub fn get_mut(&mut self, fh: u64) -> Option<&mut ManifestHandle> {
+        self.handles.get_mut(&fh)
+    }
+
+    // Write data into the buffer of a file handle
+    // Returns false if the file handle does not have a buffer and fails with EFBIG if the buffer
+    // would become larger than a written file can be.
+    pub fn write(&mut self, fh: u64, offset: usize, data: &[u8]) -> Result<bool, libc::c_int> {
+        let Some(handle) = self.handles.get_mut(&fh) else {
+            return Ok(false);
+        };
+        let end = written_size(offset, data.len())?;
+        if handle.content.len() < end {
+            handle.content.resize(end, 0);
+        }
+        handle.content[offset..end].copy_from_slice(data);
+        handle.written = true;
+        Ok(true)
+    }
+
+    // Change the size of the buffer of a file handle (ftruncate)
+    // Returns false if the file handle does not have a buffer, fails like write.
+    pub fn truncate(&mut self, fh: u64, size: usize) -> Result<bool, libc::c_int> {
+        let Some(handle) = self.handles.get_mut(&fh) else {
+            return Ok(false);
+        };
+        handle.content.resize(written_size(size, 0)?, 0);
+        handle.written = true;
+        Ok(true)
+    }
+
+    // Return true if the manifest was applied from another buffer since this one was opened
+    pub fn is_stale(&self, fh: u64) -> bool {
+        self.handles
+            .get(&fh)
+            .is_some_and(|handle| handle.generation != self.generation(handle.inode))
+    }
+
+    // Remember that the buffer of a file handle was applied
+    // The objects were changed by the buffer itself, so they are not checked again.
+    pub fn applied(&mut self, fh: u64) {
+        let Some(handle) = self.handles.get_mut(&fh) else {
+            return;
+        };
+        handle.objects.clear();
+        let generation = self.generations.entry(handle.inode).or_default();
+        *generation += 1;
+        handle.generation = *generation;
+        handle.written = false;
+    }
+
+    pub fn close(&mut self, fh: u64) -> Option<ManifestHandle> {
+        self.handles.remove(&fh)
+    }
+
+    fn generation(&self, inode: Inode) -> u64 {
+        self.generations.get(&inode).copied().unwrap_or_default()
+    }
+}
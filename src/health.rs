@@ -0,0 +1,172 @@
+use crate::k8s_resource::ResourceType;
+use crate::renderer;
+use serde_json::Value;
+
+// Health of a kubernetes resource, modelled after the health assessment of Argo CD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    Healthy,
+    Progressing,
+    Degraded,
+    // No rules are configured for the kind of the resource
+    Unknown,
+}
+
+impl Health {
+    pub fn all() -> [Health; 4] {
+        [
+            Health::Healthy,
+            Health::Progressing,
+            Health::Degraded,
+            Health::Unknown,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Health::Healthy => "Healthy",
+            Health::Progressing => "Progressing",
+            Health::Degraded => "Degraded",
+            Health::Unknown => "Unknown",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Health> {
+        Health::all()
+            .into_iter()
+            .find(|health| health.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// Rule that assigns a health to resources of a kind
+// A rule matches if its JSONPath expression matches anything (or, if values are given, if any
+// of the matched values is one of them). Rules are defined in the config file:
+//   [[health]]
+//   kind = "pod"
+//   status = "Degraded"
+//   jsonpath = "$.status.containerStatuses[*].state.waiting.reason"
+//   equals = ["CrashLoopBackOff", "ImagePullBackOff"]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthRule {
+    pub resource_type: ResourceType,
+    health: Health,
+    expression: String,
+    equals: Vec<String>,
+}
+
+impl HealthRule {
+    fn new(resource_type: ResourceType, health: Health, expression: &str, equals: &[&str]) -> Self {
+        HealthRule {
+            resource_type,
+            health,
+            expression: expression.to_string(),
+            equals: equals.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+
+    // Parse a rule from an entry of the config file
+    pub fn from_toml(entry: &toml::Value) -> Result<HealthRule, String> {
+        let get = |key: &str| {
+            entry
+                .get(key)
+                .and_then(toml::Value::as_str)
+                .ok_or_else(|| format!("Health rule is missing \"{}\"", key))
+        };
+        let kind = get("kind")?;
+        let resource_type = ResourceType::from_kind(kind)
+            .ok_or_else(|| format!("Health rules are not supported for kind \"{}\"", kind))?;
+        let status = get("status")?;
+        let health = Health::from_name(status)
+            .filter(|health| *health != Health::Unknown)
+            .ok_or_else(|| format!("Invalid health status \"{}\"", status))?;
+        let equals = match entry.get("equals") {
+            None => Vec::new(),
+            Some(equals) => equals
+                .as_array()
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(toml::Value::as_str)
+                        .map(String::from)
+                })
+                .ok_or_else(|| String::from("\"equals\" of a health rule has to be a list"))?
+                .collect(),
+        };
+
+        Ok(HealthRule {
+            resource_type,
+            health,
+            expression: renderer::parse_expression(get("jsonpath")?)?,
+            equals,
+        })
+    }
+
+    fn matches(&self, object: &Value) -> bool {
+        match renderer::query(&self.expression, object) {
+            Ok(values) if self.equals.is_empty() => !values.is_empty(),
+            Ok(values) => values.iter().any(|value| self.equals.contains(value)),
+            Err(error) => {
+                log::error!("Could not evaluate health rule: {}", error);
+                false
+            }
+        }
+    }
+}
+
+// Rules that are used for pods if the config file does not define any
+pub fn default_rules() -> Vec<HealthRule> {
+    vec![
+        HealthRule::new(
+            ResourceType::Pod,
+            Health::Degraded,
+            "$.status.phase",
+            &["Failed"],
+        ),
+        HealthRule::new(
+            ResourceType::Pod,
+            Health::Degraded,
+            "$.status.containerStatuses[*].state.waiting.reason",
+            &[
+                "CrashLoopBackOff",
+                "ImagePullBackOff",
+                "ErrImagePull",
+                "InvalidImageName",
+                "CreateContainerConfigError",
+            ],
+        ),
+        HealthRule::new(
+            ResourceType::Pod,
+            Health::Healthy,
+            "$.status.phase",
+            &["Succeeded"],
+        ),
+        HealthRule::new(
+            ResourceType::Pod,
+            Health::Progressing,
+            "$.status.phase",
+            &["Pending"],
+        ),
+        HealthRule::new(
+            ResourceType::Pod,
+            Health::Progressing,
+            "$.status.containerStatuses[*].ready",
+            &["false"],
+        ),
+    ]
+}
+
+// Assess the health of an object with the first matching rule for its kind
+// Objects for which rules exist, but none matches, are healthy.
+pub fn assess(rules: &[HealthRule], resource_type: ResourceType, object: &Value) -> Health {
+    let mut rules = rules
+        .iter()
+        .filter(|rule| rule.resource_type == resource_type)
+        .peekable();
+    if rules.peek().is_none() {
+        return Health::Unknown;
+    }
+    rules
+        .find(|rule| rule.matches(object))
+        .map(|rule| rule.health)
+        .unwrap_or(Health::Healthy)
+}
@@ -0,0 +1,34 @@
+// Names that shells, editors and version control tools probe for in every directory
+// Looking them up fails right away instead of searching the directory (or, in _query and
+// _rbac, running kubectl).
+pub const DEFAULT_IGNORE_PATTERNS: [&str; 8] = [
+    ".git",
+    ".svn",
+    ".hg",
+    "__pycache__",
+    "*.swp",
+    "*.swx",
+    ".DS_Store",
+    "._*",
+];
+
+// Return true if the name matches one of the glob patterns
+pub fn is_ignored(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| glob_matches(pattern, name))
+}
+
+// Match a name against a glob pattern with "*" (any number of characters) and "?" (exactly one)
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    matches_bytes(pattern.as_bytes(), name.as_bytes())
+}
+
+fn matches_bytes(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|start| matches_bytes(rest, &name[start..])),
+        Some((b'?', rest)) => !name.is_empty() && matches_bytes(rest, &name[1..]),
+        Some((character, rest)) => {
+            name.first() == Some(character) && matches_bytes(rest, &name[1..])
+        }
+    }
+}
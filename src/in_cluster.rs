@@ -0,0 +1,90 @@
+use crate::kubectl;
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Running inside a cluster with the service account of the pod instead of a kubeconfig
+// (e.g. as a sidecar that exposes the cluster as a volume to other containers)
+// kubectl gets a generated kubeconfig that references the token and the CA certificate that
+// kubernetes mounts into the pod, so rotated tokens are picked up without a restart.
+
+// Name of the context of the generated kubeconfig
+pub const CONTEXT_NAME: &str = "in-cluster";
+// Where kubernetes mounts the token, the CA certificate and the namespace of the service account
+const SERVICE_ACCOUNT_DIRECTORY: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+// Generate the kubeconfig of the service account and let all kubectl calls use it
+pub fn set_up() -> io::Result<()> {
+    let path = env::temp_dir().join(format!(
+        "k8sfs-in-cluster-{}.kubeconfig",
+        std::process::id()
+    ));
+    fs::write(&path, kubeconfig(Path::new(SERVICE_ACCOUNT_DIRECTORY))?)?;
+    log::info!("Using the service account of the pod ({})", path.display());
+    kubectl::set_kubeconfig(path);
+    Ok(())
+}
+
+// Build a kubeconfig (JSON is valid YAML) for the API server of the cluster the pod runs in
+fn kubeconfig(service_account: &Path) -> io::Result<String> {
+    let host = env::var("KUBERNETES_SERVICE_HOST").map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "KUBERNETES_SERVICE_HOST is not set, k8sfs is not running inside a cluster",
+        )
+    })?;
+    let port = env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| String::from("443"));
+    let token_file = existing_file(service_account, "token")?;
+    let ca_file = existing_file(service_account, "ca.crt")?;
+    let namespace = fs::read_to_string(service_account.join("namespace"))
+        .map(|namespace| namespace.trim().to_string())
+        .unwrap_or_else(|_| String::from("default"));
+    // IPv6 addresses have to be put in brackets
+    let server = match host.contains(':') {
+        true => format!("https://[{}]:{}", host, port),
+        false => format!("https://{}:{}", host, port),
+    };
+
+    let kubeconfig = json!({
+        "apiVersion": "v1",
+        "kind": "Config",
+        "clusters": [{
+            "name": CONTEXT_NAME,
+            "cluster": {
+                "server": server,
+                "certificate-authority": ca_file,
+            },
+        }],
+        "users": [{
+            "name": CONTEXT_NAME,
+            "user": { "tokenFile": token_file },
+        }],
+        "contexts": [{
+            "name": CONTEXT_NAME,
+            "context": {
+                "cluster": CONTEXT_NAME,
+                "user": CONTEXT_NAME,
+                "namespace": namespace,
+            },
+        }],
+        "current-context": CONTEXT_NAME,
+    });
+    Ok(serde_json::to_string_pretty(&kubeconfig)?)
+}
+
+// Return the path of a file of the service account if it exists
+fn existing_file(service_account: &Path, name: &str) -> io::Result<PathBuf> {
+    let path = service_account.join(name);
+    if !path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "{} does not exist, is a service account mounted?",
+                path.display()
+            ),
+        ));
+    }
+    Ok(path)
+}
@@ -0,0 +1,175 @@
+use crate::kubectl;
+use crate::reports::field;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+
+// Number of operations that are kept in the journal and can be undone
+const MAX_JOURNAL_ENTRIES: usize = 20;
+
+// Fields that are set by the API server and have to be removed before an object can be
+// applied again
+const SERVER_FIELDS: [&str; 6] = [
+    "resourceVersion",
+    "uid",
+    "creationTimestamp",
+    "generation",
+    "managedFields",
+    "selfLink",
+];
+
+// A change to the cluster that was made through the filesystem
+pub struct Entry {
+    description: String,
+    // State of the changed objects before the change
+    previous: Vec<Value>,
+    // Objects that did not exist before the change
+    created: Vec<Value>,
+}
+
+impl Entry {
+    // Build an entry from the state of the affected objects before and after the change
+    pub fn new(description: &str, previous: Vec<Value>, current: Vec<Value>) -> Self {
+        let created = current
+            .into_iter()
+            .filter(|object| {
+                !previous
+                    .iter()
+                    .any(|existing| same_object(existing, object))
+            })
+            .map(clean)
+            .collect();
+        Entry {
+            description: description.to_string(),
+            previous: previous.into_iter().map(clean).collect(),
+            created,
+        }
+    }
+
+    // Return true if undoing the entry would not change anything
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.created.is_empty()
+    }
+
+    // Revert the change by deleting created objects and applying the previous state
+    fn undo(&self, context: &str, force_conflicts: bool) -> kubectl::ApplyOutcome {
+        let mut success = true;
+        let mut report = String::new();
+        if !self.created.is_empty() {
+            match kubectl::delete(&object_list(&self.created), context) {
+                Ok(deleted) => report.push_str(&deleted),
+                Err(error) => {
+                    success = false;
+                    report.push_str(&format!("{}\n", error));
+                }
+            }
+        }
+        if !self.previous.is_empty() {
+            let outcome = kubectl::apply(&object_list(&self.previous), context, force_conflicts);
+            success &= outcome.success;
+            report.push_str(&outcome.report);
+        }
+
+        kubectl::ApplyOutcome::new(success, report)
+    }
+}
+
+// In memory journal of the last changes that were made through the filesystem
+// This is a safety net for mistakes in write mode, changes are lost once k8sfs exits.
+#[derive(Default)]
+pub struct Journal {
+    // Entries ordered from oldest to newest
+    entries: VecDeque<Entry>,
+    // Outcome of the last undo
+    last_undo: Option<String>,
+}
+
+impl Journal {
+    pub fn record(&mut self, entry: Entry) {
+        if entry.is_empty() {
+            log::debug!("Not journaling {}, nothing changed", entry.description);
+            return;
+        }
+        if self.entries.len() == MAX_JOURNAL_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    // Undo the last count changes, starting with the newest one
+    // A change that could not be undone stays in the journal, so that undoing it can be tried
+    // again, and older changes are not undone before it.
+    // Returns false if undoing any of them failed
+    pub fn undo(&mut self, count: usize, context: &str, force_conflicts: bool) -> bool {
+        let mut success = true;
+        let mut report = String::new();
+        for _ in 0..count {
+            let Some(entry) = self.entries.back() else {
+                break;
+            };
+            log::info!("Undoing {}", entry.description);
+            let outcome = entry.undo(context, force_conflicts);
+            report.push_str(&format!("undo {}\n{}", entry.description, outcome.report));
+            if !outcome.success {
+                success = false;
+                break;
+            }
+            self.entries.pop_back();
+        }
+        self.last_undo = Some(report);
+
+        success
+    }
+
+    // Human readable list of the changes that can be undone, newest first
+    pub fn summary(&self) -> String {
+        let mut summary =
+            String::from("# Write a number N to this file to undo the last N changes\n");
+        for (index, entry) in self.entries.iter().rev().enumerate() {
+            summary.push_str(&format!(
+                "{} {} ({} changed, {} created)\n",
+                index + 1,
+                entry.description,
+                entry.previous.len(),
+                entry.created.len()
+            ));
+        }
+        if let Some(last_undo) = &self.last_undo {
+            summary.push_str(&format!("\n# Last undo\n{}", last_undo));
+        }
+        summary
+    }
+}
+
+// Return true if both values describe the same kubernetes object
+pub fn same_object(a: &Value, b: &Value) -> bool {
+    ["/kind", "/metadata/namespace", "/metadata/name"]
+        .iter()
+        .all(|pointer| field(a, pointer) == field(b, pointer))
+}
+
+// Remove everything from an object that is managed by the API server
+pub fn clean(mut object: Value) -> Value {
+    if let Some(object) = object.as_object_mut() {
+        object.remove("status");
+    }
+    if let Some(metadata) = object
+        .pointer_mut("/metadata")
+        .and_then(Value::as_object_mut)
+    {
+        for server_field in SERVER_FIELDS {
+            metadata.remove(server_field);
+        }
+    }
+    object
+}
+
+// Wrap objects into a list that kubectl accepts as a manifest
+pub fn object_list(objects: &[Value]) -> Vec<u8> {
+    json!({
+        "apiVersion": "v1",
+        "kind": "List",
+        "items": objects,
+    })
+    .to_string()
+    .into_bytes()
+}
@@ -1,19 +1,97 @@
+use crate::cache::CacheKey;
+use crate::encoding::Encoding;
 use crate::filesystem::Inode;
+use crate::kubectl::{self, K8sError, Tracked};
+use crate::query::{Query, SelectorType};
+use crate::reports::Report;
+use crate::watcher::ContainerKey;
 use fuser::{FileAttr, FileType};
-use std::{process::Command, process::Output, time::SystemTime};
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime};
 
 // Block size is the amount of bytes that can be requested during read / write IO operations
 const BLOCK_SIZE: u32 = 1024;
 // Suffix that is added to a file name if the file should represent a definition file
-const DEFINITION_FILE_SUFFIX: &str = "_definition.yaml";
+pub const DEFAULT_DEFINITION_SUFFIX: &str = "_definition.yaml";
+// Name of definition files with --definition-as-manifest
+pub const MANIFEST_FILE_NAME: &str = "manifest.yaml";
+// Files that are written to are kept in memory, so they can not grow beyond this size
+// The API server does not accept larger objects anyway (etcd limits requests to 1.5MiB).
+pub const MAX_WRITTEN_SIZE: usize = 4 * 1024 * 1024;
+
+// Size of a file that is kept in memory after data was written at an offset
+// Fails with EFBIG instead of overflowing or growing beyond MAX_WRITTEN_SIZE.
+pub fn written_size(offset: usize, length: usize) -> Result<usize, libc::c_int> {
+    offset
+        .checked_add(length)
+        .filter(|size| *size <= MAX_WRITTEN_SIZE)
+        .ok_or(libc::EFBIG)
+}
 
 //  Resource types that are currently supported
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ResourceType {
     Root,
     Context,
     Namespace,
     Pod,
+    Node,
+}
+
+impl ResourceType {
+    // Return the resource type for a kind as it is written in the config file
+    pub fn from_kind(kind: &str) -> Option<ResourceType> {
+        match kind.to_lowercase().as_str() {
+            "namespace" | "namespaces" => Some(ResourceType::Namespace),
+            "pod" | "pods" => Some(ResourceType::Pod),
+            "node" | "nodes" => Some(ResourceType::Node),
+            _ => None,
+        }
+    }
+
+    // Resource types that are shown in the filesystem
+    pub fn kinds() -> [ResourceType; 3] {
+        [
+            ResourceType::Namespace,
+            ResourceType::Pod,
+            ResourceType::Node,
+        ]
+    }
+
+    // Return true if resources of this type belong to a namespace
+    pub fn is_namespaced(&self) -> bool {
+        *self == ResourceType::Pod
+    }
+
+    // Changes to resources of this type that can be made with file operations
+    // (with --allow-write)
+    pub fn write_operations(&self) -> &'static [&'static str] {
+        match self {
+            ResourceType::Namespace => &["mkdir (create)", "rmdir (delete)"],
+            ResourceType::Pod => &["exec.stdin (exec)"],
+            ResourceType::Node | ResourceType::Root | ResourceType::Context => &[],
+        }
+    }
+
+    // Name of the resource type that is used in kubectl commands
+    pub fn kubectl_name(&self) -> &'static str {
+        match self {
+            ResourceType::Namespace => "namespaces",
+            ResourceType::Pod => "pods",
+            ResourceType::Node => "nodes",
+            ResourceType::Root | ResourceType::Context => "",
+        }
+    }
+
+    // Kind of the resource type as it appears in objects (e.g. involvedObject.kind of events)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ResourceType::Namespace => "Namespace",
+            ResourceType::Pod => "Pod",
+            ResourceType::Node => "Node",
+            ResourceType::Root | ResourceType::Context => "",
+        }
+    }
 }
 
 // Helper method to build kubectl commands that will be used at runtime to do various tasks
@@ -36,6 +114,10 @@ fn build_kubectl_command(
             "kubectl --context {} --namespace {} {} pods {}",
             context, namespace, action, resource_name
         ),
+        ResourceType::Node => format!(
+            "kubectl --context {} {} nodes {}",
+            context, action, resource_name
+        ),
         _ => format!(
             "Files of type {:?} do not support {}!",
             resource_type, action
@@ -43,14 +125,252 @@ fn build_kubectl_command(
     }
 }
 
+// Parse a RFC 3339 timestamp as it is returned by the API server (e.g. 2024-01-02T03:04:05Z)
+// Fractional seconds are ignored
+pub fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    let (date, time) = timestamp.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    // Split off the UTC offset ("Z", "+01:00" or "-01:00")
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => return None,
+    };
+    let mut time = time
+        .split('.')
+        .next()?
+        .splitn(3, ':')
+        .map(str::parse::<i64>);
+    let (hours, minutes, seconds) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    let offset_seconds = match offset.split_at(1) {
+        ("Z" | "z", _) => 0,
+        (sign, offset) => {
+            let (offset_hours, offset_minutes) = offset.split_once(':')?;
+            let offset_seconds =
+                offset_hours.parse::<i64>().ok()? * 3600 + offset_minutes.parse::<i64>().ok()? * 60;
+            if sign == "-" {
+                -offset_seconds
+            } else {
+                offset_seconds
+            }
+        }
+    };
+
+    // Days since the unix epoch of a date in the proleptic gregorian calendar
+    // See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hours * 3600 + minutes * 60 + seconds - offset_seconds;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(seconds).ok()?))
+}
+
+// Format a time as a RFC 3339 timestamp in UTC (e.g. 2024-01-02T03:04:05Z)
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()) as i64;
+    let (days, seconds_of_day) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Date in the proleptic gregorian calendar of days since the unix epoch
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+// Name of the marker file that is added to directories whose content could not be listed
+// because of missing RBAC permissions
+pub const FORBIDDEN_FILE_NAME: &str = "FORBIDDEN";
+// Name of the marker file that is added to directories whose content could not be listed
+// for other reasons (e.g. the API server was not reachable)
+pub const FAILED_FILE_NAME: &str = "FAILED";
+
+// Describes what a file represents and therefore where its content comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileKind {
+    // Directory that represents the kubernetes resource itself
+    Resource,
+    // Regular file that contains the description of the kubernetes resource
+    Definition,
+    // Regular file with content that is already known when the file is created
+    Static(Vec<u8>),
+    // Executable shell script whose content is already known when the file is created
+    Script(Vec<u8>),
+    // Directory that does not represent a kubernetes resource (e.g. .k8sfs)
+    Directory,
+    // Directory where files that are written to it are applied to the cluster
+    DropDirectory,
+    // Regular file written by the user that is applied to the cluster when it is closed
+    Manifest(Vec<u8>),
+    // Regular file that contains statistics about the content cache
+    // The content is generated by the filesystem
+    CacheStats,
+    // Regular file that contains statistics about filesystem operations
+    // The content is generated by the filesystem
+    Stats,
+    // Regular file that contains the number of kubectl calls per operation and kind
+    // The content is generated by the filesystem
+    ApiUsage,
+    // Write only file that resets the statistics when written to
+    StatsReset,
+    // Regular file that contains the last failed kubectl call of a kubernetes resource
+    // The content is generated by the filesystem
+    LastError {
+        resource_type: ResourceType,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        name: String,
+    },
+    // Write only file that removes the finalizers of a kubernetes resource when written to
+    RemoveFinalizers {
+        resource_type: ResourceType,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        name: String,
+    },
+    // Regular file whose content is generated from the state of the cluster
+    Report(Report),
+    // Regular file that lists the journaled changes and undoes them when written to
+    // The content is generated by the filesystem
+    Undo,
+    // Regular file that lists the changes that are queued while the API server is unreachable
+    // and cancels them when written to (see --queue-offline)
+    // The content is generated by the filesystem
+    PendingOps,
+    // Regular file that previews and deletes resources by age when rules are written to it
+    // The content is generated by the filesystem
+    Prune,
+    // Regular file that contains the restarts of a container that the watcher observed
+    // The content is generated by the filesystem
+    RestartHistory(ContainerKey),
+    // Regular file that forwards everything that is written to it to an exec session
+    ExecStdin(ContainerKey),
+    // Regular file that contains the output of an exec session
+    // Reading blocks until the session printed something
+    ExecStdout(ContainerKey),
+    // Regular file that streams the events of the cluster as JSON lines
+    // Reading blocks until the next event occurs
+    EventStream,
+    // Regular file that streams the progress of the rollout of a deployment or StatefulSet
+    // (resource is "deployments" or "statefulsets")
+    // Reading blocks until the progress changes and ends when the rollout is done
+    RolloutStatus {
+        namespace: String,
+        resource: String,
+        name: String,
+    },
+    // Directory with the log files of a node, which is filled when it is accessed
+    NodeLogs(String),
+    // Directory of a service port where every looked up name is a path that is fetched
+    // through the API server proxy
+    ServiceProxy {
+        namespace: String,
+        // [https:]<service>:<port> as it is used in proxy URLs
+        service: String,
+    },
+    // Directory where every looked up name is interpreted as a selector
+    QueryType(SelectorType),
+    // Directory that contains symlinks to all resources that match a query
+    Query(Query),
+    // Directory of the group-version layout with symlinks to all resources of a kind in the
+    // namespace (see --layout)
+    ApiKind(String),
+    // Directory where every looked up name is interpreted as the next part of a permission
+    // check (verb and resource so far)
+    CanI(Vec<String>),
+    // Directory where every looked up IP address is a symlink to the pod or service that has it
+    ByIp,
+    // Directory of a ConfigMap with a file per key
+    // Keys are added and removed by creating and deleting files (with --allow-write),
+    // unless the ConfigMap is immutable
+    ConfigMap {
+        namespace: String,
+        name: String,
+        immutable: bool,
+    },
+    // Regular file with the value of a key of a ConfigMap
+    // Written content is kept until the file is flushed, which patches only this key
+    ConfigMapKey {
+        namespace: String,
+        configmap: String,
+        key: String,
+        written: Option<Vec<u8>>,
+        immutable: bool,
+    },
+    // Symlink to another file in the filesystem
+    Symlink(String),
+}
+
+// Formats that definitions can be read in, chosen by the extension of the file name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DefinitionFormat {
+    Yaml,
+    Json,
+    // Output of kubectl describe
+    Describe,
+}
+
+impl DefinitionFormat {
+    pub fn from_extension(extension: &str) -> Option<DefinitionFormat> {
+        match extension {
+            "yaml" | "yml" => Some(DefinitionFormat::Yaml),
+            "json" => Some(DefinitionFormat::Json),
+            "txt" => Some(DefinitionFormat::Describe),
+            _ => None,
+        }
+    }
+}
+
 // Represents a kubernetes resource
 pub struct ResourceFile {
     pub inode: Inode,
     pub parent: Inode,
-    _resource_type: ResourceType,
+    resource_type: ResourceType,
     pub name: String,
+    // Creation timestamp (RFC 3339) of the kubernetes resource, if the file represents one
+    pub creation_timestamp: Option<String>,
+    // Timestamp (RFC 3339) of the most recent change to the kubernetes resource that is known
+    pub modification_timestamp: Option<String>,
+    kind: FileKind,
+    // Encoding that is applied to the content of the file
+    encoding: Encoding,
+    // Owner (uid, gid) of files that were created by a user
+    owner: (u32, u32),
+    // Permissions that the file is allowed to have (e.g. mode & ~umask of created files)
+    // The permissions of the file kind are never exceeded.
+    permission_mask: u16,
+    // Special mode bits that mark the file (see --mode)
+    mode_marker: u16,
     delete_cmd: String,
     description_cmd: String,
+    // Retrieves the kubernetes resource (an output format is appended)
+    get_cmd: String,
 }
 
 impl ResourceFile {
@@ -65,10 +385,18 @@ impl ResourceFile {
         Self {
             inode,
             parent,
-            _resource_type: resource_type,
+            resource_type,
             name: resource_name.to_string(),
+            creation_timestamp: None,
+            modification_timestamp: None,
+            kind: FileKind::Resource,
+            encoding: Encoding::Identity,
+            owner: (0, 0),
+            permission_mask: 0o7777,
+            mode_marker: 0,
+            // Namespaces with content terminate in the background, rmdir does not wait for it
             delete_cmd: build_kubectl_command(
-                "delete",
+                "delete --wait=false",
                 resource_type,
                 context,
                 namespace,
@@ -81,49 +409,277 @@ impl ResourceFile {
                 namespace,
                 resource_name,
             ),
+            get_cmd: build_kubectl_command("get", resource_type, context, namespace, resource_name),
         }
     }
 
-    // Generate a definition file from the current file
-    pub fn create_definition_file(&self, inode: Inode) -> Self {
+    // Create a file that does not represent a kubernetes resource
+    pub fn new_virtual(inode: Inode, parent: Inode, name: &str, kind: FileKind) -> Self {
+        Self {
+            inode,
+            parent,
+            resource_type: ResourceType::Root,
+            name: name.to_string(),
+            creation_timestamp: None,
+            modification_timestamp: None,
+            kind,
+            encoding: Encoding::Identity,
+            owner: (0, 0),
+            permission_mask: 0o7777,
+            mode_marker: 0,
+            delete_cmd: build_kubectl_command("delete", ResourceType::Root, "", "", name),
+            description_cmd: String::new(),
+            get_cmd: String::new(),
+        }
+    }
+
+    // Leave the events out of the description (see --show-events)
+    pub fn hide_events(&mut self) {
+        self.description_cmd.push_str(" --show-events=false");
+    }
+
+    // Generate a definition file with the given name from the current file
+    pub fn create_definition_file(&self, inode: Inode, name: String) -> Self {
         ResourceFile {
             inode,
             parent: self.parent,
-            _resource_type: self._resource_type,
-            name: format!("{}{}", self.name, DEFINITION_FILE_SUFFIX),
+            resource_type: self.resource_type,
+            name,
+            creation_timestamp: self.creation_timestamp.clone(),
+            modification_timestamp: self.modification_timestamp.clone(),
+            kind: FileKind::Definition,
+            encoding: Encoding::Identity,
+            owner: (0, 0),
+            permission_mask: 0o7777,
+            mode_marker: 0,
             delete_cmd: self.delete_cmd.clone(),
             description_cmd: self.description_cmd.clone(),
+            get_cmd: self.get_cmd.clone(),
+        }
+    }
+
+    // Generate a file with the definition of the current resource in a format that was chosen
+    // by the extension of the looked up name (e.g. <pod>.json)
+    pub fn create_formatted_file(
+        &self,
+        inode: Inode,
+        name: String,
+        format: DefinitionFormat,
+    ) -> Self {
+        let description_cmd = match format {
+            DefinitionFormat::Yaml => format!("{} -o yaml", self.get_cmd),
+            DefinitionFormat::Json => format!("{} -o json", self.get_cmd),
+            DefinitionFormat::Describe => self.description_cmd.clone(),
+        };
+        ResourceFile {
+            parent: self.inode,
+            description_cmd,
+            ..self.create_definition_file(inode, name)
+        }
+    }
+
+    // Generate a marker file that informs the user that the content of the current
+    // directory could not be listed because of missing permissions
+    pub fn create_error_file(&self, inode: Inode, error: &K8sError) -> Self {
+        let name = match error {
+            K8sError::Forbidden(_) => FORBIDDEN_FILE_NAME,
+            _ => FAILED_FILE_NAME,
+        };
+        ResourceFile::new_virtual(
+            inode,
+            self.inode,
+            name,
+            FileKind::Static(format!("{}\n", error).into_bytes()),
+        )
+    }
+
+    // Return the current resourceVersion of the kubernetes resource of the file
+    pub fn resource_version(&self) -> Option<String> {
+        if self.get_cmd.is_empty() {
+            return None;
+        }
+        let version_cmd = format!("{} -o jsonpath={{.metadata.resourceVersion}}", self.get_cmd);
+        match execute_command(&version_cmd) {
+            Ok(output) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            }
+            _ => {
+                log::error!("Could not get the resourceVersion of {}", self.name);
+                None
+            }
+        }
+    }
+
+    // Use the owner and the requested permissions of the user who created the file
+    pub fn set_creator(&mut self, uid: u32, gid: u32, mode: u32, umask: u32) {
+        self.owner = (uid, gid);
+        self.permission_mask = (mode & !umask & 0o7777) as u16;
+    }
+
+    pub fn resource_type(&self) -> ResourceType {
+        self.resource_type
+    }
+
+    pub fn kind(&self) -> &FileKind {
+        &self.kind
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn set_mode_marker(&mut self, mode_marker: u16) {
+        self.mode_marker = mode_marker;
+    }
+
+    // Write data into a file that is kept in memory
+    // Fails with EPERM if the file does not support being written to and with EFBIG if it
+    // would become larger than MAX_WRITTEN_SIZE.
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), libc::c_int> {
+        let content = self.written_content().ok_or(libc::EPERM)?;
+        let end = written_size(offset, data.len())?;
+        if content.len() < end {
+            content.resize(end, 0);
+        }
+        content[offset..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    // Change the size of a file that is kept in memory
+    // Fails like write if the file does not support being truncated or would be too large.
+    pub fn truncate(&mut self, size: usize) -> Result<(), libc::c_int> {
+        let content = self.written_content().ok_or(libc::EPERM)?;
+        content.resize(written_size(size, 0)?, 0);
+        Ok(())
+    }
+
+    // Content of a file that is kept in memory while it is written
+    fn written_content(&mut self) -> Option<&mut Vec<u8>> {
+        match &mut self.kind {
+            FileKind::Manifest(content)
+            | FileKind::ConfigMapKey {
+                written: Some(content),
+                ..
+            } => Some(content),
+            _ => None,
+        }
+    }
+
+    // Keep the content of a ConfigMap key in memory until it is flushed, starting with its
+    // current value
+    pub fn start_editing(&mut self, current: Vec<u8>) {
+        if let FileKind::ConfigMapKey { written, .. } = &mut self.kind {
+            written.get_or_insert(current);
+        }
+    }
+
+    // Read the value of a ConfigMap key from the cluster again after it was flushed
+    pub fn finish_editing(&mut self) {
+        if let FileKind::ConfigMapKey { written, .. } = &mut self.kind {
+            *written = None;
         }
     }
 
-    // Return true if the current file is a definition file
-    fn is_definition_file(&self) -> bool {
-        self.name.ends_with(DEFINITION_FILE_SUFFIX)
+    // Replace the content of a file whose content is known in advance
+    pub fn set_content(&mut self, new_content: Vec<u8>) {
+        if let FileKind::Static(content) = &mut self.kind {
+            *content = new_content;
+        }
+    }
+
+    // Turn a file that was written to into a read-only file with the given content
+    pub fn seal(&mut self, content: Vec<u8>) {
+        self.kind = FileKind::Static(content);
     }
 
     // Return the file type if the current file
     pub fn filetype(&self) -> FileType {
-        if self.is_definition_file() {
-            FileType::RegularFile
-        } else {
-            FileType::Directory
+        match self.kind {
+            FileKind::Resource
+            | FileKind::Directory
+            | FileKind::DropDirectory
+            | FileKind::QueryType(_)
+            | FileKind::Query(_)
+            | FileKind::ApiKind(_)
+            | FileKind::CanI(_)
+            | FileKind::ByIp
+            | FileKind::NodeLogs(_)
+            | FileKind::ServiceProxy { .. }
+            | FileKind::ConfigMap { .. } => FileType::Directory,
+            FileKind::Symlink(_) => FileType::Symlink,
+            FileKind::Definition
+            | FileKind::Static(_)
+            | FileKind::Script(_)
+            | FileKind::Manifest(_)
+            | FileKind::CacheStats
+            | FileKind::Stats
+            | FileKind::ApiUsage
+            | FileKind::LastError { .. }
+            | FileKind::StatsReset
+            | FileKind::RemoveFinalizers { .. }
+            | FileKind::Undo
+            | FileKind::PendingOps
+            | FileKind::Prune
+            | FileKind::ExecStdin(_)
+            | FileKind::ExecStdout(_)
+            | FileKind::EventStream
+            | FileKind::RolloutStatus { .. }
+            | FileKind::Report(_)
+            | FileKind::RestartHistory(_)
+            | FileKind::ConfigMapKey { .. } => FileType::RegularFile,
         }
     }
 
     // Return the file attributes of the current file
     pub fn fileattrs(&self) -> FileAttr {
-        let permissions = if self.filetype() == FileType::Directory {
-            0o555
-        } else {
-            0o444
+        self.fileattrs_with_size(self.size())
+    }
+
+    // Return the file attributes of the current file with a file size that is already known
+    // This avoids generating the content of the file again (e.g. when it is cached)
+    pub fn fileattrs_with_size(&self, file_size: u64) -> FileAttr {
+        let permissions = match self.kind {
+            FileKind::ConfigMap {
+                immutable: true, ..
+            } => 0o555,
+            FileKind::ConfigMapKey {
+                immutable: true, ..
+            } => 0o444,
+            FileKind::DropDirectory | FileKind::ConfigMap { .. } => 0o755,
+            FileKind::Manifest(_)
+            | FileKind::Undo
+            | FileKind::PendingOps
+            | FileKind::Prune
+            | FileKind::ConfigMapKey { .. } => 0o644,
+            FileKind::Symlink(_) => 0o777,
+            FileKind::Script(_) => 0o555,
+            FileKind::ExecStdin(_) | FileKind::StatsReset | FileKind::RemoveFinalizers { .. } => {
+                0o222
+            }
+            _ if self.filetype() == FileType::Directory => 0o555,
+            _ => 0o444,
         };
-        let file_size = self.size();
         let file_block_size = if file_size > 0 {
             (file_size + BLOCK_SIZE as u64 - 1) / file_size
         } else {
             0
         };
 
+        let creation_time = self
+            .creation_timestamp
+            .as_deref()
+            .and_then(parse_timestamp)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let modification_time = self
+            .modification_timestamp
+            .as_deref()
+            .and_then(parse_timestamp)
+            .unwrap_or(creation_time);
+
         FileAttr {
             ino: self.inode,
             // Length is in bytes so getting the Vec length should be equivaled to the file size
@@ -131,15 +687,15 @@ impl ResourceFile {
             // We add a whole block and subtract 1 to catch all cases where the file
             // size is less than a single block
             blocks: file_block_size,
-            atime: SystemTime::UNIX_EPOCH,
-            mtime: SystemTime::UNIX_EPOCH,
-            ctime: SystemTime::UNIX_EPOCH,
-            crtime: SystemTime::UNIX_EPOCH,
+            atime: modification_time,
+            mtime: modification_time,
+            ctime: modification_time,
+            crtime: creation_time,
             kind: self.filetype(),
-            perm: permissions,
+            perm: (permissions | self.mode_marker) & self.permission_mask,
             nlink: 1,
-            uid: 0,
-            gid: 0,
+            uid: self.owner.0,
+            gid: self.owner.1,
             rdev: 0,
             blksize: BLOCK_SIZE,
             flags: 0,
@@ -149,35 +705,83 @@ impl ResourceFile {
     // Get the description for the current file
     // This is called when opening a file
     pub fn get_desc(&self) -> Vec<u8> {
-        if self.filetype() != FileType::RegularFile {
-            log::error!("Fatal ERROR!! You should never reach this!!");
-            return Vec::new();
+        match &self.kind {
+            FileKind::Resource
+            | FileKind::Directory
+            | FileKind::DropDirectory
+            | FileKind::CacheStats
+            | FileKind::Stats
+            | FileKind::ApiUsage
+            | FileKind::LastError { .. }
+            | FileKind::StatsReset
+            | FileKind::RemoveFinalizers { .. }
+            | FileKind::Undo
+            | FileKind::PendingOps
+            | FileKind::Prune
+            | FileKind::ExecStdin(_)
+            | FileKind::ExecStdout(_)
+            | FileKind::EventStream
+            | FileKind::RolloutStatus { .. }
+            | FileKind::RestartHistory(_)
+            | FileKind::QueryType(_)
+            | FileKind::Query(_)
+            | FileKind::ApiKind(_)
+            | FileKind::CanI(_)
+            | FileKind::ByIp
+            | FileKind::NodeLogs(_)
+            | FileKind::ServiceProxy { .. }
+            | FileKind::ConfigMap { .. }
+            | FileKind::ConfigMapKey { .. }
+            | FileKind::Symlink(_) => {
+                log::error!("Fatal ERROR!! You should never reach this!!");
+                return Vec::new();
+            }
+            FileKind::Static(content) | FileKind::Script(content) | FileKind::Manifest(content) => {
+                return content.clone()
+            }
+            FileKind::Report(report) => return report.generate(),
+            FileKind::Definition => {}
         }
 
-        let description = self.execute_command(&self.description_cmd);
-
-        if let Ok(description) = description {
-            if description.status.success() {
-                description.stdout
-            } else {
-                log::error!("Could not get description for {}", self.name);
-                log::debug!(
-                    "Command failed with: {}",
-                    String::from_utf8(description.stderr)
-                        .unwrap_or(String::from("Could not parse stderr! Invalid UTF-8!"))
-                );
-                Vec::new()
-            }
-        } else {
-            log::error!("Could not get description for {}", self.name);
-            log::debug!("Comand failed with: {:?}", description.err());
-            Vec::new()
+        Description {
+            name: self.name.clone(),
+            command: self.description_cmd.clone(),
         }
+        .fetch()
+    }
+
+    // Return the description of a definition file, which can be fetched on another thread
+    pub fn description(&self) -> Option<Description> {
+        (self.kind == FileKind::Definition).then(|| Description {
+            name: self.name.clone(),
+            command: self.description_cmd.clone(),
+        })
+    }
+
+    // Return the key that the content of the file is cached under, if it is cached at all
+    // Only content that is fetched from the cluster is cached. The key describes that content,
+    // so files of different mounts of a context share cached content.
+    pub fn cache_key(&self) -> Option<CacheKey> {
+        let key = match &self.kind {
+            FileKind::Definition => self.description_cmd.clone(),
+            FileKind::Report(report) => format!("{:?}", report),
+            FileKind::ConfigMapKey {
+                namespace,
+                configmap,
+                key,
+                written: None,
+                ..
+            } => format!("configmap {}/{}/{}", namespace, configmap, key),
+            _ => return None,
+        };
+        Some(format!("{} {:?}", key, self.encoding))
     }
 
     // Calculate the file size of the current file
     pub fn size(&self) -> u64 {
-        if self.filetype() == FileType::RegularFile {
+        if let FileKind::Symlink(target) = &self.kind {
+            target.len() as u64
+        } else if self.filetype() == FileType::RegularFile {
             self.get_desc().len() as u64
         } else {
             0
@@ -185,30 +789,64 @@ impl ResourceFile {
     }
 
     // Delete the underlying kubernetes resource that this file represents
-    pub fn delete(&self) -> bool {
-        let result = self.execute_command(&self.delete_cmd);
-        if let Ok(result) = result {
-            let success = result.status.success();
-            if !success {
+    pub fn delete(&self) -> Result<(), K8sError> {
+        match execute_command(&self.delete_cmd) {
+            Ok(result) if result.status.success() => Ok(()),
+            Ok(result) => {
+                let error = K8sError::from_stderr(&result.stderr);
+                log::debug!("Command failed with: {}", error);
+                Err(error)
+            }
+            Err(error) => {
+                log::debug!("Comand failed with: {:?}", error);
+                Err(K8sError::Failed(error.to_string()))
+            }
+        }
+    }
+}
+
+// Output of "kubectl describe" for a kubernetes resource
+#[derive(Debug, Clone)]
+pub struct Description {
+    // Name of the definition file
+    name: String,
+    command: String,
+}
+
+impl Description {
+    pub fn fetch(&self) -> Vec<u8> {
+        let description = execute_command(&self.command);
+
+        if let Ok(description) = description {
+            if description.status.success() {
+                description.stdout
+            } else {
+                log::error!("Could not get description for {}", self.name);
                 log::debug!(
                     "Command failed with: {}",
-                    String::from_utf8(result.stderr)
+                    String::from_utf8(description.stderr)
                         .unwrap_or(String::from("Could not parse stderr! Invalid UTF-8!"))
                 );
+                Vec::new()
             }
-            success
         } else {
-            log::debug!("Comand failed with: {:?}", result.err());
-            false
+            log::error!("Could not get description for {}", self.name);
+            log::debug!("Comand failed with: {:?}", description.err());
+            Vec::new()
         }
     }
+}
 
-    // Helper method to execute various internal commands
-    // See delete() and get_desc()
-    fn execute_command(&self, command: &str) -> std::io::Result<Output> {
-        log::debug!("Executing command: {}", command);
-        let command_vec: Vec<&str> = command.split(' ').collect();
-        let command_args = &command_vec[1..];
-        Command::new(command_vec[0]).args(command_args).output()
-    }
+// Helper method to execute various internal commands
+// See ResourceFile::delete() and Description::fetch()
+fn execute_command(command: &str) -> std::io::Result<Output> {
+    log::debug!("Executing command: {}", command);
+    let command_vec: Vec<&str> = command.split(' ').collect();
+    let command_args = &command_vec[1..];
+    let mut command = if command_vec[0] == "kubectl" {
+        kubectl::command()
+    } else {
+        Command::new(command_vec[0])
+    };
+    command.args(command_args).tracked_output()
 }
@@ -1,13 +1,248 @@
+use crate::diagnostics;
+use crate::usage;
+use libc::{c_int, EACCES, EEXIST, EIO, ENOENT};
 use serde_json::Value;
-use std::process::Command;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// Name under which kubectl identifies itself to the API server
+// client-go builds the User-Agent from the base name of argv[0]
+// (e.g. "k8sfs-0.1.0/v1.28.2 (linux/amd64) kubernetes/89a4ea3"), which allows cluster
+// admins to attribute API traffic that was caused by the filesystem.
+static USER_AGENT: OnceLock<String> = OnceLock::new();
+
+// Kubeconfig that all kubectl calls use instead of the default one (see in_cluster)
+static KUBECONFIG: OnceLock<PathBuf> = OnceLock::new();
+
+// Field manager of all changes that are made through the filesystem
+// Server-side apply only takes ownership of the fields that are part of a manifest, so fields
+// of other managers (e.g. the replicas of an autoscaler) are not overwritten.
+const FIELD_MANAGER_ARG: &str = "--field-manager=k8sfs";
+
+// Set the name that is used in the User-Agent of every kubectl call
+// If identity is set (e.g. "alice@_mnt_k8s") it is added to the User-Agent as well
+pub fn set_user_agent(identity: Option<&str>) {
+    let mut user_agent = format!("k8sfs-{}", env!("CARGO_PKG_VERSION"));
+    if let Some(identity) = identity {
+        // "/" would cut off everything before it, because only the base name is used
+        user_agent.push_str(&format!("+{}", identity.replace('/', "_")));
+    }
+    if USER_AGENT.set(user_agent).is_err() {
+        log::error!("User-Agent was already set");
+    }
+}
+
+// Let all kubectl calls use a kubeconfig instead of the one of the user
+pub fn set_kubeconfig(path: PathBuf) {
+    if KUBECONFIG.set(path).is_err() {
+        log::error!("Kubeconfig was already set");
+    }
+}
+
+// Create a kubectl command that identifies itself as k8sfs
+pub fn command() -> Command {
+    let mut command = Command::new("kubectl");
+    if let Some(user_agent) = USER_AGENT.get() {
+        command.arg0(user_agent);
+    }
+    if let Some(kubeconfig) = KUBECONFIG.get() {
+        command.env("KUBECONFIG", kubeconfig);
+    }
+    command
+}
+
+// Running kubectl through these methods records the call in the API usage (.k8sfs/api-usage)
+pub trait Tracked {
+    fn tracked_output(&mut self) -> io::Result<Output>;
+    fn tracked_spawn(&mut self) -> io::Result<Child>;
+}
+
+impl Tracked for Command {
+    fn tracked_output(&mut self) -> io::Result<Output> {
+        if self.get_program() != "kubectl" {
+            return self.output();
+        }
+        usage::record(self.get_args());
+        let output = self.output()?;
+        diagnostics::record(self, &output);
+        Ok(output)
+    }
+
+    fn tracked_spawn(&mut self) -> io::Result<Child> {
+        if self.get_program() == "kubectl" {
+            usage::record(self.get_args());
+        }
+        self.spawn()
+    }
+}
+
+// Lists of resources that are shared by all reads during a bulk window
+// Key: (context, namespace, resource)
+type BulkLists = HashMap<(String, String, String), Vec<Value>>;
+
+// While a bulk window is open, single objects are taken from a list of all objects of
+// their namespace, which is only retrieved once. This turns thousands of kubectl calls during
+// recursive traversals (e.g. "grep -r") into one call per namespace and resource type.
+static BULK_WINDOW: Mutex<Option<(Instant, BulkLists)>> = Mutex::new(None);
+
+// Open a bulk window or extend the current one
+pub fn start_bulk_window(duration: Duration) {
+    let mut window = BULK_WINDOW.lock().unwrap();
+    let until = Instant::now() + duration;
+    match window.as_mut() {
+        Some((current_until, _)) if *current_until > Instant::now() => *current_until = until,
+        _ => {
+            log::debug!("Switching to bulk list calls for {:?}", duration);
+            *window = Some((until, HashMap::new()));
+        }
+    }
+}
+
+// Return the objects of a resource type in a namespace from the bulk window
+// Returns None if no bulk window is open
+fn bulk_list(context: &str, namespace: &str, resource: &str) -> Option<Vec<Value>> {
+    let key = (
+        context.to_string(),
+        namespace.to_string(),
+        resource.to_string(),
+    );
+    {
+        let mut window = BULK_WINDOW.lock().unwrap();
+        match window.as_ref() {
+            Some((until, _)) if *until <= Instant::now() => {
+                *window = None;
+                return None;
+            }
+            Some((_, lists)) => {
+                if let Some(list) = lists.get(&key) {
+                    return Some(list.clone());
+                }
+            }
+            None => return None,
+        }
+    }
+
+    // The lock is not held while kubectl is running
+    let list = list_items(context, Some(namespace), resource, &[]).ok()?;
+    if let Some((_, lists)) = BULK_WINDOW.lock().unwrap().as_mut() {
+        lists.insert(key, list.clone());
+    }
+    Some(list)
+}
+
+// Number of kubectl calls that failed since the statistics were last reset
+static API_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+pub fn api_errors() -> u64 {
+    API_ERRORS.load(Ordering::Relaxed)
+}
+
+pub fn reset_api_errors() {
+    API_ERRORS.store(0, Ordering::Relaxed);
+}
+
+// Reasons why a kubectl call about kubernetes resources can fail
+#[derive(Debug, Error)]
+pub enum K8sError {
+    // The API server refused the request because of missing RBAC permissions (HTTP 403)
+    #[error("{0}")]
+    Forbidden(String),
+    // The resource does not exist (HTTP 404)
+    #[error("{0}")]
+    NotFound(String),
+    // The resource exists already (HTTP 409)
+    #[error("{0}")]
+    AlreadyExists(String),
+    // kubectl could not be executed or returned an error
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl K8sError {
+    // Classify what a failed kubectl call printed to stderr
+    // kubectl does not expose the HTTP status code, so we have to rely on the reason that
+    // is printed for errors of the API server
+    // Example: Error from server (Forbidden): pods is forbidden: User "foo" cannot list ...
+    pub fn from_stderr(stderr: &[u8]) -> Self {
+        let stderr = String::from_utf8_lossy(stderr).trim().to_owned();
+        if stderr.contains("(Forbidden)") {
+            K8sError::Forbidden(stderr)
+        } else if stderr.contains("(NotFound)") {
+            K8sError::NotFound(stderr)
+        } else if stderr.contains("(AlreadyExists)") {
+            K8sError::AlreadyExists(stderr)
+        } else {
+            K8sError::Failed(stderr)
+        }
+    }
+
+    // Error code that is returned to the process that caused the kubectl call
+    pub fn errno(&self) -> c_int {
+        match self {
+            K8sError::Forbidden(_) => EACCES,
+            K8sError::NotFound(_) => ENOENT,
+            K8sError::AlreadyExists(_) => EEXIST,
+            K8sError::Failed(_) => EIO,
+        }
+    }
+}
+
+// Return true if kubectl failed because the API server could not be reached (e.g. because the
+// VPN is down), in which case retrying the same call later can succeed
+// Examples: Unable to connect to the server: dial tcp 10.0.0.1:443: i/o timeout
+//           The connection to the server 10.0.0.1:6443 was refused - did you specify ...
+pub fn is_unreachable(message: &str) -> bool {
+    message.contains("Unable to connect to the server")
+        || message.contains("The connection to the server")
+}
+
+// Retrieve the version of kubectl (e.g. v1.28.2)
+pub fn client_version() -> Result<String, K8sError> {
+    let output = command()
+        .args(["version", "--client", "-ojson"])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+    if !output.status.success() {
+        return Err(K8sError::from_stderr(&output.stderr));
+    }
+    let version: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|error| K8sError::Failed(format!("Could not parse kubectl output: {}", error)))?;
+
+    Ok(version
+        .pointer("/clientVersion/gitVersion")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+        .to_string())
+}
+
+// Retrieve the versions of kubectl and the API server of a context
+//   {"clientVersion": {"major": "1", "minor": "28", ...}, "serverVersion": {...}}
+pub fn versions(context: &str) -> Result<Value, K8sError> {
+    let output = command()
+        .args(["--context", context, "version", "-ojson"])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+    if !output.status.success() {
+        return Err(K8sError::from_stderr(&output.stderr));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|error| K8sError::Failed(format!("Could not parse kubectl output: {}", error)))
+}
 
 // Retrieve the default context that will be used by kubectl
 pub fn current_context() -> String {
     String::from_utf8(
-        Command::new("kubectl")
+        command()
             .arg("config")
             .arg("current-context")
-            .output()
+            .tracked_output()
             .expect("Could not determine the current context")
             .stdout,
     )
@@ -17,84 +252,597 @@ pub fn current_context() -> String {
 }
 
 // Create a kubernetes namespace in a specific context
-pub fn create_namespace(name: &str, context: &str) -> bool {
-    let status = Command::new("kubectl")
+// Returns the name of the namespace as it was returned by the API server
+pub fn create_namespace(name: &str, context: &str) -> Result<String, K8sError> {
+    let output = command()
         .arg("--context")
         .arg(context)
         .arg("create")
         .arg("namespace")
         .arg(name)
-        .status();
+        .arg("-ojson")
+        .tracked_output();
 
-    if let Ok(status) = status {
-        status.success()
-    } else {
-        false
+    match output {
+        Ok(output) if output.status.success() => {
+            let created: Value = serde_json::from_slice(&output.stdout).unwrap_or(Value::Null);
+            Ok(created
+                .pointer("/metadata/name")
+                .and_then(Value::as_str)
+                .unwrap_or(name)
+                .to_string())
+        }
+        Ok(output) => {
+            let error = K8sError::from_stderr(&output.stderr);
+            log::debug!("Could not create namespace {}: {}", name, error);
+            Err(error)
+        }
+        Err(error) => {
+            log::debug!("Could not execute kubectl\nExited with {:?}", error);
+            Err(K8sError::Failed(error.to_string()))
+        }
     }
 }
 
-// List all namespaces in a specific context
-pub fn namespaces(context: &str) -> Vec<String> {
-    retrieve_k8s_resources(vec!["--context", context, "namespace", "-ojson"])
+// Outcome of applying (or deleting) a manifest to the cluster
+pub struct ApplyOutcome {
+    pub success: bool,
+    // kubectl prints one line per object (e.g. "deployment.apps/web created")
+    // Errors for objects that could not be applied are appended at the end
+    pub report: String,
+    // Warnings of the API server (e.g. deprecated APIs or PodSecurity violations)
+    pub warnings: Vec<String>,
+    // Fields of the manifest are owned by another field manager (see --force-conflicts)
+    pub conflict: bool,
+    // Only what kubectl printed to stdout (e.g. JSON output)
+    stdout: String,
 }
 
-// List all pods in a specific namespace in a specific context
-pub fn pods(context: &str, namespace: &str) -> Vec<String> {
-    retrieve_k8s_resources(vec![
+impl ApplyOutcome {
+    pub fn new(success: bool, report: String) -> Self {
+        ApplyOutcome {
+            success,
+            report,
+            warnings: Vec::new(),
+            conflict: false,
+            stdout: String::new(),
+        }
+    }
+}
+
+// Apply a manifest, which may contain multiple YAML documents, in a specific context
+// Objects are applied server-side, with force_conflicts fields of other managers are taken
+// over instead of failing.
+pub fn apply(manifest: &[u8], context: &str, force_conflicts: bool) -> ApplyOutcome {
+    log::debug!("Applying manifest in context {}", context);
+    let mut kubectl_args = vec![
         "--context",
         context,
-        "--namespace",
+        "apply",
+        "--server-side",
+        FIELD_MANAGER_ARG,
+    ];
+    if force_conflicts {
+        kubectl_args.push("--force-conflicts");
+    }
+    kubectl_args.extend(["-f", "-"]);
+    run_with_manifest(&kubectl_args, manifest)
+}
+
+// Check if a manifest could be applied without changing anything
+// The API server validates the objects (including admission webhooks).
+pub fn apply_dry_run(manifest: &[u8], context: &str) -> ApplyOutcome {
+    log::debug!("Applying manifest in context {} (dry run)", context);
+    run_with_manifest(
+        &[
+            "--context",
+            context,
+            "apply",
+            "--server-side",
+            FIELD_MANAGER_ARG,
+            "--dry-run=server",
+            "-f",
+            "-",
+        ],
+        manifest,
+    )
+}
+
+// Wait until the custom resource definitions of a manifest can be used
+pub fn wait_established(manifest: &[u8], context: &str, timeout: Duration) -> ApplyOutcome {
+    let timeout = format!("--timeout={}s", timeout.as_secs());
+    run_with_manifest(
+        &[
+            "--context",
+            context,
+            "wait",
+            "--for=condition=Established",
+            &timeout,
+            "-f",
+            "-",
+        ],
+        manifest,
+    )
+}
+
+// Delete all objects of a manifest in a specific context
+// Returns what kubectl printed for the deleted objects (e.g. pod "web-0" deleted)
+pub fn delete(manifest: &[u8], context: &str) -> Result<String, K8sError> {
+    log::debug!("Deleting manifest in context {}", context);
+    let outcome = run_with_manifest(
+        &[
+            "--context",
+            context,
+            "delete",
+            "--ignore-not-found",
+            "-f",
+            "-",
+        ],
+        manifest,
+    );
+    if outcome.success {
+        Ok(outcome.report)
+    } else {
+        Err(K8sError::from_stderr(outcome.report.as_bytes()))
+    }
+}
+
+// Delete a namespace without waiting until its content is gone
+pub fn delete_namespace(name: &str, context: &str) -> Result<(), K8sError> {
+    let output = command()
+        .args([
+            "--context",
+            context,
+            "delete",
+            "namespace",
+            name,
+            "--wait=false",
+        ])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(K8sError::from_stderr(&output.stderr))
+    }
+}
+
+// Remove all finalizers of an object
+// Objects that are stuck terminating are deleted once their finalizers are gone
+pub fn remove_finalizers(
+    context: &str,
+    namespace: Option<&str>,
+    resource: &str,
+    name: &str,
+) -> ApplyOutcome {
+    merge_patch(
+        context,
         namespace,
-        "pods",
-        "-ojson",
-    ])
+        resource,
+        name,
+        r#"{"metadata":{"finalizers":null}}"#,
+    )
 }
 
-// Helper method to retieve kubernetes resources
-fn retrieve_k8s_resources(kubectl_args: Vec<&str>) -> Vec<String> {
-    log::debug!("Trying to retrieve k8s resources with {:?}", kubectl_args);
-    // Vec to store the retrieved resource names
-    let mut resources = Vec::new();
-    let cmd_output = Command::new("kubectl")
-        .arg("get")
+// Change an object with a JSON merge patch, fields that are not part of the patch are kept
+// null removes a field (e.g. a key of a ConfigMap)
+pub fn merge_patch(
+    context: &str,
+    namespace: Option<&str>,
+    resource: &str,
+    name: &str,
+    patch: &str,
+) -> ApplyOutcome {
+    let mut kubectl_args = vec!["--context", context];
+    if let Some(namespace) = namespace {
+        kubectl_args.extend(["--namespace", namespace]);
+    }
+    kubectl_args.extend(["patch", resource, name, "--type=merge", FIELD_MANAGER_ARG]);
+    kubectl_args.extend(["--patch", patch]);
+    run_with_manifest(&kubectl_args, &[])
+}
+
+// Remove the finalizers of a terminating namespace through the finalize API
+// This skips the cleanup of the content of the namespace, which might leave orphaned objects.
+pub fn finalize_namespace(context: &str, name: &str) -> ApplyOutcome {
+    let mut namespace = match get_object(context, None, "namespaces", name) {
+        Ok(namespace) => namespace,
+        Err(error) => return ApplyOutcome::new(false, error.to_string()),
+    };
+    namespace["spec"]["finalizers"] = Value::Array(Vec::new());
+    let path = format!("/api/v1/namespaces/{}/finalize", name);
+    run_with_manifest(
+        &["--context", context, "replace", "--raw", &path, "-f", "-"],
+        namespace.to_string().as_bytes(),
+    )
+}
+
+// Retrieve the current state of all objects of a manifest
+// Objects that do not exist (yet) are skipped
+pub fn get_manifest_objects(manifest: &[u8], context: &str) -> Result<Vec<Value>, K8sError> {
+    let outcome = run_with_manifest(
+        &[
+            "--context",
+            context,
+            "get",
+            "--ignore-not-found",
+            "-ojson",
+            "-f",
+            "-",
+        ],
+        manifest,
+    );
+    if !outcome.success {
+        return Err(K8sError::Failed(outcome.report.trim().to_owned()));
+    }
+    // Nothing is printed if none of the objects exist
+    if outcome.stdout.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let result: Value = serde_json::from_str(&outcome.stdout)
+        .map_err(|error| K8sError::Failed(format!("Could not parse kubectl output: {}", error)))?;
+    // A single object is printed as is, multiple objects are wrapped in a list
+    match result.get("items").and_then(Value::as_array) {
+        Some(items) => Ok(items.clone()),
+        None => Ok(vec![result]),
+    }
+}
+
+// Execute kubectl with a manifest on stdin
+fn run_with_manifest(kubectl_args: &[&str], manifest: &[u8]) -> ApplyOutcome {
+    let mut command = command();
+    let child = command
         .args(kubectl_args)
-        .output();
-
-    if let Ok(cmd_output) = cmd_output {
-        let result: Value = serde_json::from_slice(&cmd_output.stdout).unwrap_or(Value::Null);
-        if !result.is_null() {
-            // Option.unwrap_or requires that we use a reference because Value.get return a Option<&Value>
-            // so Option.unwrap_or uses that too
-            for resource_object in result
-                .get("items")
-                .unwrap_or(&Value::Array(vec![]))
-                .as_array()
-                .unwrap_or(&Vec::<Value>::new())
-            {
-                if let Some(resource_object) = resource_object.get("metadata") {
-                    resources.push(
-                        resource_object
-                            .get("name")
-                            .unwrap()
-                            .to_string()
-                            .replace('\"', ""),
-                    );
-                } else {
-                    log::debug!(
-                        "Could not get namespace metadata from {:?}",
-                        resource_object
-                    );
-                }
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .tracked_spawn();
+
+    let output = child.and_then(|mut child| {
+        // stdin has to be closed before waiting, otherwise kubectl waits for more input
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(manifest)?;
+        }
+        child.wait_with_output()
+    });
+
+    match output {
+        Ok(output) => {
+            diagnostics::record(&command, &output);
+            if !output.status.success() {
+                API_ERRORS.fetch_add(1, Ordering::Relaxed);
             }
-        } else {
-            log::debug!("Could not parse kubectl output");
+            let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // kubectl prints the warnings of the API server as "Warning: <message>"
+            let warnings = stderr
+                .lines()
+                .filter_map(|line| line.strip_prefix("Warning: "))
+                .map(String::from)
+                .collect();
+            // e.g. "Apply failed with 1 conflict: conflict with "kubectl-edit" ..."
+            let conflict = !output.status.success() && stderr.contains("Apply failed with");
+            ApplyOutcome {
+                success: output.status.success(),
+                report: format!("{}{}", stdout, stderr),
+                warnings,
+                conflict,
+                stdout,
+            }
+        }
+        Err(error) => {
+            API_ERRORS.fetch_add(1, Ordering::Relaxed);
+            log::debug!("Could not execute kubectl\nExited with {:?}", error);
+            ApplyOutcome::new(false, format!("Could not execute kubectl: {}\n", error))
         }
+    }
+}
+
+// List all namespaces in a specific context
+pub fn namespaces(context: &str) -> Result<Vec<Value>, K8sError> {
+    get_items(context, None, "namespaces", &[])
+}
+
+// List all pods in a specific namespace in a specific context
+pub fn pods(context: &str, namespace: &str) -> Result<Vec<Value>, K8sError> {
+    get_items(context, Some(namespace), "pods", &[])
+}
+
+// Retrieve the logs of a container
+pub fn logs(
+    context: &str,
+    namespace: &str,
+    pod: &str,
+    container: &str,
+) -> Result<Vec<u8>, K8sError> {
+    log::debug!("Retrieving logs of {}/{}/{}", namespace, pod, container);
+    let output = command()
+        .args(["--context", context, "--namespace", namespace])
+        .args(["logs", pod, "--container", container])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(K8sError::from_stderr(&output.stderr))
+    }
+}
+
+// Retrieve a log file of a node through the logs endpoint of the kubelet
+// An empty path lists the available log files (/var/log on the node).
+pub fn node_log(context: &str, node: &str, path: &str) -> Result<Vec<u8>, K8sError> {
+    get_raw(
+        context,
+        &format!("/api/v1/nodes/{}/proxy/logs/{}", node, path),
+    )
+}
+
+// Retrieve an HTTP endpoint of a service through the API server proxy
+// service is [https:]<service>:<port>
+pub fn service_proxy(
+    context: &str,
+    namespace: &str,
+    service: &str,
+    path: &str,
+) -> Result<Vec<u8>, K8sError> {
+    get_raw(
+        context,
+        &format!(
+            "/api/v1/namespaces/{}/services/{}/proxy/{}",
+            namespace, service, path
+        ),
+    )
+}
+
+// Estimate the number of objects of a resource type in a namespace without listing them
+// Only the first object is retrieved, the API server tells how many are remaining.
+pub fn count_items(context: &str, namespace: &str, resource: &str) -> Result<u64, K8sError> {
+    let path = format!("/api/v1/namespaces/{}/{}?limit=1", namespace, resource);
+    let list: Value = serde_json::from_slice(&get_raw(context, &path)?)
+        .map_err(|error| K8sError::Failed(format!("Could not parse list: {}", error)))?;
+    let listed = list
+        .pointer("/items")
+        .and_then(Value::as_array)
+        .map_or(0, Vec::len);
+    let remaining = list
+        .pointer("/metadata/remainingItemCount")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    Ok(listed as u64 + remaining)
+}
+
+// List one page of the objects of a resource type in a namespace
+// Returns the objects and the continue token of the next page, which is None for the last page.
+// Continue tokens are URL safe base64, so they can be passed on as they are.
+pub fn list_page(
+    context: &str,
+    namespace: &str,
+    resource: &str,
+    limit: u64,
+    continue_token: Option<&str>,
+) -> Result<(Vec<Value>, Option<String>), K8sError> {
+    let mut path = format!(
+        "/api/v1/namespaces/{}/{}?limit={}",
+        namespace, resource, limit
+    );
+    if let Some(continue_token) = continue_token {
+        path.push_str(&format!("&continue={}", continue_token));
+    }
+    let mut list: Value = serde_json::from_slice(&get_raw(context, &path)?)
+        .map_err(|error| K8sError::Failed(format!("Could not parse list: {}", error)))?;
+    let continue_token = list
+        .pointer("/metadata/continue")
+        .and_then(Value::as_str)
+        .filter(|token| !token.is_empty())
+        .map(String::from);
+    let items = match list.pointer_mut("/items").map(Value::take) {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+    Ok((items, continue_token))
+}
+
+// Verbs that the API server supports for a resource of the core API group (e.g. pods)
+pub fn verbs(context: &str, resource: &str) -> Result<Vec<String>, K8sError> {
+    let discovery: Value = serde_json::from_slice(&get_raw(context, "/api/v1")?)
+        .map_err(|error| K8sError::Failed(format!("Could not parse discovery: {}", error)))?;
+    let verbs = discovery
+        .pointer("/resources")
+        .and_then(Value::as_array)
+        .and_then(|resources| {
+            resources
+                .iter()
+                .find(|entry| entry.pointer("/name").and_then(Value::as_str) == Some(resource))
+        })
+        .and_then(|entry| entry.pointer("/verbs"))
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            K8sError::Failed(format!("{} are not served by the API server", resource))
+        })?;
+    Ok(verbs
+        .iter()
+        .filter_map(Value::as_str)
+        .map(String::from)
+        .collect())
+}
+
+// Retrieve a path of the API server as it is
+fn get_raw(context: &str, raw_path: &str) -> Result<Vec<u8>, K8sError> {
+    log::debug!("Retrieving {}", raw_path);
+    let output = command()
+        .args(["--context", context, "get", "--raw", raw_path])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
     } else {
-        log::error!(
-            "Could not get kubernetes resources\nExited with {:?}",
-            cmd_output
-        )
+        API_ERRORS.fetch_add(1, Ordering::Relaxed);
+        Err(K8sError::from_stderr(&output.stderr))
+    }
+}
+
+// Check if the current user is allowed to do something with a resource type
+// If namespace is None, the permission is checked for all namespaces
+pub fn can_i(
+    context: &str,
+    verb: &str,
+    resource: &str,
+    namespace: Option<&str>,
+) -> Result<bool, K8sError> {
+    let mut kubectl_args = vec!["--context", context];
+    match namespace {
+        Some(namespace) => kubectl_args.extend(["--namespace", namespace]),
+        None => kubectl_args.push("--all-namespaces"),
+    }
+    kubectl_args.extend(["auth", "can-i", verb, resource]);
+    log::debug!("Checking permissions with {:?}", kubectl_args);
+    let output = command()
+        .args(kubectl_args)
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+
+    // kubectl prints the answer and exits with 1 if the answer is no
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(K8sError::from_stderr(&output.stderr)),
+    }
+}
+
+// Retrieve the part of the kubeconfig that is needed to connect to a context, unredacted
+// Files that it refers to (e.g. relative paths to CA certificates) are embedded, so that it
+// can be used from anywhere.
+pub fn kubeconfig(context: &str) -> Result<Value, K8sError> {
+    let output = command()
+        .args(["config", "view", "--minify", "--flatten", "--raw"])
+        .args(["--context", context, "-ojson"])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+    if !output.status.success() {
+        return Err(K8sError::from_stderr(&output.stderr));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|error| K8sError::Failed(format!("Could not parse kubeconfig: {}", error)))
+}
+
+// Retrieve the names of all contexts of the kubeconfig
+pub fn contexts() -> Result<Vec<String>, K8sError> {
+    let output = command()
+        .args(["config", "get-contexts", "-oname"])
+        .tracked_output()
+        .map_err(|error| K8sError::Failed(error.to_string()))?;
+    if !output.status.success() {
+        return Err(K8sError::from_stderr(&output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+// Retrieve the full objects of a kubernetes resource type
+// If namespace is None, the resources are retrieved from all namespaces (or the resource
+// type is not namespaced)
+pub fn get_items(
+    context: &str,
+    namespace: Option<&str>,
+    resource: &str,
+    extra_args: &[&str],
+) -> Result<Vec<Value>, K8sError> {
+    if let (Some(namespace), []) = (namespace, extra_args) {
+        if let Some(list) = bulk_list(context, namespace, resource) {
+            return Ok(list);
+        }
+    }
+    list_items(context, namespace, resource, extra_args)
+}
+
+// Retrieve the full objects of a kubernetes resource type without using the bulk window
+fn list_items(
+    context: &str,
+    namespace: Option<&str>,
+    resource: &str,
+    extra_args: &[&str],
+) -> Result<Vec<Value>, K8sError> {
+    let mut kubectl_args = vec!["--context", context];
+    match namespace {
+        Some(namespace) => kubectl_args.extend(["--namespace", namespace]),
+        None => kubectl_args.push("--all-namespaces"),
+    }
+    kubectl_args.extend([resource, "-ojson"]);
+    kubectl_args.extend(extra_args);
+
+    let result = get_json(kubectl_args)?;
+    Ok(result
+        .get("items")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default())
+}
+
+// Retrieve a single kubernetes object
+pub fn get_object(
+    context: &str,
+    namespace: Option<&str>,
+    resource: &str,
+    name: &str,
+) -> Result<Value, K8sError> {
+    // Objects that are missing from the bulk list (e.g. because they were just created)
+    // are retrieved on their own
+    let bulk_object = namespace
+        .and_then(|namespace| bulk_list(context, namespace, resource))
+        .and_then(|list| {
+            list.into_iter().find(|object| {
+                object.pointer("/metadata/name").and_then(Value::as_str) == Some(name)
+            })
+        });
+    if let Some(object) = bulk_object {
+        return Ok(object);
+    }
+
+    let mut kubectl_args = vec!["--context", context];
+    if let Some(namespace) = namespace {
+        kubectl_args.extend(["--namespace", namespace]);
+    }
+    kubectl_args.extend([resource, name, "-ojson"]);
+
+    get_json(kubectl_args)
+}
+
+// Execute "kubectl get" with the given arguments and parse its JSON output
+// The arguments have to contain "-ojson"
+pub fn get_json(kubectl_args: Vec<&str>) -> Result<Value, K8sError> {
+    log::debug!("Trying to retrieve k8s resources with {:?}", kubectl_args);
+    let cmd_output = command().arg("get").args(kubectl_args).tracked_output();
+
+    let cmd_output = match cmd_output {
+        Ok(cmd_output) => cmd_output,
+        Err(error) => {
+            API_ERRORS.fetch_add(1, Ordering::Relaxed);
+            log::debug!("Could not execute kubectl\nExited with {:?}", error);
+            return Err(K8sError::Failed(error.to_string()));
+        }
+    };
+
+    if !cmd_output.status.success() {
+        API_ERRORS.fetch_add(1, Ordering::Relaxed);
+        let error = K8sError::from_stderr(&cmd_output.stderr);
+        log::debug!("Could not get kubernetes resources\nFailed with {}", error);
+        return Err(error);
+    }
+
+    let result: Value = serde_json::from_slice(&cmd_output.stdout).unwrap_or(Value::Null);
+    if result.is_null() {
+        log::debug!("Could not parse kubectl output");
+        return Err(K8sError::Failed(String::from(
+            "Could not parse kubectl output",
+        )));
     }
 
-    resources
+    Ok(result)
 }
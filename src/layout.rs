@@ -0,0 +1,35 @@
+// How the resources of a namespace are laid out in its directory (see --layout)
+// The default layout groups resources by kind (<pod>, services/<service>, ...). The
+// group-version layout adds _api/<group>/<version>/<Kind>/<name> symlinks to the same resources,
+// so that kinds with the same name in different API groups can be told apart by their path. The
+// tree is kept under a reserved name, so that it does not collide with pods that are named like
+// an API group. Only the kinds that the mount shows are listed there, custom resources need API
+// discovery, which the mount does not do.
+
+// Name of the directory inside namespace directories with the group-version layout
+pub const API_DIRECTORY_NAME: &str = "_api";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    Kind,
+    GroupVersion,
+}
+
+impl Layout {
+    pub fn from_name(name: &str) -> Option<Layout> {
+        match name {
+            "kind" => Some(Layout::Kind),
+            "group-version" => Some(Layout::GroupVersion),
+            _ => None,
+        }
+    }
+}
+
+// Kinds that have a directory in the group-version layout (group, version, kind)
+// The core group, which has no name in the API, is called "core".
+pub const API_KINDS: [(&str, &str, &str); 4] = [
+    ("core", "v1", "Pod"),
+    ("core", "v1", "Service"),
+    ("core", "v1", "ConfigMap"),
+    ("apps", "v1", "Deployment"),
+];
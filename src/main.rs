@@ -1,48 +1,851 @@
+mod activity;
+mod admission;
+mod cache;
+mod capabilities;
+mod certs;
+mod check;
+mod config;
+mod containers;
+mod cost;
+mod daemon;
+mod diagnostics;
+mod drain;
+mod encoding;
+mod events;
+mod exec;
 mod filesystem;
+mod gitops;
+mod handles;
+mod health;
+mod ignore;
+mod in_cluster;
+mod journal;
 mod k8s_resource;
 mod kubectl;
+mod layout;
+mod modes;
+mod notify;
+mod pagination;
+mod pending;
+mod prefetch;
+mod priority;
+mod prune;
+mod query;
+mod quota;
+mod rbac;
+mod renderer;
+mod reports;
+mod rollout;
+mod scheduling;
+mod sidecar;
+mod stats;
+mod storage;
+mod stream;
+mod templates;
+mod timeline;
+mod trash;
+mod traversal;
+mod tree;
+mod usage;
+mod version;
+mod watcher;
+mod xattr;
+mod yaml;
 
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use config::Config;
 use env_logger::Env;
-use filesystem::K8sFS;
-use fuser::{self, MountOption};
+use filesystem::{K8sFS, Recovery};
+use k8s_resource::ResourceType;
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Name of the binary when k8sfs is installed as a mount helper
+// mount(8) executes /sbin/mount.<type> for `mount -t <type>`
+const MOUNT_HELPER_NAME: &str = "mount.k8sfs";
+// How often we check whether the idle timeout was reached
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// How often we try to mount again after the connection to the kernel broke in a row
+const MAX_REMOUNT_ATTEMPTS: u32 = 5;
+// Wait before the first remount, doubled after every attempt that failed again
+const REMOUNT_BACKOFF: Duration = Duration::from_secs(1);
+// Sessions that ran at least this long reset the number of remount attempts
+const REMOUNT_RESET_AFTER: Duration = Duration::from_secs(60);
+
+// Tells the process that started the mount helper that the filesystem is mounted (see daemonize)
+static MOUNTED_SIGNAL: Mutex<Option<UnixStream>> = Mutex::new(None);
 
 fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
-    let matches = Command::new("k8sfs")
+
+    if is_mount_helper() {
+        let config = Config::from_mount_helper_args(&mount_helper_command().get_matches())
+            .unwrap_or_else(|error| exit_with_error(&error));
+        // mount(8) waits for the helper to exit, so we have to continue in the background
+        daemonize();
+        mount(config);
+        return;
+    }
+
+    let matches = command().get_matches();
+    let socket_path = matches
+        .get_one::<String>("socket")
+        .map(PathBuf::from)
+        .unwrap_or_else(daemon::default_socket_path);
+    match matches.subcommand() {
+        Some(("mount", matches)) => {
+            mount(Config::from_args(matches).unwrap_or_else(|error| exit_with_error(&error)))
+        }
+        Some(("check", matches)) => {
+            let namespaces: Vec<String> = matches
+                .get_many::<String>("namespace")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            if !check::run(
+                matches.get_one::<String>("context").map(String::as_str),
+                &namespaces,
+                matches.get_flag("allow-write"),
+            ) {
+                std::process::exit(1);
+            }
+        }
+        Some(("doctor", matches)) => {
+            let namespaces: Vec<String> = matches
+                .get_many::<String>("namespace")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            if !check::doctor(
+                matches.get_one::<String>("context").map(String::as_str),
+                &namespaces,
+                matches.get_flag("allow-write"),
+            ) {
+                std::process::exit(1);
+            }
+        }
+        Some(("kinds", _)) => print_kinds(),
+        Some(("export", matches)) => export(matches),
+        Some(("apply-tree", matches)) => {
+            if !tree::apply(
+                Path::new(matches.get_one::<String>("directory").unwrap()),
+                matches.get_one::<String>("context").map(String::as_str),
+                matches.get_flag("dry-run"),
+                matches.get_flag("prune"),
+                matches.get_flag("force-conflicts"),
+            ) {
+                std::process::exit(1);
+            }
+        }
+        Some(("daemon", matches)) => daemon_command(matches, &socket_path),
+        Some(("sidecar", _)) => {
+            run_sidecar(Config::from_env().unwrap_or_else(|error| exit_with_error(&error)))
+        }
+        // "k8sfs <mountpoint>" is the same as "k8sfs mount <mountpoint>"
+        _ => mount(Config::from_args(&matches).unwrap_or_else(|error| exit_with_error(&error))),
+    }
+}
+
+// Run the daemon or send a request to it
+fn daemon_command(matches: &ArgMatches, socket_path: &Path) {
+    match matches.subcommand() {
+        Some(("start", _)) => {
+            // The identity of --identify depends on the mount point, which differs per mount
+            kubectl::set_user_agent(None);
+            // All mounts of the daemon share one log file
+            if let Some(log_file) = diagnostics::default_log_file("daemon") {
+                diagnostics::set_log_file(log_file);
+            }
+            if let Err(error) = daemon::run(socket_path, parse_mount_args) {
+                exit_with_error(&error.to_string());
+            }
+        }
+        Some(("mount", _)) => {
+            // The daemon parses the arguments itself, so they are passed on as they are
+            let args: Vec<String> = std::env::args()
+                .skip_while(|arg| arg != "mount")
+                .skip(1)
+                .collect();
+            send_to_daemon(socket_path, json!({"command": "mount", "args": args}));
+        }
+        Some(("umount", matches)) => send_to_daemon(
+            socket_path,
+            json!({
+                "command": "umount",
+                "mountpoint": matches.get_one::<String>("mountpoint"),
+            }),
+        ),
+        Some(("status", _)) => send_to_daemon(socket_path, json!({"command": "status"})),
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+// Export the manifests of a context to a directory (see "k8sfs export")
+fn export(matches: &ArgMatches) {
+    let directory = matches.get_one::<String>("directory").unwrap();
+    let config = Config::new(directory)
+        .context(matches.get_one::<String>("context").cloned())
+        .namespaces(
+            matches
+                .get_many::<String>("namespace")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+        );
+    match K8sFS::new(config).export(Path::new(directory)) {
+        Ok(exported) => println!("Exported {} manifests to {}", exported, directory),
+        Err(error) => exit_with_error(&format!("Could not export to {}: {}", directory, error)),
+    }
+}
+
+// Print the kinds of kubernetes resources that are shown in the filesystem
+fn print_kinds() {
+    println!("{:<12}WRITE OPERATIONS", "KIND");
+    for kind in ResourceType::kinds() {
+        let operations = kind.write_operations();
+        println!(
+            "{:<12}{}",
+            kind.kubectl_name(),
+            if operations.is_empty() {
+                String::from("-")
+            } else {
+                operations.join(", ")
+            }
+        );
+    }
+    println!("\nManifests of any kind can be applied by writing them to .k8sfs/apply (with --allow-write).");
+}
+
+// Prepare kubectl for the calls of a mount
+fn set_up_kubectl(config: &Config) {
+    kubectl::set_user_agent(config.identity().as_deref());
+    if config.in_cluster {
+        if let Err(error) = in_cluster::set_up() {
+            exit_with_error(&format!("Could not use the service account: {}", error));
+        }
+    }
+    if let Some(log_file) = config.kubectl_log_file() {
+        diagnostics::set_log_file(log_file);
+    }
+}
+
+// Mount the filesystem in a sidecar container and unmount it when the container is stopped
+// Other containers of the pod see the mount through a shared volume with mount propagation.
+fn run_sidecar(config: Config) {
+    set_up_kubectl(&config);
+    sidecar::handle_termination();
+    let mountpoint = config.mountpoint.clone();
+    if let Err(error) = std::fs::create_dir_all(&mountpoint) {
+        exit_with_error(&format!("Could not create {}: {}", mountpoint, error));
+    }
+    let fs = K8sFS::new(config);
+    let mount_options = fs.mount_options();
+    let changes = fs.change_notifier();
+    let activity = fs.activity();
+    log::info!("Mounting K8sFS at {}...", mountpoint);
+    let session = fuser::spawn_mount2(fs, &mountpoint, &mount_options)
+        .unwrap_or_else(|error| exit_with_error(&format!("Could not mount: {}", error)));
+    changes.set_notifier(session.notifier());
+    while !activity.is_destroyed() && !sidecar::is_terminated() {
+        thread::sleep(IDLE_CHECK_INTERVAL);
+    }
+    log::info!("Unmounting K8sFS...");
+    drop(session);
+    // Other containers might still use the mount, which keeps a regular unmount from working
+    detach_mountpoint(&mountpoint);
+}
+
+// Mount the filesystem and block until it is unmounted
+fn mount(config: Config) {
+    set_up_kubectl(&config);
+
+    let mountpoint = config.mountpoint.clone();
+    let idle_timeout = config.idle_timeout;
+    let fs = K8sFS::new(config);
+    let mount_options = fs.mount_options();
+    let changes = fs.change_notifier();
+
+    log::info!("Mounting K8sFS...");
+    if let Some(idle_timeout) = idle_timeout {
+        let activity = fs.activity();
+        // The session is unmounted when it is dropped
+        let session = fuser::spawn_mount2(fs, mountpoint, &mount_options).unwrap_or_else(|error| {
+            exit_with_error(&format!("Could not mount the filesystem: {}", error))
+        });
+        report_mounted();
+        changes.set_notifier(session.notifier());
+        while !activity.is_destroyed() {
+            if activity.idle_for() >= idle_timeout {
+                log::info!(
+                    "Filesystem was not used for {} seconds, unmounting K8sFS...",
+                    idle_timeout.as_secs()
+                );
+                break;
+            }
+            thread::sleep(IDLE_CHECK_INTERVAL);
+        }
+    } else {
+        let recovery = Recovery::default();
+        let mut fs = fs.with_recovery(recovery.clone());
+        // Resolved before mounting, the mount point cannot be resolved anymore once the
+        // connection broke
+        let mount_path =
+            std::fs::canonicalize(&mountpoint).unwrap_or_else(|_| PathBuf::from(&mountpoint));
+        let mut attempts = 0;
+        loop {
+            let mut session = match fuser::Session::new(fs, Path::new(&mountpoint), &mount_options)
+            {
+                Ok(session) => session,
+                Err(error) => {
+                    exit_with_error(&format!("Could not mount the filesystem: {}", error))
+                }
+            };
+            report_mounted();
+            changes.set_notifier(session.notifier());
+            let started = Instant::now();
+            let result = session.run();
+            // The session also ends without an error when the kernel aborted the connection,
+            // but only an unmount removes the mount point
+            let broken = result.is_err() || is_mounted(&mount_path);
+            // Dropping the session destroys the filesystem, which hands it over to recovery
+            drop(session);
+            if !broken {
+                return;
+            }
+
+            // The kernel aborted the session (e.g. the connection broke during suspend)
+            match result {
+                Err(error) => log::error!("Connection to the kernel broke: {}", error),
+                Ok(()) => log::error!("Connection to the kernel was aborted"),
+            }
+            if started.elapsed() >= REMOUNT_RESET_AFTER {
+                attempts = 0;
+            }
+            attempts += 1;
+            let Some(recovered) = recovery.lock().unwrap().take() else {
+                exit_with_error("Filesystem was not handed over, giving up");
+            };
+            if attempts > MAX_REMOUNT_ATTEMPTS {
+                exit_with_error(&format!(
+                    "Giving up after {} remounts",
+                    MAX_REMOUNT_ATTEMPTS
+                ));
+            }
+            detach_mountpoint(&mountpoint);
+            thread::sleep(REMOUNT_BACKOFF * 2u32.pow(attempts - 1));
+            log::info!(
+                "Remounting K8sFS (attempt {} of {})...",
+                attempts,
+                MAX_REMOUNT_ATTEMPTS
+            );
+            fs = recovered.remount(recovery.clone());
+        }
+    }
+}
+
+// Whether a file system is still mounted at the (canonical) path
+fn is_mounted(path: &Path) -> bool {
+    let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+        return false;
+    };
+    mountinfo
+        .lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .any(|mounted| Path::new(&unescape_mount_path(mounted)) == path)
+}
+
+// Mount points in mountinfo have spaces, tabs, newlines and backslashes as octal escapes
+fn unescape_mount_path(escaped: &str) -> String {
+    let mut path = String::new();
+    let mut rest = escaped;
+    while let Some(index) = rest.find('\\') {
+        path.push_str(&rest[..index]);
+        let code = rest.get(index + 1..index + 4);
+        match code.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                path.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                path.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    path.push_str(rest);
+    path
+}
+
+// Lazily unmount a mount point whose FUSE connection broke
+// Processes that still use it keep the stale mount, new ones see the remounted filesystem.
+fn detach_mountpoint(mountpoint: &str) {
+    let detached = std::process::Command::new("fusermount")
+        .args(["-u", "-z"])
+        .arg(mountpoint)
+        .output();
+    match detached {
+        Ok(output) if !output.status.success() => log::debug!(
+            "Could not detach {}: {}",
+            mountpoint,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ),
+        Err(error) => log::debug!("Could not run fusermount: {}", error),
+        Ok(_) => (),
+    }
+}
+
+// Send a request to the daemon and print its output
+fn send_to_daemon(socket_path: &Path, request: serde_json::Value) {
+    match daemon::request(socket_path, request) {
+        Ok(output) => print!("{}", output),
+        Err(error) => exit_with_error(&error),
+    }
+}
+
+// Parse the arguments of "k8sfs daemon mount" (used by the daemon)
+fn parse_mount_args(args: &[String]) -> Result<Config, String> {
+    let matches = Command::new("k8sfs daemon mount")
+        .args(mount_args())
+        .arg(socket_arg())
+        .try_get_matches_from(std::iter::once(&String::from("k8sfs daemon mount")).chain(args))
+        .map_err(|error| error.to_string())?;
+    Config::from_args(&matches)
+}
+
+// Command line interface of k8sfs
+fn command() -> Command {
+    Command::new("k8sfs")
+        .version("0.1.0")
+        .author("blinxen")
+        .args(mount_args())
+        .arg(socket_arg().global(true))
+        // "k8sfs <mountpoint>" is kept as a shortcut for "k8sfs mount <mountpoint>"
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("mount")
+                .about("Mount a kubernetes context")
+                .args(mount_args()),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check that a context can be mounted (connectivity and RBAC permissions)")
+                .args(mount_args().into_iter().filter(|arg| {
+                    ["allow-write", "context", "namespace"].contains(&arg.get_id().as_str())
+                })),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check the environment (FUSE, kubectl, kubeconfig) and the cluster and tell how to fix problems")
+                .args(mount_args().into_iter().filter(|arg| {
+                    ["allow-write", "context", "namespace"].contains(&arg.get_id().as_str())
+                })),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write the definitions of all resources to a directory as a YAML snapshot")
+                .arg(
+                    Arg::new("directory")
+                        .required(true)
+                        .index(1)
+                        .help("Directory that the manifests are written to"),
+                )
+                .args(
+                    mount_args()
+                        .into_iter()
+                        .filter(|arg| ["context", "namespace"].contains(&arg.get_id().as_str())),
+                ),
+        )
+        .subcommand(
+            Command::new("apply-tree")
+                .about("Apply a directory tree of manifests (e.g. a snapshot) in dependency order")
+                .arg(
+                    Arg::new("directory")
+                        .required(true)
+                        .index(1)
+                        .help("Directory that contains the manifests"),
+                )
+                .args(
+                    mount_args().into_iter().filter(|arg| {
+                        ["context", "force-conflicts"].contains(&arg.get_id().as_str())
+                    }),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("Only validate the manifests with the API server"),
+                )
+                .arg(
+                    Arg::new("prune")
+                        .long("prune")
+                        .action(ArgAction::SetTrue)
+                        .help("Delete namespaced objects that are not part of the tree"),
+                ),
+        )
+        .subcommand(
+            Command::new("kinds").about(
+                "List the supported kinds of kubernetes resources and how they can be changed",
+            ),
+        )
+        .subcommand(Command::new("sidecar").about(
+            "Mount the cluster in a sidecar container, configured by K8SFS_MOUNTPOINT and K8SFS_OPTIONS",
+        ))
+        .subcommand(
+            Command::new("daemon")
+                .about("Manage several mounts from one process")
+                .subcommand_required(true)
+                .subcommand(Command::new("start").about("Run the daemon"))
+                .subcommand(
+                    Command::new("mount")
+                        .about("Mount a kubernetes context in the running daemon")
+                        .args(mount_args()),
+                )
+                .subcommand(
+                    Command::new("umount")
+                        .about("Unmount a filesystem of the running daemon")
+                        .arg(
+                            Arg::new("mountpoint")
+                                .required(true)
+                                .index(1)
+                                .help("Filesystem mount point"),
+                        ),
+                )
+                .subcommand(Command::new("status").about("List the mounts of the running daemon")),
+        )
+}
+
+// Control socket of the daemon
+fn socket_arg() -> Arg {
+    Arg::new("socket")
+        .long("socket")
+        .value_name("PATH")
+        .help("Control socket of the daemon (default: $XDG_RUNTIME_DIR/k8sfs.sock)")
+}
+
+// Arguments that configure a mount
+fn mount_args() -> Vec<Arg> {
+    vec![
+        Arg::new("mountpoint")
+            .required(true)
+            .index(1)
+            .help("Filesystem mount point"),
+        Arg::new("allow-write")
+            .long("allow-write")
+            .short('w')
+            .action(ArgAction::SetTrue)
+            .help(
+                "Allow writing to filesystem.\nThis means that users can create kubernetes resources with IO operations.",
+            ),
+        Arg::new("force-write")
+            .long("force-write")
+            .action(ArgAction::SetTrue)
+            .help("Allow writing even if the context does not match writable_contexts of the config file"),
+        Arg::new("context")
+            .long("context")
+            .help("Kubernetes context that should be mounted (default: current context)"),
+        Arg::new("in-cluster")
+            .long("in-cluster")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("context")
+            .help("Connect with the service account of the pod k8sfs runs in instead of a kubeconfig"),
+        Arg::new("namespace")
+            .long("namespace")
+            .short('n')
+            .action(ArgAction::Append)
+            .help("Only show this namespace. Can be specified multiple times."),
+        Arg::new("idle-timeout")
+            .long("idle-timeout")
+            .value_name("SECONDS")
+            .value_parser(clap::value_parser!(u64))
+            .help("Unmount and exit after the filesystem was not used for SECONDS"),
+        Arg::new("trash-retention")
+            .long("trash-retention")
+            .value_name("SECONDS")
+            .value_parser(clap::value_parser!(u64))
+            .help("Keep the manifests of resources that were deleted through the mount in .k8sfs/trash for SECONDS [default: 86400]"),
+        Arg::new("timeline-minutes")
+            .long("timeline-minutes")
+            .value_name("MINUTES")
+            .value_parser(clap::value_parser!(u64))
+            .help("Include what happened within the last MINUTES in the timeline file [default: 60]"),
+        Arg::new("expiring-certs-days")
+            .long("expiring-certs-days")
+            .value_name("DAYS")
+            .value_parser(clap::value_parser!(u64))
+            .help("List certificates of TLS secrets that expire within DAYS in expiring-certs [default: 30]"),
+        Arg::new("cache-max-bytes")
+            .long("cache-max-bytes")
+            .value_name("BYTES")
+            .value_parser(clap::value_parser!(usize))
+            .help("Maximum amount of memory that is used to cache file content (default: 64MiB)"),
+        Arg::new("identify")
+            .long("identify")
+            .action(ArgAction::SetTrue)
+            .help("Add the local user and the mount point to the User-Agent that is sent to the API server"),
+        Arg::new("sort-by-age")
+            .long("sort-by-age")
+            .action(ArgAction::SetTrue)
+            .help("List kubernetes resources from newest to oldest"),
+        Arg::new("force-delete")
+            .long("force-delete")
+            .action(ArgAction::SetTrue)
+            .help("Finalize namespaces that are stuck terminating on the next rmdir right away"),
+        Arg::new("force-conflicts")
+            .long("force-conflicts")
+            .action(ArgAction::SetTrue)
+            .help("Take over fields that are owned by another field manager when applying manifests"),
+        Arg::new("protect-gitops")
+            .long("protect-gitops")
+            .action(ArgAction::SetTrue)
+            .help("Refuse to change resources that are managed by Argo CD or Flux"),
+        Arg::new("argocd-instance-label")
+            .long("argocd-instance-label")
+            .value_name("LABEL")
+            .help("Label that Argo CD tracks resources with (e.g. app.kubernetes.io/instance), only the tracking annotation is used by default"),
+        Arg::new("queue-offline")
+            .long("queue-offline")
+            .action(ArgAction::SetTrue)
+            .help("Queue applies and deletions while the API server is unreachable and replay them once it is reachable again (see .k8sfs/pending-ops)"),
+        Arg::new("events-stream")
+            .long("events-stream")
+            .action(ArgAction::SetTrue)
+            .help("Add an events.stream file that streams the events of the cluster as JSON lines"),
+        Arg::new("max-changes-per-hour")
+            .long("max-changes-per-hour")
+            .value_name("COUNT")
+            .value_parser(clap::value_parser!(usize))
+            .help("Fail with EDQUOT after COUNT changes to the cluster within an hour"),
+        Arg::new("max-readahead")
+            .long("max-readahead")
+            .value_name("BYTES")
+            .value_parser(clap::value_parser!(u32))
+            .help("Maximum number of bytes that the kernel reads ahead (default: 1MiB)"),
+        Arg::new("max-write")
+            .long("max-write")
+            .value_name("BYTES")
+            .value_parser(clap::value_parser!(u32))
+            .help("Maximum size of a single write request"),
+        Arg::new("max-background")
+            .long("max-background")
+            .value_name("COUNT")
+            .value_parser(clap::value_parser!(u16))
+            .help("Maximum number of requests that the kernel sends in parallel (default: 64)"),
+        Arg::new("max-read")
+            .long("max-read")
+            .value_name("BYTES")
+            .value_parser(clap::value_parser!(u32))
+            .help("Maximum size of a single read request"),
+        Arg::new("ignore")
+            .long("ignore")
+            .value_name("GLOB")
+            .action(ArgAction::Append)
+            .help("Fail lookups of names that match GLOB right away (in addition to .git, *.swp, ...). Can be specified multiple times."),
+        Arg::new("mode")
+            .long("mode")
+            .value_name("KIND=MARKER")
+            .action(ArgAction::Append)
+            .help("Mark files of KIND (degraded, logs, service-ports) with MARKER (sticky, setgid, setuid, none). Can be specified multiple times."),
+        Arg::new("config")
+            .long("config")
+            .short('c')
+            .value_name("FILE")
+            .help("TOML config file"),
+        Arg::new("prefetch")
+            .long("prefetch")
+            .value_name("POLICY")
+            .value_parser(["off", "namespaces", "all"])
+            .help("Fetch the descriptions of listed resources in the background: after listing namespaces, all directories or never (default: namespaces)"),
+        Arg::new("layout")
+            .long("layout")
+            .value_name("LAYOUT")
+            .value_parser(["kind", "group-version"])
+            .help("Also list the resources of namespaces under _api/<group>/<version>/<Kind>/ with group-version (default: kind)"),
+        Arg::new("kubectl-log")
+            .long("kubectl-log")
+            .value_name("FILE")
+            .help("File that the errors of kubectl calls are logged to (default: $XDG_STATE_HOME/k8sfs/<mount point>.log)"),
+        Arg::new("service-proxy")
+            .long("service-proxy")
+            .action(ArgAction::SetTrue)
+            .help("Add proxy directories to services whose files fetch HTTP endpoints through the API server"),
+        Arg::new("definition-suffix")
+            .long("definition-suffix")
+            .value_name("SUFFIX")
+            .help("Suffix of the definition files of resources (default: _definition.yaml)"),
+        Arg::new("definition-as-manifest")
+            .long("definition-as-manifest")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("definition-suffix")
+            .help("Name the definition files of resources manifest.yaml"),
+        Arg::new("show-events")
+            .long("show-events")
+            .value_name("BOOL")
+            .value_parser(clap::value_parser!(bool))
+            .help("Include events in definition files (default: true). With false they are in separate events files."),
+        Arg::new("hide-completed")
+            .long("hide-completed")
+            .value_name("BOOL")
+            .value_parser(clap::value_parser!(bool))
+            .help("Move succeeded and evicted pods into the completed directory of their namespace (default: true)"),
+        Arg::new("large-cluster")
+            .long("large-cluster")
+            .action(ArgAction::SetTrue)
+            .help("Only count the pods of namespaces when mounting and list them once a namespace is accessed"),
+        Arg::new("page-size")
+            .long("page-size")
+            .value_name("COUNT")
+            .value_parser(clap::value_parser!(u64))
+            .help("With --large-cluster, list the pods of namespaces with more pods page by page while they are read, 0 disables it [default: 500]"),
+        Arg::new("expose-credentials")
+            .long("expose-credentials")
+            .action(ArgAction::SetTrue)
+            .help("Include embedded credentials in generated kubeconfig files"),
+        Arg::new("root-path")
+            .long("root-path")
+            .value_name("CONTEXT/NAMESPACE/POD")
+            .help("Only mount a subtree (e.g. my-cluster/default) read-only"),
+    ]
+}
+
+// Command line interface that mount(8) uses when calling k8sfs as a mount helper
+//   mount.k8sfs <context> <mountpoint> [-sfnv] [-o options]
+fn mount_helper_command() -> Command {
+    Command::new(MOUNT_HELPER_NAME)
         .version("0.1.0")
         .author("blinxen")
         .arg(
-            Arg::new("mountpoint")
+            Arg::new("context")
                 .required(true)
                 .index(1)
+                .help("Kubernetes context that should be mounted (\"-\" for the current context)"),
+        )
+        .arg(
+            Arg::new("mountpoint")
+                .required(true)
+                .index(2)
                 .help("Filesystem mount point"),
         )
         .arg(
-            Arg::new("allow-write")
-                .long("allow-write")
-                .short('w')
-                .action(ArgAction::SetTrue)
+            Arg::new("options")
+                .short('o')
+                .action(ArgAction::Append)
                 .help(
-                    "Allow writing to filesystem.\nThis means that users can create kubernetes resources with IO operations.",
+                    "Comma separated mount options (rw, ro, force_write, context=<name>, in_cluster, namespaces=<ns1:ns2>, ignore=<glob1:glob2>, modes=<kind=marker:...>, idle_timeout=<seconds>, trash_retention=<seconds>, expiring_certs_days=<days>, timeline_minutes=<minutes>, cache_max_bytes=<bytes>, max_changes_per_hour=<count>, max_readahead=<bytes>, max_write=<bytes>, max_background=<count>, max_read=<bytes>, identify, sort_by_age, force_delete, force_conflicts, protect_gitops, argocd_instance_label=<label>, queue_offline, events_stream, show_events=<bool>, hide_completed=<bool>, large_cluster, page_size=<count>, definition_suffix=<suffix>, definition_as_manifest, service_proxy, prefetch=<policy>, layout=<layout>, kubectl_log=<path>, expose_credentials, config=<path>, root_path=<context/namespace>)",
                 ),
         )
-        .get_matches();
+        // Flags that mount(8) might pass to the helper, but have no meaning for k8sfs
+        .arg(
+            Arg::new("sloppy")
+                .short('s')
+                .action(ArgAction::SetTrue)
+                .hide(true),
+        )
+        .arg(
+            Arg::new("fake")
+                .short('f')
+                .action(ArgAction::SetTrue)
+                .hide(true),
+        )
+        .arg(
+            Arg::new("no-mtab")
+                .short('n')
+                .action(ArgAction::SetTrue)
+                .hide(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .action(ArgAction::SetTrue)
+                .hide(true),
+        )
+        .arg(Arg::new("type").short('t').hide(true))
+}
 
-    let fs = K8sFS::new();
+// Print an error and exit
+// Used for errors that happen before the filesystem is mounted (e.g. invalid configuration)
+fn exit_with_error(error: &str) -> ! {
+    log::error!("{}", error);
+    std::process::exit(1);
+}
 
-    let mut mount_options = vec![MountOption::FSName(fs.name())];
-    if matches.get_flag("allow-write") {
-        mount_options.push(MountOption::RW);
-    } else {
-        mount_options.push(MountOption::RO);
+// Return true if k8sfs was executed as a mount helper
+fn is_mount_helper() -> bool {
+    std::env::args()
+        .next()
+        .and_then(|program| {
+            Path::new(&program)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .map(|name| name == MOUNT_HELPER_NAME)
+        .unwrap_or(false)
+}
+
+// Detach from the calling process so that mount(8) can return while the filesystem is running
+// The calling process exits once the filesystem is mounted (see report_mounted), with status 1
+// if mounting failed.
+fn daemonize() {
+    let (mut parent_end, child_end) = match UnixStream::pair() {
+        Ok(pair) => pair,
+        Err(error) => {
+            log::error!(
+                "Could not fork mount helper, continuing in the foreground: {}",
+                error
+            );
+            return;
+        }
+    };
+    // SAFETY: No other threads have been started at this point
+    match unsafe { libc::fork() } {
+        -1 => {
+            log::error!("Could not fork mount helper, continuing in the foreground");
+        }
+        0 => {
+            // Child process keeps running the filesystem in its own session
+            // SAFETY: setsid() has no memory safety requirements, it only fails if the process
+            // already leads a process group, which a forked child does not
+            unsafe { libc::setsid() };
+            drop(parent_end);
+            *MOUNTED_SIGNAL.lock().unwrap() = Some(child_end);
+        }
+        _ => {
+            drop(child_end);
+            // The child exits without reporting anything if mounting failed
+            let mut mounted = [0];
+            let status = match parent_end.read_exact(&mut mounted) {
+                Ok(()) => 0,
+                Err(_) => 1,
+            };
+            std::process::exit(status);
+        }
     }
+}
 
-    log::info!("Mounting K8sFS...");
-    fuser::mount2(
-        fs,
-        matches.get_one::<String>("mountpoint").unwrap(),
-        &mount_options,
-    )
-    .expect("Unexpected error when exiting the filesystem");
+// Tell the process that started the mount helper that the filesystem is mounted
+// Output that is not logged to a file is discarded from now on, the calling process (and the
+// terminal it ran in) are gone.
+fn report_mounted() {
+    let Some(mut signal) = MOUNTED_SIGNAL.lock().unwrap().take() else {
+        return;
+    };
+    match OpenOptions::new().read(true).write(true).open("/dev/null") {
+        Ok(null) => {
+            for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+                // SAFETY: Both file descriptors are open, dup2() replaces fd with a copy of null
+                unsafe { libc::dup2(null.as_raw_fd(), fd) };
+            }
+        }
+        Err(error) => log::error!("Could not open /dev/null: {}", error),
+    }
+    if let Err(error) = signal.write_all(&[1]) {
+        log::error!("Could not report that the filesystem is mounted: {}", error);
+    }
 }
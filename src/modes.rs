@@ -0,0 +1,91 @@
+// Special mode bits that mark files of some kinds, so that they stand out in `ls -l`, colored
+// `ls` output and file managers (see --mode)
+// FIFOs and sockets are not offered: the kernel opens such files itself instead of passing the
+// calls on to the filesystem, so logs or services shown as one could not be read anymore.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkedKind {
+    // Directories of pods that the health rules assess as degraded
+    Degraded,
+    // Log files of pods and containers
+    Logs,
+    // Port directories of services in proxy/ (see --service-proxy)
+    ServicePorts,
+}
+
+impl MarkedKind {
+    fn from_name(name: &str) -> Option<MarkedKind> {
+        match name {
+            "degraded" => Some(MarkedKind::Degraded),
+            "logs" => Some(MarkedKind::Logs),
+            "service-ports" => Some(MarkedKind::ServicePorts),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Marker {
+    None,
+    Sticky,
+    Setgid,
+    Setuid,
+}
+
+impl Marker {
+    fn from_name(name: &str) -> Option<Marker> {
+        match name {
+            "none" => Some(Marker::None),
+            "sticky" => Some(Marker::Sticky),
+            "setgid" => Some(Marker::Setgid),
+            "setuid" => Some(Marker::Setuid),
+            _ => None,
+        }
+    }
+
+    pub fn bits(&self) -> u16 {
+        match self {
+            Marker::None => 0,
+            Marker::Sticky => 0o1000,
+            Marker::Setgid => 0o2000,
+            Marker::Setuid => 0o4000,
+        }
+    }
+}
+
+// Marker of every kind that is marked, later entries win
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModeMap(Vec<(MarkedKind, Marker)>);
+
+impl Default for ModeMap {
+    fn default() -> Self {
+        ModeMap(vec![(MarkedKind::Degraded, Marker::Sticky)])
+    }
+}
+
+impl ModeMap {
+    // Parse and add a mapping in the form <kind>=<marker> (e.g. logs=setgid)
+    pub fn add(&mut self, mapping: &str) -> Result<(), String> {
+        let (kind, marker) = mapping.split_once('=').ok_or_else(|| {
+            format!(
+                "Invalid mode mapping {:?}, expected <kind>=<marker>",
+                mapping
+            )
+        })?;
+        let kind = MarkedKind::from_name(kind)
+            .ok_or_else(|| format!("Unknown kind {:?} in mode mapping", kind))?;
+        let marker = Marker::from_name(marker)
+            .ok_or_else(|| format!("Unknown marker {:?} in mode mapping", marker))?;
+        self.0.push((kind, marker));
+        Ok(())
+    }
+
+    pub fn bits(&self, kind: MarkedKind) -> u16 {
+        self.0
+            .iter()
+            .rev()
+            .find(|(marked_kind, _)| *marked_kind == kind)
+            .map(|(_, marker)| marker.bits())
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::filesystem::Inode;
+use crate::watcher::ObjectKey;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+#[derive(Default)]
+struct NotifyState {
+    // Used to tell the kernel that files changed, set once the filesystem is mounted
+    notifier: Option<fuser::Notifier>,
+    // Inodes of the directory and the files of every watched object
+    object_inodes: HashMap<ObjectKey, Vec<Inode>>,
+    // When files last changed according to the watcher
+    modified: HashMap<Inode, SystemTime>,
+    // Files whose cached content is outdated
+    stale: HashSet<Inode>,
+}
+
+// Forwards changes that the watcher observed to the kernel, so that inotify works on the mount
+// (e.g. "inotifywait -r /mnt/k8s/default" reports pods whose status changed), and sets the
+// modification time of the files of an object to when it changed ("find -newer" works)
+// Clones share the same state.
+#[derive(Clone, Default)]
+pub struct ChangeNotifier {
+    state: Arc<Mutex<NotifyState>>,
+}
+
+impl ChangeNotifier {
+    pub fn set_notifier(&self, notifier: fuser::Notifier) {
+        self.state.lock().unwrap().notifier = Some(notifier);
+    }
+
+    // Remember which inodes belong to an object
+    pub fn register(&self, key: ObjectKey, inodes: Vec<Inode>) {
+        self.state.lock().unwrap().object_inodes.insert(key, inodes);
+    }
+
+    // Bump the modification time of the files of an object and invalidate them in the kernel
+    pub fn changed(&self, key: &ObjectKey, time: SystemTime) {
+        let mut state = self.state.lock().unwrap();
+        let Some(inodes) = state.object_inodes.get(key).cloned() else {
+            return;
+        };
+        for inode in inodes {
+            state.modified.insert(inode, time);
+            state.stale.insert(inode);
+            if let Some(notifier) = &state.notifier {
+                // Fails for inodes that the kernel does not know (yet), which is fine
+                if let Err(error) = notifier.inval_inode(inode, 0, -1) {
+                    log::debug!("Could not invalidate {}: {}", inode, error);
+                }
+            }
+        }
+    }
+
+    // Tell the kernel that the attributes and the content of a file are outdated
+    pub fn invalidate(&self, inode: Inode) {
+        if let Some(notifier) = &self.state.lock().unwrap().notifier {
+            if let Err(error) = notifier.inval_inode(inode, 0, -1) {
+                log::debug!("Could not invalidate {}: {}", inode, error);
+            }
+        }
+    }
+
+    // Return when a file last changed according to the watcher
+    pub fn modification_time(&self, inode: Inode) -> Option<SystemTime> {
+        self.state.lock().unwrap().modified.get(&inode).copied()
+    }
+
+    // Return the files whose cached content is outdated and forget them
+    pub fn take_stale(&self) -> Vec<Inode> {
+        self.state.lock().unwrap().stale.drain().collect()
+    }
+}
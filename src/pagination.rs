@@ -0,0 +1,54 @@
+// Listings of huge namespaces that are read page by page (see --large-cluster and --page-size)
+// Reading a deferred namespace with more pods than the page size lists its pods one page at a
+// time while the directory is read, instead of all of them when it is opened. Every directory
+// handle follows its own continue tokens, pods that are already there (e.g. because they were
+// looked up) are skipped.
+
+use crate::filesystem::Inode;
+use std::collections::HashMap;
+
+// Number of pods that are listed at once by default
+pub const DEFAULT_PAGE_SIZE: u64 = 500;
+
+pub struct PagedListing {
+    pub namespace_inode: Inode,
+    // Continue token of the next page, None before the first page
+    pub continue_token: Option<String>,
+    // Set once the last page was listed
+    pub complete: bool,
+}
+
+#[derive(Default)]
+pub struct PagedListings {
+    listings: HashMap<u64, PagedListing>,
+    // Directory handle 0 is used for directories that are listed at once
+    last_fh: u64,
+}
+
+impl PagedListings {
+    // Start a listing of a namespace and return its directory handle
+    pub fn open(&mut self, namespace_inode: Inode) -> u64 {
+        self.last_fh += 1;
+        self.listings.insert(
+            self.last_fh,
+            PagedListing {
+                namespace_inode,
+                continue_token: None,
+                complete: false,
+            },
+        );
+        self.last_fh
+    }
+
+    pub fn get(&self, fh: u64) -> Option<&PagedListing> {
+        self.listings.get(&fh)
+    }
+
+    pub fn get_mut(&mut self, fh: u64) -> Option<&mut PagedListing> {
+        self.listings.get_mut(&fh)
+    }
+
+    pub fn close(&mut self, fh: u64) {
+        self.listings.remove(&fh);
+    }
+}
@@ -0,0 +1,109 @@
+// Changes that could not be made because the API server was unreachable (see --queue-offline)
+// They are replayed in order once it can be reached again, e.g. after a VPN reconnected.
+// .k8sfs/pending-ops lists them, writing a number N to it cancels the Nth change and writing
+// "retry" replays them right away. The queue is kept in memory and is lost once k8sfs exits.
+
+use crate::k8s_resource::format_timestamp;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+pub const PENDING_OPS_FILE_NAME: &str = "pending-ops";
+
+// How often replaying the queue is attempted while the filesystem is used
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub enum Operation {
+    // Manifest that was written to the apply directory, with its objects as they were when it
+    // was opened for writing (if it was edited)
+    Apply {
+        name: String,
+        manifest: Vec<u8>,
+        opened: Option<Vec<Value>>,
+    },
+    // Namespace that was removed with rmdir
+    DeleteNamespace(String),
+}
+
+impl Operation {
+    pub fn description(&self) -> String {
+        match self {
+            Operation::Apply { name, .. } => format!("apply {}", name),
+            Operation::DeleteNamespace(namespace) => format!("rmdir {}", namespace),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PendingOps {
+    // Operations ordered from oldest to newest, with when they were queued
+    operations: VecDeque<(Operation, SystemTime)>,
+    last_attempt: Option<Instant>,
+    // Outcome of the last replay
+    last_replay: Option<String>,
+}
+
+impl PendingOps {
+    pub fn queue(&mut self, operation: Operation) {
+        log::warn!(
+            "The API server is unreachable, queueing {}",
+            operation.description()
+        );
+        self.operations.push_back((operation, SystemTime::now()));
+        self.last_attempt = Some(Instant::now());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    // Cancel the operation with the number that the summary shows (starting at 1)
+    pub fn cancel(&mut self, number: usize) -> Option<Operation> {
+        let (operation, _) = self.operations.remove(number.checked_sub(1)?)?;
+        log::info!("Cancelled queued {}", operation.description());
+        Some(operation)
+    }
+
+    // Return true if the queue should be replayed now
+    pub fn is_due(&self) -> bool {
+        !self.operations.is_empty()
+            && self
+                .last_attempt
+                .is_none_or(|last_attempt| last_attempt.elapsed() >= RETRY_INTERVAL)
+    }
+
+    // Take the oldest operation to replay it
+    pub fn next(&mut self) -> Option<(Operation, SystemTime)> {
+        self.last_attempt = Some(Instant::now());
+        self.operations.pop_front()
+    }
+
+    // Put an operation back at the front of the queue, because it could still not be made
+    pub fn requeue(&mut self, operation: (Operation, SystemTime)) {
+        self.operations.push_front(operation);
+    }
+
+    pub fn finish_replay(&mut self, report: String) {
+        self.last_replay = Some(report);
+    }
+
+    // Human readable list of the queued operations, oldest first
+    pub fn summary(&self) -> String {
+        let mut summary = String::from(
+            "# Write a number N to this file to cancel the Nth change, \"retry\" to replay them now\n",
+        );
+        for (index, (operation, queued)) in self.operations.iter().enumerate() {
+            summary.push_str(&format!(
+                "{} {} (queued {})\n",
+                index + 1,
+                operation.description(),
+                format_timestamp(*queued)
+            ));
+        }
+        if let Some(last_replay) = &self.last_replay {
+            summary.push_str(&format!("\n# Last replay\n{}", last_replay));
+        }
+        summary
+    }
+}
@@ -0,0 +1,113 @@
+use crate::filesystem::Inode;
+use crate::k8s_resource::Description;
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+// Number of threads that fetch descriptions in the background
+const PREFETCH_WORKERS: usize = 4;
+// Number of descriptions that can wait for a worker
+// Listings with more children than this only prefetch the first ones, which keeps the
+// number of kubectl calls that a single "ls" causes bounded.
+const PREFETCH_QUEUE: usize = 32;
+
+// Which directory listings cause the descriptions of their children to be prefetched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrefetchPolicy {
+    Off,
+    // Only listings of namespace directories (the pods of the namespace)
+    Namespaces,
+    // Listings of all directories that contain kubernetes resources (e.g. nodes/ as well)
+    All,
+}
+
+impl PrefetchPolicy {
+    pub fn from_name(name: &str) -> Option<PrefetchPolicy> {
+        match name {
+            "off" => Some(PrefetchPolicy::Off),
+            "namespaces" => Some(PrefetchPolicy::Namespaces),
+            "all" => Some(PrefetchPolicy::All),
+            _ => None,
+        }
+    }
+}
+
+// Description that was fetched in the background
+pub struct Prefetched {
+    pub inode: Inode,
+    pub content: Vec<u8>,
+    pub fetched_at: Instant,
+}
+
+// Fetches the descriptions of resources in background workers, so that opening a file after
+// listing its directory does not have to wait for kubectl
+// The results are collected by the filesystem and stored in its content cache.
+pub struct Prefetcher {
+    jobs: SyncSender<(Inode, Description)>,
+    results: Receiver<Prefetched>,
+    // Inodes that were requested and whose result was not collected yet
+    pending: HashSet<Inode>,
+}
+
+impl Prefetcher {
+    pub fn start() -> Self {
+        let (jobs, job_receiver) = mpsc::sync_channel::<(Inode, Description)>(PREFETCH_QUEUE);
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        for _ in 0..PREFETCH_WORKERS {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            thread::spawn(move || loop {
+                // The lock is only held while waiting for the next job
+                let job = job_receiver.lock().unwrap().recv();
+                let Ok((inode, description)) = job else {
+                    return;
+                };
+                let content = description.fetch();
+                let prefetched = Prefetched {
+                    inode,
+                    content,
+                    fetched_at: Instant::now(),
+                };
+                if result_sender.send(prefetched).is_err() {
+                    return;
+                }
+            });
+        }
+        Prefetcher {
+            jobs,
+            results,
+            pending: HashSet::new(),
+        }
+    }
+
+    // Fetch a description in the background
+    // Returns false if the queue is full, the description is not fetched in that case
+    pub fn request(&mut self, inode: Inode, description: Description) -> bool {
+        if self.pending.contains(&inode) {
+            return true;
+        }
+        match self.jobs.try_send((inode, description)) {
+            Ok(()) => {
+                self.pending.insert(inode);
+                true
+            }
+            Err(TrySendError::Full(_)) => false,
+            Err(TrySendError::Disconnected(_)) => {
+                log::error!("Prefetch workers are gone");
+                false
+            }
+        }
+    }
+
+    // Return all descriptions that were fetched since the last call
+    pub fn take_results(&mut self) -> Vec<Prefetched> {
+        let results: Vec<Prefetched> = self.results.try_iter().collect();
+        for result in &results {
+            self.pending.remove(&result.inode);
+        }
+        results
+    }
+}
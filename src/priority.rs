@@ -0,0 +1,98 @@
+// Priority classes of the cluster and the priority of pods
+// priorityclasses/ in the context directory contains a _summary table and a file per class with
+// one "key: value" line per setting. The status file of every pod tells which class it uses and
+// whether it was preempted, to find out why pods are preempted under pressure.
+
+use crate::kubectl::{self, K8sError};
+use crate::reports::{array, field};
+use serde_json::Value;
+
+pub const PRIORITY_CLASSES_DIRECTORY_NAME: &str = "priorityclasses";
+
+// Preemption policy that the API server uses if a class does not set one
+const DEFAULT_PREEMPTION_POLICY: &str = "PreemptLowerPriority";
+
+// Table of all priority classes, the class that pods without a class get is marked with a "*"
+// Classes are ordered by their value, highest first, which is the order of preemption.
+pub fn summary(context: &str) -> Result<String, K8sError> {
+    let mut classes = kubectl::get_items(context, None, "priorityclasses", &[])?;
+    classes.sort_by_key(|class| std::cmp::Reverse(value(class)));
+    let mut report = format!("  {:<40}{:<14}{}\n", "NAME", "VALUE", "PREEMPTION");
+    for class in &classes {
+        report.push_str(&format!(
+            "{} {:<40}{:<14}{}\n",
+            if is_global_default(class) { "*" } else { " " },
+            field(class, "/metadata/name"),
+            value(class),
+            preemption_policy(class)
+        ));
+    }
+    Ok(report)
+}
+
+// Settings of a single priority class as "key: value" lines
+pub fn report(context: &str, name: &str) -> Result<String, K8sError> {
+    let class = kubectl::get_object(context, None, "priorityclasses", name)?;
+    Ok(format!(
+        "value: {}\nglobalDefault: {}\npreemptionPolicy: {}\ndescription: {}\n",
+        value(&class),
+        is_global_default(&class),
+        preemption_policy(&class),
+        field(&class, "/description")
+    ))
+}
+
+// Priority of a pod as "key: value" lines
+// The API server copies the value and the preemption policy of the class into the pod spec.
+//   priorityClassName: high-priority
+//   priority: 1000000
+//   preemptionPolicy: PreemptLowerPriority
+//   nominatedNode: node-2             (the pod preempts pods on this node to be scheduled)
+//   preempted: Preempted by a pod on node node-1
+pub fn pod_priority(pod: &Value) -> String {
+    let class = match field(pod, "/spec/priorityClassName") {
+        "" => "<none>",
+        class => class,
+    };
+    let mut report = format!(
+        "priorityClassName: {}\npriority: {}\npreemptionPolicy: {}\n",
+        class,
+        pod.pointer("/spec/priority")
+            .and_then(Value::as_i64)
+            .unwrap_or_default(),
+        match field(pod, "/spec/preemptionPolicy") {
+            "" => DEFAULT_PREEMPTION_POLICY,
+            policy => policy,
+        }
+    );
+    let nominated_node = field(pod, "/status/nominatedNodeName");
+    if !nominated_node.is_empty() {
+        report.push_str(&format!("nominatedNode: {}\n", nominated_node));
+    }
+    // Pods that are preempted get a DisruptionTarget condition before they are terminated
+    if let Some(condition) = array(pod, "/status/conditions").iter().find(|condition| {
+        field(condition, "/type") == "DisruptionTarget"
+            && field(condition, "/reason") == "PreemptionByScheduler"
+    }) {
+        report.push_str(&format!("preempted: {}\n", field(condition, "/message")));
+    }
+    report
+}
+
+fn value(class: &Value) -> i64 {
+    class
+        .pointer("/value")
+        .and_then(Value::as_i64)
+        .unwrap_or_default()
+}
+
+fn is_global_default(class: &Value) -> bool {
+    class.pointer("/globalDefault") == Some(&Value::Bool(true))
+}
+
+fn preemption_policy(class: &Value) -> &str {
+    match field(class, "/preemptionPolicy") {
+        "" => DEFAULT_PREEMPTION_POLICY,
+        policy => policy,
+    }
+}
@@ -0,0 +1,158 @@
+// Age based housekeeping through .k8sfs/prune
+// Writing a rule like "jobs older-than 7d in ns-ci" lists the matching resources in
+// .k8sfs/prune.preview without deleting anything. Writing "confirm" deletes exactly the previewed
+// resources, so nothing is deleted that was not looked at first.
+
+use crate::k8s_resource::parse_timestamp;
+use crate::kubectl::{self, K8sError};
+use crate::reports::{self, field};
+use serde_json::Value;
+use std::time::Duration;
+
+pub const PRUNE_FILE_NAME: &str = "prune";
+// Suffix of the file next to the prune file that lists what a rule would delete
+pub const PREVIEW_SUFFIX: &str = ".preview";
+
+// Resources of a kind in a namespace that are older than a maximum age
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruneRule {
+    resource: String,
+    max_age: Duration,
+    // The age as it was written (e.g. 7d)
+    age: String,
+    namespace: String,
+}
+
+impl PruneRule {
+    // Parse a rule: <resource> older-than <age> in <namespace>
+    // The age is a number with a unit: s, m, h or d (e.g. 7d)
+    pub fn parse(rule: &str) -> Result<PruneRule, String> {
+        let words: Vec<&str> = rule.split_whitespace().collect();
+        let [resource, "older-than", age, "in", namespace] = words[..] else {
+            return Err(format!(
+                "Invalid rule {:?}, expected \"<resource> older-than <age> in <namespace>\"",
+                rule
+            ));
+        };
+        if !resource
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || ".-".contains(character))
+        {
+            return Err(format!("Invalid resource {:?}", resource));
+        }
+        Ok(PruneRule {
+            resource: resource.to_string(),
+            max_age: parse_age(age).ok_or_else(|| format!("Invalid age {:?}", age))?,
+            age: age.to_string(),
+            namespace: namespace.to_string(),
+        })
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    // Return the resources that match the rule, oldest first
+    // Resources that are already being deleted do not match.
+    pub fn matches(&self, context: &str) -> Result<Vec<Value>, K8sError> {
+        let mut objects = kubectl::get_items(context, Some(&self.namespace), &self.resource, &[])?;
+        objects.retain(|object| {
+            field(object, "/metadata/deletionTimestamp").is_empty()
+                && age(object).is_some_and(|age| age > self.max_age)
+        });
+        objects.sort_by_key(|object| std::cmp::Reverse(age(object)));
+        Ok(objects)
+    }
+}
+
+impl std::fmt::Display for PruneRule {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} older-than {} in {}",
+            self.resource, self.age, self.namespace
+        )
+    }
+}
+
+// State of the prune control: the previewed rule and the outcome of the last pruning
+#[derive(Default)]
+pub struct Pruner {
+    previewed: Option<(PruneRule, Vec<Value>)>,
+    last_outcome: Option<String>,
+}
+
+impl Pruner {
+    // Remember what a rule matched, so that it can be confirmed
+    pub fn preview(&mut self, rule: PruneRule, objects: Vec<Value>, skipped: &[String]) -> String {
+        let mut preview = format!(
+            "# {}: {} to delete, write \"confirm\" to {} to delete them\n",
+            rule,
+            objects.len(),
+            PRUNE_FILE_NAME
+        );
+        for object in &objects {
+            preview.push_str(&format!(
+                "{}/{}\t{}\n",
+                field(object, "/kind"),
+                field(object, "/metadata/name"),
+                age(object).map_or_else(|| "<unknown>".to_string(), reports::format_age)
+            ));
+        }
+        for skipped in skipped {
+            preview.push_str(&format!("# skipped {}\n", skipped));
+        }
+        self.previewed = Some((rule, objects));
+        preview
+    }
+
+    // Take the previewed rule and its resources to delete them
+    pub fn take_preview(&mut self) -> Option<(PruneRule, Vec<Value>)> {
+        self.previewed.take()
+    }
+
+    pub fn finish(&mut self, outcome: String) {
+        self.last_outcome = Some(outcome);
+    }
+
+    // Usage, the previewed rule and the outcome of the last pruning
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "# Write a rule like \"jobs older-than 7d in ns-ci\" to this file to preview it in {}{},\n\
+             # then write \"confirm\" to delete the previewed resources\n",
+            PRUNE_FILE_NAME, PREVIEW_SUFFIX
+        );
+        if let Some((rule, objects)) = &self.previewed {
+            summary.push_str(&format!(
+                "\n# Previewed\n{} ({} to delete)\n",
+                rule,
+                objects.len()
+            ));
+        }
+        if let Some(last_outcome) = &self.last_outcome {
+            summary.push_str(&format!("\n# Last prune\n{}", last_outcome));
+        }
+        summary
+    }
+}
+
+fn age(object: &Value) -> Option<Duration> {
+    parse_timestamp(field(object, "/metadata/creationTimestamp"))?
+        .elapsed()
+        .ok()
+}
+
+// Parse an age like 30s, 15m, 12h or 7d
+// Ages that do not fit into 64 bit seconds are invalid.
+fn parse_age(age: &str) -> Option<Duration> {
+    let unit = age.chars().last()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    let count: u64 = age[..age.len() - 1].parse().ok()?;
+    count.checked_mul(seconds).map(Duration::from_secs)
+}
@@ -0,0 +1,84 @@
+// Virtual query directories
+// A path like _query/label/app=web/ is a directory that contains symlinks to all pods
+// (across namespaces) that match the label selector "app=web".
+
+// Name of the directory in the context directory that contains the query types
+pub const QUERY_DIRECTORY_NAME: &str = "_query";
+
+// Characters (besides alphanumeric ones) that are allowed in selectors
+// This covers equality (app=web, app!=web), set based (env in (prod,dev)) and
+// existence (!canary) selectors
+const SELECTOR_CHARACTERS: &str = "-_./=!,() ";
+
+// How resources are selected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectorType {
+    // kubectl --selector
+    Label,
+    // kubectl --field-selector
+    Field,
+}
+
+impl SelectorType {
+    pub fn all() -> [SelectorType; 2] {
+        [SelectorType::Label, SelectorType::Field]
+    }
+
+    // Name of the directory that contains queries of this type
+    pub fn directory_name(&self) -> &'static str {
+        match self {
+            SelectorType::Label => "label",
+            SelectorType::Field => "field",
+        }
+    }
+}
+
+// A query that was parsed from a directory name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub selector_type: SelectorType,
+    pub selector: String,
+}
+
+impl Query {
+    // Parse a directory name into a query
+    // Returns None if the name is not a valid selector, which also filters out probes
+    // for files like .git or .hidden
+    pub fn parse(selector_type: SelectorType, selector: &str) -> Option<Query> {
+        if selector.trim().is_empty()
+            || selector.starts_with('.')
+            || !selector
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || SELECTOR_CHARACTERS.contains(c))
+        {
+            log::debug!("{:?} is not a valid selector", selector);
+            return None;
+        }
+
+        Some(Query {
+            selector_type,
+            selector: selector.to_string(),
+        })
+    }
+
+    // kubectl arguments that select the resources matching this query
+    pub fn kubectl_args(&self) -> [String; 2] {
+        let flag = match self.selector_type {
+            SelectorType::Label => "--selector",
+            SelectorType::Field => "--field-selector",
+        };
+        [flag.to_string(), self.selector.clone()]
+    }
+}
+
+// Name of the symlink for a resource in a query directory
+// Namespaces can not contain "_", so this is unambiguous
+pub fn symlink_name(namespace: &str, name: &str) -> String {
+    format!("{}_{}", namespace, name)
+}
+
+// Target of the symlink for a resource in a query directory
+// Query directories are always three levels below the mount point (_query/<type>/<selector>)
+pub fn symlink_target(namespace: &str, path: &str) -> String {
+    format!("../../../{}/{}", namespace, path)
+}
@@ -0,0 +1,48 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Time frame in which the number of changes is limited
+const QUOTA_WINDOW: Duration = Duration::from_secs(3600);
+
+// Limits how many changes can be made to the cluster through the filesystem per hour
+// (see --max-changes-per-hour), so that a runaway script can not hammer the cluster
+pub struct ChangeQuota {
+    // None if the number of changes is not limited
+    limit: Option<usize>,
+    // When the changes within the window were made, oldest first
+    changes: VecDeque<Instant>,
+}
+
+impl ChangeQuota {
+    pub fn new(limit: Option<usize>) -> Self {
+        ChangeQuota {
+            limit,
+            changes: VecDeque::new(),
+        }
+    }
+
+    // Record a change
+    // Returns false and records nothing if the limit was reached within the last hour
+    pub fn try_acquire(&mut self) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        let now = Instant::now();
+        while self
+            .changes
+            .front()
+            .is_some_and(|change| now.duration_since(*change) >= QUOTA_WINDOW)
+        {
+            self.changes.pop_front();
+        }
+        if self.changes.len() >= limit {
+            log::error!(
+                "Not changing the cluster, the limit of {} changes per hour was reached",
+                limit
+            );
+            return false;
+        }
+        self.changes.push_back(now);
+        true
+    }
+}
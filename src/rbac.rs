@@ -0,0 +1,24 @@
+// Virtual directories to explore RBAC permissions
+// Reading rbac/can-i/<verb>/<resource>/<namespace> answers whether the current user is allowed
+// to do something, like "kubectl auth can-i" does (via a SelfSubjectAccessReview).
+
+// Name of the directory in the context directory that contains the RBAC explorer
+pub const RBAC_DIRECTORY_NAME: &str = "rbac";
+// Name of the directory inside the RBAC directory that contains the verbs
+pub const CAN_I_DIRECTORY_NAME: &str = "can-i";
+// Name that is used instead of a namespace to check permissions in all namespaces
+pub const ALL_NAMESPACES_NAME: &str = "_all";
+
+// Characters (besides alphanumeric ones) that are allowed in verbs and resources
+// This covers wildcards (*) and resources of API groups (deployments.apps)
+const NAME_CHARACTERS: &str = "-_.*";
+
+// Return true if a looked up name can be used as a verb, resource or namespace
+// This also filters out probes for files like .git or .hidden
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || NAME_CHARACTERS.contains(c))
+}
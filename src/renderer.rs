@@ -0,0 +1,81 @@
+use crate::k8s_resource::ResourceType;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+// Extra file per kubernetes resource whose content is projected from the resource with a
+// JSONPath expression. Renderers are defined in the config file:
+//   [[renderers]]
+//   kind = "pod"
+//   file = "ip"
+//   jsonpath = "$.status.podIP"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Renderer {
+    pub resource_type: ResourceType,
+    pub file_name: String,
+    expression: String,
+}
+
+impl Renderer {
+    // Parse a renderer from an entry of the config file
+    pub fn from_toml(entry: &toml::Value) -> Result<Renderer, String> {
+        let get = |key: &str| {
+            entry
+                .get(key)
+                .and_then(toml::Value::as_str)
+                .ok_or_else(|| format!("Renderer is missing \"{}\"", key))
+        };
+        let kind = get("kind")?;
+        let resource_type = ResourceType::from_kind(kind)
+            .ok_or_else(|| format!("Renderers are not supported for kind \"{}\"", kind))?;
+        let file_name = get("file")?;
+        if file_name.is_empty() || file_name.contains('/') {
+            return Err(format!("Invalid renderer file name \"{}\"", file_name));
+        }
+        Ok(Renderer {
+            resource_type,
+            file_name: file_name.to_string(),
+            expression: parse_expression(get("jsonpath")?)?,
+        })
+    }
+
+    // Render an object with the JSONPath expression of this renderer
+    // Every matched value is written on its own line. Strings are written without quotes.
+    pub fn render(&self, object: &Value) -> String {
+        match query(&self.expression, object) {
+            Ok(nodes) => nodes.iter().map(|node| format!("{}\n", node)).collect(),
+            Err(error) => format!("{}\n", error),
+        }
+    }
+}
+
+// Validate a JSONPath expression from the config file
+// kubectl style expressions (.status.podIP or {.status.podIP}) are accepted as well
+pub fn parse_expression(expression: &str) -> Result<String, String> {
+    let mut expression = expression
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .to_string();
+    if expression.starts_with('.') {
+        expression.insert(0, '$');
+    }
+    JsonPath::parse(&expression)
+        .map_err(|error| format!("Invalid jsonpath \"{}\": {}", expression, error))?;
+
+    Ok(expression)
+}
+
+// Return all values that a JSONPath expression matches in an object
+// Strings are returned without quotes, everything else as JSON.
+pub fn query(expression: &str, object: &Value) -> Result<Vec<String>, String> {
+    let path =
+        JsonPath::parse(expression).map_err(|error| format!("Invalid jsonpath: {}", error))?;
+    Ok(path
+        .query(object)
+        .all()
+        .into_iter()
+        .map(|node| match node {
+            Value::String(string) => string.clone(),
+            node => node.to_string(),
+        })
+        .collect())
+}
@@ -0,0 +1,1816 @@
+use crate::admission::Inventory;
+use crate::capabilities;
+use crate::certs;
+use crate::containers;
+use crate::cost::{self, PriceTable};
+use crate::drain;
+use crate::gitops;
+use crate::health::{self, Health, HealthRule};
+use crate::k8s_resource::{format_timestamp, parse_timestamp, ResourceType};
+use crate::kubectl::{self, K8sError};
+use crate::priority;
+use crate::rbac;
+use crate::renderer::Renderer;
+use crate::scheduling;
+use crate::storage::{self, StorageKind};
+use crate::timeline;
+use crate::version;
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+
+// Warning events that are younger than this affect the health of a deployment
+const RECENT_EVENTS: Duration = Duration::from_secs(600);
+
+// Warning events that are younger than this are listed in the warnings file of a namespace
+const WARNINGS_WINDOW: Duration = Duration::from_secs(3600);
+
+// Maximum length of an owner chain, which protects against cycles in ownerReferences
+const MAX_OWNER_DEPTH: usize = 8;
+
+// How long a namespace has to be terminating before rmdir finalizes it
+pub const FORCE_DELETE_AFTER: Duration = Duration::from_secs(60);
+
+// Parts of the metadata of a kubernetes resource that have their own file in .metadata/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetadataField {
+    Labels,
+    Annotations,
+    Finalizers,
+    OwnerReferences,
+}
+
+impl MetadataField {
+    pub fn all() -> [MetadataField; 4] {
+        [
+            MetadataField::Labels,
+            MetadataField::Annotations,
+            MetadataField::Finalizers,
+            MetadataField::OwnerReferences,
+        ]
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            MetadataField::Labels => "labels",
+            MetadataField::Annotations => "annotations",
+            MetadataField::Finalizers => "finalizers",
+            MetadataField::OwnerReferences => "owner-refs",
+        }
+    }
+}
+
+// Files whose content is generated from the state of the cluster every time they are read
+#[derive(Debug, Clone, PartialEq)]
+pub enum Report {
+    // Pending pods of a namespace that could not be scheduled, with the reasons why
+    Unschedulable {
+        context: String,
+        namespace: String,
+        // List pods from newest to oldest instead of in the order the API returned them
+        newest_first: bool,
+    },
+    // Problems of a pod's containers (image pull errors, crash loops, OOM kills, failing probes)
+    Problems {
+        context: String,
+        namespace: String,
+        pod: String,
+    },
+    // Warning events of a namespace from the last hour, grouped by the object they are about
+    Warnings {
+        context: String,
+        namespace: String,
+    },
+    // Health of a pod as assessed by the health rules
+    Status {
+        context: String,
+        namespace: String,
+        pod: String,
+        rules: Vec<HealthRule>,
+    },
+    // Health of all pods of a namespace
+    Health {
+        context: String,
+        namespace: String,
+        rules: Vec<HealthRule>,
+    },
+    // Health of a deployment in a single line
+    DeploymentHealth {
+        context: String,
+        namespace: String,
+        deployment: String,
+    },
+    // HTTP endpoint of a service that is fetched through the API server proxy
+    ServiceProxy {
+        context: String,
+        namespace: String,
+        // [https:]<service>:<port>
+        service: String,
+        // Path of the endpoint with "/" encoded as "%2F", which is used as the file name
+        file: String,
+    },
+    // Pods that a service selects and whether they are ready
+    Backends {
+        context: String,
+        namespace: String,
+        service: String,
+    },
+    // Services that select a pod
+    Services {
+        context: String,
+        namespace: String,
+        pod: String,
+    },
+    // Verbs of the kind of a resource and whether they are allowed
+    Capabilities {
+        context: String,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        resource_type: ResourceType,
+        allow_write: bool,
+    },
+    // Chain of controllers of a pod (e.g. Pod -> ReplicaSet -> Deployment)
+    Owner {
+        context: String,
+        namespace: String,
+        pod: String,
+    },
+    // Node selector, affinities, tolerations and topology spread constraints of a pod
+    Scheduling {
+        context: String,
+        namespace: String,
+        pod: String,
+    },
+    // Differences between the spec of a pod and the pod template of its deployment
+    TemplateDrift {
+        context: String,
+        namespace: String,
+        pod: String,
+        deployment: String,
+    },
+    // Whether the current user is allowed to do something (yes / no)
+    CanI {
+        context: String,
+        verb: String,
+        resource: String,
+        // None to check all namespaces
+        namespace: Option<String>,
+    },
+    // Subject, SANs and validity of the certificates of a TLS secret
+    CertInfo {
+        context: String,
+        namespace: String,
+        secret: String,
+    },
+    // Certificates of all TLS secrets that expire within a number of days
+    ExpiringCerts {
+        context: String,
+        window_days: u64,
+    },
+    // Warning events, container restarts and node condition changes of the last minutes
+    Timeline {
+        context: String,
+        minutes: u64,
+    },
+    // Table of all nodes with their versions, like "kubectl get nodes -o wide"
+    NodeInventory {
+        context: String,
+    },
+    // Conditions of a node (Ready, MemoryPressure, DiskPressure, PIDPressure, ...)
+    NodeConditions {
+        context: String,
+        node: String,
+    },
+    // What draining a node would do with its pods
+    DrainPlan {
+        context: String,
+        node: String,
+    },
+    // Log of a node that is served by its kubelet
+    NodeLog {
+        context: String,
+        node: String,
+        // File in /var/log on the node, None for the log of the kubelet itself
+        file: Option<String>,
+    },
+    // Capacity, allocatable and requested resources of a node
+    Allocatable {
+        context: String,
+        node: String,
+    },
+    // Whether a namespace is terminating and what it is waiting for
+    TerminationStatus {
+        context: String,
+        namespace: String,
+        // Terminating namespaces are finalized on rmdir without waiting (see --force-delete)
+        force_delete: bool,
+    },
+    // Contexts of the kubeconfig, the mounted one is marked with "*"
+    Contexts {
+        context: String,
+    },
+    // Webhook configurations or aggregated APIServices of the cluster
+    Admission {
+        context: String,
+        inventory: Inventory,
+    },
+    // Storage classes or CSI drivers of the cluster, one line per object
+    StorageSummary {
+        context: String,
+        kind: StorageKind,
+    },
+    // Settings of a storage class or CSI driver
+    Storage {
+        context: String,
+        kind: StorageKind,
+        name: String,
+    },
+    // Priority classes of the cluster, one line per class
+    PriorityClasses {
+        context: String,
+    },
+    // Settings of a priority class
+    PriorityClass {
+        context: String,
+        name: String,
+    },
+    // Versions of kubectl and the API server and whether they are within the supported skew
+    Version {
+        context: String,
+    },
+    // Requested resources of the pods of a namespace and what they cost
+    CostEstimate {
+        context: String,
+        namespace: String,
+        prices: PriceTable,
+    },
+    // Kubeconfig that uses a namespace by default
+    Kubeconfig {
+        context: String,
+        namespace: String,
+        // Include credentials that are embedded in the kubeconfig
+        expose_credentials: bool,
+    },
+    // Containers of a pod, their roles and which one is used by pod level files
+    Containers {
+        context: String,
+        namespace: String,
+        pod: String,
+    },
+    // State, readiness and restarts of a container
+    ContainerStatus {
+        context: String,
+        namespace: String,
+        pod: String,
+        container: String,
+    },
+    // Condensed spec of a container (image, command, ports, probes, ...)
+    ContainerSpec {
+        context: String,
+        namespace: String,
+        pod: String,
+        container: String,
+    },
+    // Logs of a container
+    Logs {
+        context: String,
+        namespace: String,
+        pod: String,
+        container: String,
+    },
+    // Events of a kubernetes resource, oldest first (instead of in the description)
+    Events {
+        context: String,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        resource_type: ResourceType,
+        name: String,
+    },
+    // Labels, annotations, finalizers or owner references of a kubernetes resource
+    Metadata {
+        context: String,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        resource_type: ResourceType,
+        name: String,
+        field: MetadataField,
+    },
+    // Argo CD application, Flux source or Helm release that manages a kubernetes resource
+    Ownership {
+        context: String,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        resource_type: ResourceType,
+        name: String,
+        // Label that Argo CD tracks resources with (see --argocd-instance-label)
+        argocd_instance_label: Option<String>,
+    },
+    // Projection of a kubernetes resource that is defined in the config file
+    Render {
+        context: String,
+        // None for resources that are not namespaced
+        namespace: Option<String>,
+        name: String,
+        renderer: Renderer,
+    },
+}
+
+impl Report {
+    // Name of the file that contains the report
+    pub fn file_name(&self) -> &str {
+        match self {
+            Report::Unschedulable { .. } => "unschedulable",
+            Report::Problems { .. } => "problems",
+            Report::Warnings { .. } => "warnings",
+            Report::Status { .. } => "status",
+            Report::Health { .. } | Report::DeploymentHealth { .. } => "health",
+            Report::Backends { .. } => "backends",
+            Report::ServiceProxy { file, .. } => file,
+            Report::Services { .. } => "services",
+            Report::Owner { .. } => "owner",
+            Report::Capabilities { .. } => capabilities::CAPABILITIES_FILE_NAME,
+            Report::Scheduling { .. } => scheduling::SCHEDULING_FILE_NAME,
+            Report::TemplateDrift { .. } => "template-drift",
+            Report::CanI { namespace, .. } => {
+                namespace.as_deref().unwrap_or(rbac::ALL_NAMESPACES_NAME)
+            }
+            Report::CertInfo { .. } => certs::CERT_INFO_FILE_NAME,
+            Report::ExpiringCerts { .. } => certs::EXPIRING_CERTS_FILE_NAME,
+            Report::Timeline { .. } => timeline::TIMELINE_FILE_NAME,
+            Report::NodeInventory { .. } => "nodes.tsv",
+            Report::NodeConditions { .. } => "conditions",
+            Report::DrainPlan { .. } => drain::DRAIN_PLAN_FILE_NAME,
+            Report::NodeLog { file, .. } => file.as_deref().unwrap_or("kubelet.log"),
+            Report::Allocatable { .. } => "allocatable",
+            Report::TerminationStatus { .. } => ".termination-status",
+            Report::Contexts { .. } => "contexts",
+            Report::Admission { inventory, .. } => inventory.file_name(),
+            Report::StorageSummary { .. } => storage::SUMMARY_FILE_NAME,
+            Report::Storage { name, .. } => name,
+            Report::PriorityClasses { .. } => storage::SUMMARY_FILE_NAME,
+            Report::PriorityClass { name, .. } => name,
+            Report::Version { .. } => version::VERSION_FILE_NAME,
+            Report::CostEstimate { .. } => cost::COST_ESTIMATE_FILE_NAME,
+            Report::Kubeconfig { .. } => "kubeconfig",
+            Report::Containers { .. } => "_summary",
+            Report::ContainerSpec { .. } => "spec",
+            Report::ContainerStatus { .. } => "status",
+            Report::Logs { .. } => "logs",
+            Report::Events { .. } => "events",
+            Report::Metadata { field, .. } => field.file_name(),
+            Report::Ownership { .. } => gitops::OWNERSHIP_FILE_NAME,
+            Report::Render { renderer, .. } => &renderer.file_name,
+        }
+    }
+
+    // Generate the content of the report
+    pub fn generate(&self) -> Vec<u8> {
+        let report = match self {
+            Report::Unschedulable {
+                context,
+                namespace,
+                newest_first,
+            } => unschedulable(context, namespace, *newest_first).map(String::into_bytes),
+            Report::Problems {
+                context,
+                namespace,
+                pod,
+            } => problems(context, namespace, pod).map(String::into_bytes),
+            Report::Warnings { context, namespace } => {
+                warnings(context, namespace).map(String::into_bytes)
+            }
+            Report::Status {
+                context,
+                namespace,
+                pod,
+                rules,
+            } => kubectl::get_object(context, Some(namespace), "pods", pod).map(|pod| {
+                format!(
+                    "{}\n{}",
+                    health::assess(rules, ResourceType::Pod, &pod).name(),
+                    priority::pod_priority(&pod)
+                )
+                .into_bytes()
+            }),
+            Report::Health {
+                context,
+                namespace,
+                rules,
+            } => namespace_health(context, namespace, rules).map(String::into_bytes),
+            Report::DeploymentHealth {
+                context,
+                namespace,
+                deployment,
+            } => deployment_health(context, namespace, deployment).map(String::into_bytes),
+            Report::ServiceProxy {
+                context,
+                namespace,
+                service,
+                file,
+            } => kubectl::service_proxy(
+                context,
+                namespace,
+                service,
+                &file.replace("%2F", "/").replace("%2f", "/"),
+            ),
+            Report::Backends {
+                context,
+                namespace,
+                service,
+            } => backends(context, namespace, service).map(String::into_bytes),
+            Report::Services {
+                context,
+                namespace,
+                pod,
+            } => services(context, namespace, pod).map(String::into_bytes),
+            Report::Owner {
+                context,
+                namespace,
+                pod,
+            } => owner_chain(context, namespace, pod).map(String::into_bytes),
+            Report::Capabilities {
+                context,
+                namespace,
+                resource_type,
+                allow_write,
+            } => capabilities::report(context, namespace.as_deref(), *resource_type, *allow_write)
+                .map(String::into_bytes),
+            Report::Scheduling {
+                context,
+                namespace,
+                pod,
+            } => kubectl::get_object(context, Some(namespace), "pods", pod)
+                .map(|pod| scheduling::summary(&pod).into_bytes()),
+            Report::TemplateDrift {
+                context,
+                namespace,
+                pod,
+                deployment,
+            } => template_drift(context, namespace, pod, deployment).map(String::into_bytes),
+            Report::CanI {
+                context,
+                verb,
+                resource,
+                namespace,
+            } => kubectl::can_i(context, verb, resource, namespace.as_deref())
+                .map(|allowed| if allowed { "yes\n" } else { "no\n" }.as_bytes().to_vec()),
+            Report::CertInfo {
+                context,
+                namespace,
+                secret,
+            } => certs::cert_info(context, namespace, secret).map(String::into_bytes),
+            Report::ExpiringCerts {
+                context,
+                window_days,
+            } => certs::expiring(context, *window_days).map(String::into_bytes),
+            Report::Timeline { context, minutes } => {
+                timeline::timeline(context, *minutes).map(String::into_bytes)
+            }
+            Report::NodeInventory { context } => node_inventory(context).map(String::into_bytes),
+            Report::NodeConditions { context, node } => {
+                node_conditions(context, node).map(String::into_bytes)
+            }
+            Report::DrainPlan { context, node } => {
+                drain::plan(context, node).map(String::into_bytes)
+            }
+            Report::NodeLog {
+                context,
+                node,
+                file: Some(file),
+            } => kubectl::node_log(context, node, file),
+            // The journal of the kubelet can only be queried if the NodeLogQuery feature is
+            // enabled, otherwise kubelet.log is used (e.g. on nodes without systemd)
+            Report::NodeLog {
+                context,
+                node,
+                file: None,
+            } => kubectl::node_log(context, node, "?query=kubelet")
+                .or_else(|_| kubectl::node_log(context, node, "kubelet.log")),
+            Report::Allocatable { context, node } => {
+                allocatable(context, node).map(String::into_bytes)
+            }
+            Report::TerminationStatus {
+                context,
+                namespace,
+                force_delete,
+            } => termination_status(context, namespace, *force_delete).map(String::into_bytes),
+            Report::Contexts { context } => kubectl::contexts().map(|contexts| {
+                contexts
+                    .iter()
+                    .map(|name| {
+                        let marker = if name == context { '*' } else { ' ' };
+                        format!("{} {}\n", marker, name)
+                    })
+                    .collect::<String>()
+                    .into_bytes()
+            }),
+            Report::Admission { context, inventory } => {
+                inventory.report(context).map(String::into_bytes)
+            }
+            Report::StorageSummary { context, kind } => {
+                kind.summary(context).map(String::into_bytes)
+            }
+            Report::Storage {
+                context,
+                kind,
+                name,
+            } => kind.report(context, name).map(String::into_bytes),
+            Report::PriorityClasses { context } => {
+                priority::summary(context).map(String::into_bytes)
+            }
+            Report::PriorityClass { context, name } => {
+                priority::report(context, name).map(String::into_bytes)
+            }
+            Report::Version { context } => version::report(context).map(String::into_bytes),
+            Report::CostEstimate {
+                context,
+                namespace,
+                prices,
+            } => prices.estimate(context, namespace).map(String::into_bytes),
+            Report::Kubeconfig {
+                context,
+                namespace,
+                expose_credentials,
+            } => kubeconfig(context, namespace, *expose_credentials).map(String::into_bytes),
+            Report::Containers {
+                context,
+                namespace,
+                pod,
+            } => containers(context, namespace, pod).map(String::into_bytes),
+            Report::ContainerSpec {
+                context,
+                namespace,
+                pod,
+                container,
+            } => container_spec(context, namespace, pod, container).map(String::into_bytes),
+            Report::ContainerStatus {
+                context,
+                namespace,
+                pod,
+                container,
+            } => container_status(context, namespace, pod, container).map(String::into_bytes),
+            Report::Logs {
+                context,
+                namespace,
+                pod,
+                container,
+            } => kubectl::logs(context, namespace, pod, container),
+            Report::Events {
+                context,
+                namespace,
+                resource_type,
+                name,
+            } => {
+                events(context, namespace.as_deref(), *resource_type, name).map(String::into_bytes)
+            }
+            Report::Metadata {
+                context,
+                namespace,
+                resource_type,
+                name,
+                field,
+            } => kubectl::get_object(
+                context,
+                namespace.as_deref(),
+                resource_type.kubectl_name(),
+                name,
+            )
+            .map(|object| metadata(&object, *field).into_bytes()),
+            Report::Ownership {
+                context,
+                namespace,
+                resource_type,
+                name,
+                argocd_instance_label,
+            } => kubectl::get_object(
+                context,
+                namespace.as_deref(),
+                resource_type.kubectl_name(),
+                name,
+            )
+            .map(|object| {
+                gitops::ownership(context, &object, argocd_instance_label.as_deref()).into_bytes()
+            }),
+            Report::Render {
+                context,
+                namespace,
+                name,
+                renderer,
+            } => kubectl::get_object(
+                context,
+                namespace.as_deref(),
+                renderer.resource_type.kubectl_name(),
+                name,
+            )
+            .map(|object| renderer.render(&object).into_bytes()),
+        };
+
+        match report {
+            Ok(report) => report,
+            Err(reason) => {
+                log::error!("Could not generate {}: {}", self.file_name(), reason);
+                format!("Could not generate report: {}\n", reason).into_bytes()
+            }
+        }
+    }
+}
+
+// Return the string at a JSON pointer or an empty string if it does not exist
+pub fn field<'a>(value: &'a Value, pointer: &str) -> &'a str {
+    value.pointer(pointer).and_then(Value::as_str).unwrap_or("")
+}
+
+// Return the array at a JSON pointer or an empty slice if it does not exist
+pub fn array<'a>(value: &'a Value, pointer: &str) -> &'a [Value] {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+// Return the most recent timestamp of an object that the API server reports
+// Besides the creation, this covers condition changes and container (re)starts / terminations.
+// RFC 3339 timestamps in UTC can be compared as strings.
+pub fn last_update(object: &Value) -> Option<&str> {
+    let statuses = array(object, "/status/initContainerStatuses")
+        .iter()
+        .chain(array(object, "/status/containerStatuses"));
+    let container_timestamps = statuses.flat_map(|status| {
+        [
+            field(status, "/state/running/startedAt"),
+            field(status, "/state/terminated/finishedAt"),
+        ]
+    });
+    let condition_timestamps = array(object, "/status/conditions")
+        .iter()
+        .map(|condition| field(condition, "/lastTransitionTime"));
+    // Every write of a field manager (labels, spec, status, ...) updates its time
+    let managed_timestamps = array(object, "/metadata/managedFields")
+        .iter()
+        .map(|managed| field(managed, "/time"));
+
+    std::iter::once(field(object, "/metadata/creationTimestamp"))
+        .chain(condition_timestamps)
+        .chain(container_timestamps)
+        .chain(managed_timestamps)
+        .filter(|timestamp| !timestamp.is_empty())
+        .max()
+}
+
+// List pending pods that the scheduler could not place on a node
+// The reason is taken from the PodScheduled condition and, if that has no message, from
+// the most recent FailedScheduling event of the pod.
+fn unschedulable(context: &str, namespace: &str, newest_first: bool) -> Result<String, K8sError> {
+    let mut pods = kubectl::get_items(
+        context,
+        Some(namespace),
+        "pods",
+        &["--field-selector=status.phase=Pending"],
+    )?;
+    // Events are only used to enrich the report, so we can live without them
+    let mut events = kubectl::get_items(
+        context,
+        Some(namespace),
+        "events",
+        &["--field-selector=reason=FailedScheduling"],
+    )
+    .unwrap_or_default();
+    // RFC 3339 timestamps can be compared as strings
+    events.sort_by(|a, b| field(b, "/lastTimestamp").cmp(field(a, "/lastTimestamp")));
+
+    let mut report = String::new();
+    if newest_first {
+        pods.sort_by(|a, b| {
+            field(b, "/metadata/creationTimestamp").cmp(field(a, "/metadata/creationTimestamp"))
+        });
+        report.push_str("# Sorted by creationTimestamp (newest first)\n");
+    }
+    for pod in &pods {
+        let name = field(pod, "/metadata/name");
+        let condition = array(pod, "/status/conditions").iter().find(|condition| {
+            field(condition, "/type") == "PodScheduled" && field(condition, "/status") == "False"
+        });
+        let Some(condition) = condition else {
+            // The pod was scheduled and is pending for another reason (e.g. pulling images)
+            continue;
+        };
+
+        let mut message = field(condition, "/message");
+        if message.is_empty() {
+            message = events
+                .iter()
+                .find(|event| field(event, "/involvedObject/name") == name)
+                .map(|event| field(event, "/message"))
+                .unwrap_or("No reason available");
+        }
+        report.push_str(&format!(
+            "{} ({})\n  {}\n",
+            name,
+            field(condition, "/reason"),
+            message
+        ));
+    }
+
+    if !report.lines().any(|line| !line.starts_with('#')) {
+        report.push_str("No unschedulable pods\n");
+    }
+
+    Ok(report)
+}
+
+// Reasons of waiting containers that indicate a problem
+const WAITING_PROBLEMS: [&str; 6] = [
+    "ImagePullBackOff",
+    "ErrImagePull",
+    "InvalidImageName",
+    "CrashLoopBackOff",
+    "CreateContainerConfigError",
+    "CreateContainerError",
+];
+
+// Summarize everything that is wrong with the containers of a pod
+// Container statuses only know about the current and the last state, so warning events
+// (e.g. failing probes) are added as well.
+fn problems(context: &str, namespace: &str, pod_name: &str) -> Result<String, K8sError> {
+    let pod = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    let field_selector = format!(
+        "--field-selector=involvedObject.kind=Pod,involvedObject.name={},type=Warning",
+        pod_name
+    );
+    // Events are only used to enrich the report, so we can live without them
+    let events = kubectl::get_items(context, Some(namespace), "events", &[&field_selector])
+        .unwrap_or_default();
+
+    let mut report = String::new();
+    let statuses = array(&pod, "/status/initContainerStatuses")
+        .iter()
+        .chain(array(&pod, "/status/containerStatuses"));
+    for status in statuses {
+        let container = field(status, "/name");
+        let waiting_reason = field(status, "/state/waiting/reason");
+        if WAITING_PROBLEMS.contains(&waiting_reason) {
+            report.push_str(&format!(
+                "{}: {}: {}\n",
+                container,
+                waiting_reason,
+                field(status, "/state/waiting/message")
+            ));
+        }
+        for state in ["/state/terminated", "/lastState/terminated"] {
+            let terminated = status.pointer(state).unwrap_or(&Value::Null);
+            if field(terminated, "/reason") == "OOMKilled" {
+                report.push_str(&format!(
+                    "{}: OOMKilled (exit code {}, finished at {}, {} restarts)\n",
+                    container,
+                    terminated.pointer("/exitCode").unwrap_or(&Value::Null),
+                    field(terminated, "/finishedAt"),
+                    status.pointer("/restartCount").unwrap_or(&Value::Null)
+                ));
+            }
+        }
+    }
+
+    for event in &events {
+        report.push_str(&format!(
+            "event {} (x{}, last seen {}): {}\n",
+            field(event, "/reason"),
+            event.pointer("/count").and_then(Value::as_u64).unwrap_or(1),
+            field(event, "/lastTimestamp"),
+            field(event, "/message")
+        ));
+    }
+
+    if report.is_empty() {
+        report.push_str("No problems found\n");
+    }
+
+    Ok(report)
+}
+
+// Summarize the spec of a container of a pod
+fn container_spec(
+    context: &str,
+    namespace: &str,
+    pod_name: &str,
+    container_name: &str,
+) -> Result<String, K8sError> {
+    let pod = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    array(&pod, "/spec/initContainers")
+        .iter()
+        .chain(array(&pod, "/spec/containers"))
+        .find(|container| field(container, "/name") == container_name)
+        .map(containers::spec_summary)
+        .ok_or_else(|| {
+            K8sError::Failed(format!(
+                "Container {} does not exist in pod {}",
+                container_name, pod_name
+            ))
+        })
+}
+
+// State of a container as "key: value" lines
+//   state: terminated (Error, exit code 1) since 2026-10-16T11:58:02Z
+//   message: connection refused
+//   ready: false
+//   restarts: 3
+//   lastState: terminated (Error, exit code 1) since 2026-10-16T11:57:30Z
+fn container_status(
+    context: &str,
+    namespace: &str,
+    pod_name: &str,
+    container_name: &str,
+) -> Result<String, K8sError> {
+    let pod = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    // Containers have no status before the pod is scheduled
+    let Some(status) = array(&pod, "/status/initContainerStatuses")
+        .iter()
+        .chain(array(&pod, "/status/containerStatuses"))
+        .find(|status| field(status, "/name") == container_name)
+    else {
+        return Ok(format!("state: {}\n", field(&pod, "/status/phase")));
+    };
+    let mut report = format!("state: {}\n", container_state(status.pointer("/state")));
+    let message = ["/state/waiting/message", "/state/terminated/message"]
+        .iter()
+        .map(|pointer| field(status, pointer))
+        .find(|message| !message.is_empty());
+    if let Some(message) = message {
+        report.push_str(&format!("message: {}\n", message.trim_end()));
+    }
+    report.push_str(&format!(
+        "ready: {}\nrestarts: {}\n",
+        status
+            .pointer("/ready")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        status
+            .pointer("/restartCount")
+            .and_then(Value::as_u64)
+            .unwrap_or(0)
+    ));
+    if status
+        .pointer("/lastState")
+        .and_then(Value::as_object)
+        .is_some_and(|state| !state.is_empty())
+    {
+        report.push_str(&format!(
+            "lastState: {}\n",
+            container_state(status.pointer("/lastState"))
+        ));
+    }
+    Ok(report)
+}
+
+// One line description of the state of a container (waiting, running or terminated)
+fn container_state(state: Option<&Value>) -> String {
+    let state = state.unwrap_or(&Value::Null);
+    if let Some(waiting) = state.pointer("/waiting") {
+        format!("waiting ({})", field(waiting, "/reason"))
+    } else if let Some(running) = state.pointer("/running") {
+        format!("running since {}", field(running, "/startedAt"))
+    } else if let Some(terminated) = state.pointer("/terminated") {
+        format!(
+            "terminated ({}, exit code {}) since {}",
+            field(terminated, "/reason"),
+            terminated
+                .pointer("/exitCode")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            field(terminated, "/finishedAt")
+        )
+    } else {
+        String::from("unknown")
+    }
+}
+
+// List the containers of a pod with their role and state
+// The default container (see containers::default_container) is marked with a "*".
+fn containers(context: &str, namespace: &str, pod_name: &str) -> Result<String, K8sError> {
+    let pod = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    let default_container = containers::default_container(&pod);
+
+    let mut report = format!(
+        "  {:<30}{:<10}{:<8}{:<10}{}\n",
+        "NAME", "ROLE", "READY", "RESTARTS", "IMAGE"
+    );
+    let specs = array(&pod, "/spec/initContainers")
+        .iter()
+        .chain(array(&pod, "/spec/containers"));
+    let statuses: Vec<&Value> = array(&pod, "/status/initContainerStatuses")
+        .iter()
+        .chain(array(&pod, "/status/containerStatuses"))
+        .collect();
+    for spec in specs {
+        let name = field(spec, "/name");
+        let status = statuses
+            .iter()
+            .find(|status| field(status, "/name") == name)
+            .copied()
+            .unwrap_or(&Value::Null);
+        let marker = if Some(name) == default_container {
+            "*"
+        } else {
+            " "
+        };
+        report.push_str(&format!(
+            "{} {:<30}{:<10}{:<8}{:<10}{}\n",
+            marker,
+            name,
+            containers::role(&pod, name).name(),
+            status
+                .pointer("/ready")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            status
+                .pointer("/restartCount")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+            field(spec, "/image")
+        ));
+    }
+
+    Ok(report)
+}
+
+// Summarize the health of all pods of a namespace
+// The number of pods per health is followed by the pods that are not healthy.
+fn namespace_health(
+    context: &str,
+    namespace: &str,
+    rules: &[HealthRule],
+) -> Result<String, K8sError> {
+    let pods = kubectl::pods(context, namespace)?;
+    let mut assessed: Vec<(Health, &str)> = pods
+        .iter()
+        .map(|pod| {
+            (
+                health::assess(rules, ResourceType::Pod, pod),
+                field(pod, "/metadata/name"),
+            )
+        })
+        .collect();
+    // Worst health first, then by name
+    assessed.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+
+    let mut report = String::new();
+    for health in Health::all() {
+        let count = assessed
+            .iter()
+            .filter(|(other, _)| *other == health)
+            .count();
+        report.push_str(&format!("{:<12}{}\n", health.name(), count));
+    }
+    let unhealthy: Vec<_> = assessed
+        .iter()
+        .filter(|(health, _)| *health != Health::Healthy)
+        .collect();
+    if !unhealthy.is_empty() {
+        report.push('\n');
+    }
+    for (health, pod) in unhealthy {
+        report.push_str(&format!("{:<12}{}\n", health.name(), pod));
+    }
+
+    Ok(report)
+}
+
+// Warning events about one object in the warnings file of a namespace
+struct WarnedObject<'a> {
+    object: String,
+    count: u64,
+    last: SystemTime,
+    reasons: Vec<(&'a str, u64)>,
+}
+
+// Count the warning events of the last hour per object, most warnings first, so that
+// "watch cat warnings" shows what is going wrong in a namespace right now
+//   COUNT  LAST                  OBJECT                   REASONS
+//   14     2026-10-16T11:59:13Z  Pod/web-5d9c7-x2x4q      BackOff (x12), Unhealthy (x2)
+// The count of an event is how often it occurred, which is not limited to the last hour.
+fn warnings(context: &str, namespace: &str) -> Result<String, K8sError> {
+    let events = kubectl::get_items(
+        context,
+        Some(namespace),
+        "events",
+        &["--field-selector=type=Warning"],
+    )?;
+    let since = SystemTime::now() - WARNINGS_WINDOW;
+    let mut objects: Vec<WarnedObject> = Vec::new();
+    for event in &events {
+        let Some(time) = [
+            "/lastTimestamp",
+            "/eventTime",
+            "/metadata/creationTimestamp",
+        ]
+        .iter()
+        .find_map(|pointer| parse_timestamp(field(event, pointer)))
+        .filter(|time| *time >= since) else {
+            continue;
+        };
+        let object = format!(
+            "{}/{}",
+            field(event, "/involvedObject/kind"),
+            field(event, "/involvedObject/name")
+        );
+        let count = event.pointer("/count").and_then(Value::as_u64).unwrap_or(1);
+        let index = match objects.iter().position(|other| other.object == object) {
+            Some(index) => index,
+            None => {
+                objects.push(WarnedObject {
+                    object,
+                    count: 0,
+                    last: time,
+                    reasons: Vec::new(),
+                });
+                objects.len() - 1
+            }
+        };
+        let warned = &mut objects[index];
+        warned.count += count;
+        warned.last = warned.last.max(time);
+        match warned
+            .reasons
+            .iter_mut()
+            .find(|(reason, _)| *reason == field(event, "/reason"))
+        {
+            Some((_, reason_count)) => *reason_count += count,
+            None => warned.reasons.push((field(event, "/reason"), count)),
+        }
+    }
+    objects.sort_by(|a, b| b.count.cmp(&a.count).then(b.last.cmp(&a.last)));
+
+    let mut report = format!(
+        "{:<7}{:<22}{:<40}{}
+",
+        "COUNT", "LAST", "OBJECT", "REASONS"
+    );
+    for mut warned in objects {
+        warned
+            .reasons
+            .sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let reasons: Vec<String> = warned
+            .reasons
+            .iter()
+            .map(|(reason, count)| format!("{} (x{})", reason, count))
+            .collect();
+        report.push_str(&format!(
+            "{:<7}{:<22}{:<40}{}
+",
+            warned.count,
+            format_timestamp(warned.last),
+            warned.object,
+            reasons.join(", ")
+        ));
+    }
+    Ok(report)
+}
+
+// Summarize the health of a deployment in one line, so that it can be watched with
+// "watch cat deployments/*/health"
+//   <deployment> <health> <ready>/<desired> ready: <reason>
+fn deployment_health(
+    context: &str,
+    namespace: &str,
+    deployment_name: &str,
+) -> Result<String, K8sError> {
+    let deployment = kubectl::get_object(context, Some(namespace), "deployments", deployment_name)?;
+    // Events are only used to enrich the report, so we can live without them
+    let events = kubectl::get_items(
+        context,
+        Some(namespace),
+        "events",
+        &["--field-selector=type=Warning"],
+    )
+    .unwrap_or_default();
+    // Events of the replica sets of a deployment are included, they explain why pods
+    // could not be created (e.g. exceeded quotas)
+    let replica_set_prefix = format!("{}-", deployment_name);
+    let mut warnings: Vec<&Value> = events
+        .iter()
+        .filter(|event| {
+            let name = field(event, "/involvedObject/name");
+            match field(event, "/involvedObject/kind") {
+                "Deployment" => name == deployment_name,
+                "ReplicaSet" => name.starts_with(&replica_set_prefix),
+                _ => false,
+            }
+        })
+        .filter(|event| {
+            [
+                "/lastTimestamp",
+                "/eventTime",
+                "/metadata/creationTimestamp",
+            ]
+            .iter()
+            .find_map(|pointer| parse_timestamp(field(event, pointer)))
+            .and_then(|timestamp| timestamp.elapsed().ok())
+            .is_some_and(|age| age < RECENT_EVENTS)
+        })
+        .collect();
+    warnings.sort_by(|a, b| field(b, "/lastTimestamp").cmp(field(a, "/lastTimestamp")));
+
+    let (health, reason) = assess_deployment(&deployment, &warnings);
+    let count = |pointer: &str| deployment.pointer(pointer).and_then(Value::as_u64);
+    let mut report = format!(
+        "{} {} {}/{} ready",
+        deployment_name,
+        health.name(),
+        count("/status/readyReplicas").unwrap_or(0),
+        count("/spec/replicas").unwrap_or(1)
+    );
+    if !reason.is_empty() {
+        report.push_str(": ");
+        // Messages can contain line breaks, but the report has to stay on one line
+        report.push_str(&reason.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    report.push('\n');
+
+    Ok(report)
+}
+
+// Derive the health of a deployment from its replica counts and conditions
+// Recent warning events (e.g. FailedCreate) mean that the rollout does not make progress.
+fn assess_deployment(deployment: &Value, warnings: &[&Value]) -> (Health, String) {
+    let count = |pointer: &str| deployment.pointer(pointer).and_then(Value::as_u64);
+    let condition = |condition_type: &str| {
+        array(deployment, "/status/conditions")
+            .iter()
+            .find(|condition| field(condition, "/type") == condition_type)
+    };
+    let describe = |condition: &Value| {
+        format!(
+            "{}: {}",
+            field(condition, "/reason"),
+            field(condition, "/message")
+        )
+    };
+
+    if let Some(failure) = condition("ReplicaFailure").filter(|c| field(c, "/status") == "True") {
+        return (Health::Degraded, describe(failure));
+    }
+    if let Some(progressing) =
+        condition("Progressing").filter(|c| field(c, "/reason") == "ProgressDeadlineExceeded")
+    {
+        return (Health::Degraded, describe(progressing));
+    }
+    if let Some(warning) = warnings.first() {
+        return (
+            Health::Degraded,
+            format!(
+                "{}: {}",
+                field(warning, "/reason"),
+                field(warning, "/message")
+            ),
+        );
+    }
+    if deployment.pointer("/spec/paused") == Some(&Value::Bool(true)) {
+        return (Health::Progressing, String::from("Rollout is paused"));
+    }
+    if count("/metadata/generation") > count("/status/observedGeneration") {
+        return (
+            Health::Progressing,
+            String::from("Waiting for the rollout to be observed"),
+        );
+    }
+
+    let desired = count("/spec/replicas").unwrap_or(1);
+    let replicas = count("/status/replicas").unwrap_or(0);
+    let updated = count("/status/updatedReplicas").unwrap_or(0);
+    let available = count("/status/availableReplicas").unwrap_or(0);
+    if updated < desired {
+        (
+            Health::Progressing,
+            format!("{} of {} replicas updated", updated, desired),
+        )
+    } else if replicas > updated {
+        (
+            Health::Progressing,
+            format!("{} old replicas pending termination", replicas - updated),
+        )
+    } else if available < updated {
+        (
+            Health::Progressing,
+            format!("{} of {} updated replicas available", available, updated),
+        )
+    } else {
+        (Health::Healthy, String::new())
+    }
+}
+
+// Return true if the selector of a service matches the labels of a pod
+// Services without a selector do not select any pods (their endpoints are managed manually).
+fn selects(service: &Value, pod: &Value) -> bool {
+    let Some(selector) = service
+        .pointer("/spec/selector")
+        .and_then(Value::as_object)
+        .filter(|selector| !selector.is_empty())
+    else {
+        return false;
+    };
+    selector.iter().all(|(key, value)| {
+        pod.pointer("/metadata/labels")
+            .and_then(|labels| labels.get(key))
+            == Some(value)
+    })
+}
+
+// Return true if the Ready condition of a pod is true
+fn is_ready(pod: &Value) -> bool {
+    array(pod, "/status/conditions").iter().any(|condition| {
+        field(condition, "/type") == "Ready" && field(condition, "/status") == "True"
+    })
+}
+
+// Return true if a pod finished and will not run again (it succeeded or was evicted)
+pub fn is_completed(pod: &Value) -> bool {
+    match field(pod, "/status/phase") {
+        "Succeeded" => true,
+        "Failed" => field(pod, "/status/reason") == "Evicted",
+        _ => false,
+    }
+}
+
+// Return true if a ConfigMap or Secret can not be changed (only deleted and recreated)
+pub fn is_immutable(object: &Value) -> bool {
+    object
+        .pointer("/immutable")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+// Credentials of kubeconfig users that are redacted unless they are exposed
+const SECRET_USER_FIELDS: [&str; 3] = ["token", "password", "client-key-data"];
+// Secrets of the configuration of (deprecated) auth provider plugins, e.g. oidc
+const SECRET_AUTH_PROVIDER_FIELDS: [&str; 4] =
+    ["id-token", "refresh-token", "access-token", "client-secret"];
+
+// Kubeconfig for a context that uses a namespace by default
+// Unless credentials are exposed, the secrets of its users are redacted. Everything else (e.g.
+// CA certificates and exec plugins) is kept, so that it works without them where possible.
+fn kubeconfig(
+    context: &str,
+    namespace: &str,
+    expose_credentials: bool,
+) -> Result<String, K8sError> {
+    let mut kubeconfig = kubectl::kubeconfig(context)?;
+    let users = match expose_credentials {
+        true => None,
+        false => kubeconfig.get_mut("users").and_then(Value::as_array_mut),
+    };
+    for user in users.into_iter().flatten() {
+        if let Some(config) = user
+            .pointer_mut("/user/auth-provider/config")
+            .and_then(Value::as_object_mut)
+        {
+            redact(config, &SECRET_AUTH_PROVIDER_FIELDS);
+        }
+        if let Some(user) = user.get_mut("user").and_then(Value::as_object_mut) {
+            redact(user, &SECRET_USER_FIELDS);
+        }
+    }
+    if let Some(context) = kubeconfig.pointer_mut("/contexts/0/context") {
+        context["namespace"] = Value::from(namespace);
+    }
+    // JSON is valid YAML, so kubectl can read it as a kubeconfig
+    Ok(format!(
+        "{}\n",
+        serde_json::to_string_pretty(&kubeconfig).unwrap_or_default()
+    ))
+}
+
+// Replace the values of fields that contain secrets the way kubectl does
+fn redact(object: &mut serde_json::Map<String, Value>, fields: &[&str]) {
+    for field in fields {
+        if let Some(value) = object.get_mut(*field) {
+            *value = Value::from("REDACTED");
+        }
+    }
+}
+
+// List the pods that a service selects with their readiness
+fn backends(context: &str, namespace: &str, service_name: &str) -> Result<String, K8sError> {
+    let service = kubectl::get_object(context, Some(namespace), "services", service_name)?;
+    let pods = kubectl::pods(context, namespace)?;
+
+    let mut report = format!("{:<50}{}\n", "POD", "READY");
+    for pod in pods.iter().filter(|pod| selects(&service, pod)) {
+        report.push_str(&format!(
+            "{:<50}{}\n",
+            field(pod, "/metadata/name"),
+            is_ready(pod)
+        ));
+    }
+    Ok(report)
+}
+
+// List the services that select a pod
+fn services(context: &str, namespace: &str, pod_name: &str) -> Result<String, K8sError> {
+    let pod = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    let services = kubectl::get_items(context, Some(namespace), "services", &[])?;
+
+    Ok(services
+        .iter()
+        .filter(|service| selects(service, &pod))
+        .map(|service| format!("{}\n", field(service, "/metadata/name")))
+        .collect())
+}
+
+// Follow the controllers of a pod until an object that is not controlled by anything
+//   Pod default/web-5d9c7-x2x4q
+//   ReplicaSet default/web-5d9c7
+//   Deployment default/web
+fn owner_chain(context: &str, namespace: &str, pod_name: &str) -> Result<String, K8sError> {
+    let mut object = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    let mut report = format!(
+        "Pod {}/{}
+",
+        namespace, pod_name
+    );
+    for _ in 0..MAX_OWNER_DEPTH {
+        let owners = array(&object, "/metadata/ownerReferences");
+        let Some(owner) = owners
+            .iter()
+            .find(|owner| owner.pointer("/controller") == Some(&Value::Bool(true)))
+            .or(owners.first())
+        else {
+            break;
+        };
+        let (kind, name) = (field(owner, "/kind"), field(owner, "/name"));
+        // Namespaced objects can be owned by cluster scoped ones (e.g. static pods by nodes)
+        let owner_namespace = match ResourceType::from_kind(kind) {
+            Some(resource_type) if !resource_type.is_namespaced() => None,
+            _ => Some(namespace),
+        };
+        match owner_namespace {
+            Some(namespace) => report.push_str(&format!(
+                "{} {}/{}
+",
+                kind, namespace, name
+            )),
+            None => report.push_str(&format!(
+                "{} {}
+",
+                kind, name
+            )),
+        }
+        // Owners can already be gone (e.g. jobs whose cron job was deleted)
+        object = match kubectl::get_object(context, owner_namespace, &kind.to_lowercase(), name) {
+            Ok(object) => object,
+            Err(reason) => {
+                log::debug!("Could not retrieve {} {}: {}", kind, name, reason);
+                break;
+            }
+        };
+    }
+    Ok(report)
+}
+
+// Compare the spec of a pod with the pod template of its deployment, one line per difference
+//   + containers[istio-proxy]: {"image":"istio/proxyv2:1.20",...}
+//   ~ containers[web].image: "nginx:1.25" -> "nginx:1.26"
+//   - containers[web].env[DEBUG]: {"name":"DEBUG","value":"1"}
+// Fields that the control plane sets on every pod (node, service account token, default
+// tolerations, ...) are left out, so what remains was usually added by mutating webhooks.
+fn template_drift(
+    context: &str,
+    namespace: &str,
+    pod_name: &str,
+    deployment_name: &str,
+) -> Result<String, K8sError> {
+    let pod = kubectl::get_object(context, Some(namespace), "pods", pod_name)?;
+    let deployment = kubectl::get_object(context, Some(namespace), "deployments", deployment_name)?;
+    let template = deployment
+        .pointer("/spec/template/spec")
+        .cloned()
+        .unwrap_or(Value::Null);
+    let mut spec = pod.pointer("/spec").cloned().unwrap_or(Value::Null);
+    remove_pod_defaults(&mut spec, &template);
+
+    let mut differences = Vec::new();
+    spec_differences("", &template, &spec, &mut differences);
+    if differences.is_empty() {
+        return Ok("No differences to the pod template\n".to_string());
+    }
+    Ok(differences
+        .iter()
+        .map(|difference| format!("{}\n", difference))
+        .collect())
+}
+
+// Remove the fields from a pod spec that are set when the pod is created or scheduled,
+// unless the template sets them as well
+fn remove_pod_defaults(spec: &mut Value, template: &Value) {
+    let Some(fields) = spec.as_object_mut() else {
+        return;
+    };
+    for name in [
+        "nodeName",
+        "serviceAccount",
+        "serviceAccountName",
+        "priority",
+        "preemptionPolicy",
+    ] {
+        if template.get(name).is_none() {
+            fields.remove(name);
+        }
+    }
+    // Token of the service account (kube-api-access-<random>)
+    let is_token = |value: &Value| field(value, "/name").starts_with("kube-api-access-");
+    if let Some(Value::Array(volumes)) = fields.get_mut("volumes") {
+        volumes.retain(|volume| !is_token(volume));
+    }
+    for containers in ["initContainers", "containers"] {
+        if let Some(Value::Array(containers)) = fields.get_mut(containers) {
+            for container in containers {
+                if let Some(Value::Array(mounts)) = container.get_mut("volumeMounts") {
+                    mounts.retain(|mount| !is_token(mount));
+                }
+            }
+        }
+    }
+    // Tolerations that the DefaultTolerationSeconds admission plugin adds
+    let template_tolerations = array(template, "/tolerations");
+    if let Some(Value::Array(tolerations)) = fields.get_mut("tolerations") {
+        tolerations.retain(|toleration| {
+            !matches!(
+                field(toleration, "/key"),
+                "node.kubernetes.io/not-ready" | "node.kubernetes.io/unreachable"
+            ) || template_tolerations.contains(toleration)
+        });
+    }
+    // Drop the fields that became empty, they are not set in the template either
+    fields.retain(|name, value| {
+        template.get(name).is_some() || value.as_array().is_none_or(|items| !items.is_empty())
+    });
+}
+
+// Collect the differences between two values of a spec
+// Elements of lists with names (containers, env, volumes, ...) are matched by their name,
+// elements of other lists by their value.
+fn spec_differences(path: &str, template: &Value, pod: &Value, differences: &mut Vec<String>) {
+    let compact = |value: &Value| serde_json::to_string(value).unwrap_or_default();
+    match (template, pod) {
+        (Value::Object(template), Value::Object(pod)) => {
+            let mut names: Vec<&String> = template.keys().chain(pod.keys()).collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                let path = match path.is_empty() {
+                    true => name.to_string(),
+                    false => format!("{}.{}", path, name),
+                };
+                match (template.get(name), pod.get(name)) {
+                    (Some(template), Some(pod)) => {
+                        spec_differences(&path, template, pod, differences)
+                    }
+                    (Some(template), None) => {
+                        differences.push(format!("- {}: {}", path, compact(template)))
+                    }
+                    (None, Some(pod)) => differences.push(format!("+ {}: {}", path, compact(pod))),
+                    (None, None) => (),
+                }
+            }
+        }
+        (Value::Array(template), Value::Array(pod)) => {
+            let named = |items: &[Value]| items.iter().all(|item| item.get("name").is_some());
+            if named(template) && named(pod) {
+                for item in template {
+                    let name = field(item, "/name");
+                    let path = format!("{}[{}]", path, name);
+                    match pod.iter().find(|other| field(other, "/name") == name) {
+                        Some(other) => spec_differences(&path, item, other, differences),
+                        None => differences.push(format!("- {}: {}", path, compact(item))),
+                    }
+                }
+                for item in pod {
+                    let name = field(item, "/name");
+                    if !template.iter().any(|other| field(other, "/name") == name) {
+                        differences.push(format!("+ {}[{}]: {}", path, name, compact(item)));
+                    }
+                }
+            } else {
+                for item in template.iter().filter(|item| !pod.contains(item)) {
+                    differences.push(format!("- {}[]: {}", path, compact(item)));
+                }
+                for item in pod.iter().filter(|item| !template.contains(item)) {
+                    differences.push(format!("+ {}[]: {}", path, compact(item)));
+                }
+            }
+        }
+        (template, pod) if template != pod => differences.push(format!(
+            "~ {}: {} -> {}",
+            path,
+            compact(template),
+            compact(pod)
+        )),
+        _ => (),
+    }
+}
+
+// List all nodes as tab separated values, one line per node
+// Roles are taken from the node-role.kubernetes.io/<role> labels like kubectl does.
+fn node_inventory(context: &str) -> Result<String, K8sError> {
+    let nodes = kubectl::get_items(context, None, "nodes", &[])?;
+    let mut report =
+        String::from("NAME\tROLES\tKUBELET\tOS-IMAGE\tKERNEL\tCONTAINER-RUNTIME\tAGE\n");
+    for node in &nodes {
+        let roles: Vec<&str> = node
+            .pointer("/metadata/labels")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|labels| labels.keys())
+            .filter_map(|label| label.strip_prefix("node-role.kubernetes.io/"))
+            .collect();
+        let age = parse_timestamp(field(node, "/metadata/creationTimestamp"))
+            .and_then(|created| SystemTime::now().duration_since(created).ok())
+            .map_or_else(|| "<unknown>".to_string(), format_age);
+        report.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            field(node, "/metadata/name"),
+            match roles.is_empty() {
+                true => "<none>".to_string(),
+                false => roles.join(","),
+            },
+            field(node, "/status/nodeInfo/kubeletVersion"),
+            field(node, "/status/nodeInfo/osImage"),
+            field(node, "/status/nodeInfo/kernelVersion"),
+            field(node, "/status/nodeInfo/containerRuntimeVersion"),
+            age
+        ));
+    }
+    Ok(report)
+}
+
+// Format an age in the largest unit like kubectl does (e.g. 45d, 3h, 12m)
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m", seconds / 60),
+        3600..86400 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+// List the conditions of a node with the time they last changed
+fn node_conditions(context: &str, node_name: &str) -> Result<String, K8sError> {
+    let node = kubectl::get_object(context, None, "nodes", node_name)?;
+
+    let mut report = format!(
+        "{:<24}{:<8}{:<22}{}\n",
+        "TYPE", "STATUS", "LAST TRANSITION", "REASON"
+    );
+    for condition in array(&node, "/status/conditions") {
+        report.push_str(&format!(
+            "{:<24}{:<8}{:<22}{}\n",
+            field(condition, "/type"),
+            field(condition, "/status"),
+            field(condition, "/lastTransitionTime"),
+            field(condition, "/reason")
+        ));
+    }
+    Ok(report)
+}
+
+// List the events of a kubernetes resource like the Events section of "kubectl describe"
+//   <last seen> <type> <reason> (x<count>) <source>: <message>
+// Events of resources that are not namespaced are searched in all namespaces, because events
+// of nodes are created in the default namespace and events of namespaces in the namespace itself.
+fn events(
+    context: &str,
+    namespace: Option<&str>,
+    resource_type: ResourceType,
+    name: &str,
+) -> Result<String, K8sError> {
+    let field_selector = format!(
+        "--field-selector=involvedObject.kind={},involvedObject.name={}",
+        resource_type.kind(),
+        name
+    );
+    let mut events = kubectl::get_items(context, namespace, "events", &[&field_selector])?;
+    events.sort_by(|a, b| field(a, "/lastTimestamp").cmp(field(b, "/lastTimestamp")));
+
+    let mut report = String::new();
+    for event in &events {
+        report.push_str(&format!(
+            "{} {} {} (x{}) {}: {}\n",
+            field(event, "/lastTimestamp"),
+            field(event, "/type"),
+            field(event, "/reason"),
+            event.pointer("/count").and_then(Value::as_u64).unwrap_or(1),
+            field(event, "/source/component"),
+            field(event, "/message").trim_end()
+        ));
+    }
+    Ok(report)
+}
+
+// One line per entry of a metadata field, e.g. "app=web" for labels
+// Line breaks in values (e.g. last-applied-configuration annotations) are escaped, so every
+// entry stays on one line.
+fn metadata(object: &Value, metadata_field: MetadataField) -> String {
+    let lines: Vec<String> = match metadata_field {
+        MetadataField::Labels | MetadataField::Annotations => {
+            let pointer = if metadata_field == MetadataField::Labels {
+                "/metadata/labels"
+            } else {
+                "/metadata/annotations"
+            };
+            object
+                .pointer(pointer)
+                .and_then(Value::as_object)
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .map(|(key, value)| {
+                            format!(
+                                "{}={}",
+                                key,
+                                value.as_str().unwrap_or_default().replace('\n', "\\n")
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+        MetadataField::Finalizers => array(object, "/metadata/finalizers")
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        MetadataField::OwnerReferences => array(object, "/metadata/ownerReferences")
+            .iter()
+            .map(|owner| format!("{}={}", field(owner, "/kind"), field(owner, "/name")))
+            .collect(),
+    };
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Describe why a namespace is still terminating and when rmdir will finalize it
+fn termination_status(
+    context: &str,
+    namespace_name: &str,
+    force_delete: bool,
+) -> Result<String, K8sError> {
+    let namespace = kubectl::get_object(context, None, "namespaces", namespace_name)?;
+    let deletion_timestamp = field(&namespace, "/metadata/deletionTimestamp");
+    let Some(terminating_since) = parse_timestamp(deletion_timestamp) else {
+        return Ok(format!("{}\n", field(&namespace, "/status/phase")));
+    };
+    let terminating_for = terminating_since.elapsed().unwrap_or_default();
+
+    let mut report = format!(
+        "Terminating since {} ({}s)\n",
+        deletion_timestamp,
+        terminating_for.as_secs()
+    );
+    let finalizers: Vec<&str> = array(&namespace, "/spec/finalizers")
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    report.push_str(&format!("Finalizers: {}\n", finalizers.join(", ")));
+    // Conditions that are true describe what blocks the deletion
+    for condition in array(&namespace, "/status/conditions")
+        .iter()
+        .filter(|condition| field(condition, "/status") == "True")
+    {
+        report.push_str(&format!(
+            "{}: {}\n",
+            field(condition, "/type"),
+            field(condition, "/message")
+        ));
+    }
+    if force_delete || terminating_for >= FORCE_DELETE_AFTER {
+        report.push_str("Run rmdir again to finalize the namespace\n");
+    } else {
+        report.push_str(&format!(
+            "rmdir finalizes the namespace in {}s\n",
+            (FORCE_DELETE_AFTER - terminating_for).as_secs()
+        ));
+    }
+    Ok(report)
+}
+
+// Parse the list of log files that the logs endpoint of a kubelet returns
+//   <pre>
+//   <a href="syslog">syslog</a>
+//   <a href="pods/">pods/</a>
+//   </pre>
+// Directories are skipped.
+pub fn node_log_files(listing: &str) -> Vec<String> {
+    listing
+        .split("href=\"")
+        .skip(1)
+        .filter_map(|entry| entry.split('"').next())
+        .filter(|file| !file.is_empty() && !file.ends_with('/') && !file.contains("://"))
+        .map(String::from)
+        .collect()
+}
+
+// Resources that are summarized in allocatable files
+const NODE_RESOURCES: [&str; 4] = ["cpu", "memory", "ephemeral-storage", "pods"];
+
+// Compare the capacity of a node with what is allocatable and what the pods on it request
+fn allocatable(context: &str, node_name: &str) -> Result<String, K8sError> {
+    let node = kubectl::get_object(context, None, "nodes", node_name)?;
+    let field_selector = format!(
+        "--field-selector=spec.nodeName={},status.phase!=Succeeded,status.phase!=Failed",
+        node_name
+    );
+    let pods = kubectl::get_items(context, None, "pods", &[&field_selector])?;
+
+    let mut report = format!(
+        "{:<20}{:<14}{:<14}{:<14}{}\n",
+        "RESOURCE", "CAPACITY", "ALLOCATABLE", "REQUESTED", "REQUESTED %"
+    );
+    for resource in NODE_RESOURCES {
+        let quantity = |pointer: &str| {
+            node.pointer(pointer)
+                .and_then(|resources| resources.get(resource))
+                .and_then(Value::as_str)
+                .and_then(parse_quantity)
+        };
+        let capacity = quantity("/status/capacity");
+        let allocatable = quantity("/status/allocatable");
+        let requested = if resource == "pods" {
+            pods.len() as f64
+        } else {
+            pods.iter().map(|pod| pod_request(pod, resource)).sum()
+        };
+        let percentage = match allocatable {
+            Some(allocatable) if allocatable > 0.0 => {
+                format!("{:.0}%", requested / allocatable * 100.0)
+            }
+            _ => String::from("-"),
+        };
+        report.push_str(&format!(
+            "{:<20}{:<14}{:<14}{:<14}{}\n",
+            resource,
+            capacity.map_or(String::from("-"), |capacity| format_quantity(
+                resource, capacity
+            )),
+            allocatable.map_or(String::from("-"), |allocatable| {
+                format_quantity(resource, allocatable)
+            }),
+            format_quantity(resource, requested),
+            percentage
+        ));
+    }
+    Ok(report)
+}
+
+// Return the amount of a resource that a pod requests
+// Init containers run before the other containers, so only the largest request of them
+// counts if it is larger than the sum of the requests of the other containers.
+pub fn pod_request(pod: &Value, resource: &str) -> f64 {
+    let request = |container: &Value| {
+        container
+            .pointer("/resources/requests")
+            .and_then(|requests| requests.get(resource))
+            .and_then(Value::as_str)
+            .and_then(parse_quantity)
+            .unwrap_or(0.0)
+    };
+    let containers: f64 = array(pod, "/spec/containers").iter().map(request).sum();
+    let init_containers = array(pod, "/spec/initContainers")
+        .iter()
+        .map(request)
+        .fold(0.0, f64::max);
+    containers.max(init_containers)
+}
+
+// Parse a kubernetes quantity (e.g. 100m, 2, 512Mi, 1G, 1e3)
+fn parse_quantity(quantity: &str) -> Option<f64> {
+    const SUFFIXES: [(&str, f64); 13] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("Pi", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("Ei", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+    for (suffix, multiplier) in SUFFIXES {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<f64>().ok().map(|number| number * multiplier);
+        }
+    }
+    // Plain numbers and decimal exponents (1e3) are understood by the float parser
+    quantity.parse::<f64>().ok()
+}
+
+// Format an amount of a resource the way it is usually written
+fn format_quantity(resource: &str, amount: f64) -> String {
+    match resource {
+        "cpu" => format!("{:.0}m", amount * 1000.0),
+        "memory" | "ephemeral-storage" => format!("{:.0}Mi", amount / (1024.0 * 1024.0)),
+        _ => format!("{:.0}", amount),
+    }
+}
@@ -0,0 +1,183 @@
+// Progress of the rollout of a deployment or StatefulSet as a stream
+// Opening the rollout-status file of a deployment or StatefulSet starts following its rollout,
+// like "kubectl rollout status": a line is appended whenever the replica counts or the progress
+// change. Reading blocks until the next line and ends when the rollout completed. If it failed
+// (e.g. the progress deadline was exceeded), reading fails with EIO at the end, so that
+// "cat rollout-status && deploy-next" stops. The rollout is no longer followed once the stream
+// is finished by the filesystem (when the file was closed by all readers).
+//   2026-10-16T11:58:02Z updated 1/3 ready 3/3 available 3/3 (ReplicaSetUpdated)
+//   2026-10-16T11:58:40Z updated 3/3 ready 3/3 available 3/3 (NewReplicaSetAvailable)
+//   rollout complete
+
+use crate::k8s_resource::format_timestamp;
+use crate::kubectl;
+use crate::reports::{array, field};
+use crate::stream::StreamBuffer;
+use serde_json::Value;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub const ROLLOUT_STATUS_FILE_NAME: &str = "rollout-status";
+// How often the object is retrieved while the rollout is followed
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Outcome of a rollout as far as it is known
+enum Progress {
+    Waiting,
+    Complete,
+    Failed(String),
+}
+
+// Start following the rollout of a deployment or StatefulSet in a background thread
+// The thread stops once the returned stream is finished.
+pub fn start(context: &str, namespace: &str, resource: &str, name: &str) -> Arc<StreamBuffer> {
+    let stream = Arc::new(StreamBuffer::default());
+    let followed_stream = stream.clone();
+    let (context, namespace, resource, name) = (
+        context.to_string(),
+        namespace.to_string(),
+        resource.to_string(),
+        name.to_string(),
+    );
+    thread::spawn(move || {
+        let mut last_line = String::new();
+        while !followed_stream.is_finished() {
+            let object = match kubectl::get_object(&context, Some(&namespace), &resource, &name) {
+                Ok(object) => object,
+                Err(error) => {
+                    followed_stream.append(format!("rollout failed: {}\n", error).as_bytes());
+                    followed_stream.fail();
+                    return;
+                }
+            };
+            let (line, progress) = match resource.as_str() {
+                "statefulsets" => statefulset_progress(&object),
+                _ => progress(&object),
+            };
+            if line != last_line {
+                followed_stream.append(
+                    format!("{} {}\n", format_timestamp(SystemTime::now()), line).as_bytes(),
+                );
+                last_line = line;
+            }
+            match progress {
+                Progress::Waiting => thread::sleep(POLL_INTERVAL),
+                Progress::Complete => {
+                    followed_stream.append(b"rollout complete\n");
+                    followed_stream.finish();
+                    return;
+                }
+                Progress::Failed(reason) => {
+                    followed_stream.append(format!("rollout failed: {}\n", reason).as_bytes());
+                    followed_stream.fail();
+                    return;
+                }
+            }
+        }
+    });
+    stream
+}
+
+// Replica counts of a StatefulSet and whether its rollout is done, using the same rules as
+// "kubectl rollout status"
+fn statefulset_progress(statefulset: &Value) -> (String, Progress) {
+    let count = |pointer: &str| {
+        statefulset
+            .pointer(pointer)
+            .and_then(Value::as_u64)
+            .unwrap_or_default()
+    };
+    let desired = statefulset
+        .pointer("/spec/replicas")
+        .and_then(Value::as_u64)
+        .unwrap_or(1);
+    let (updated, ready) = (
+        count("/status/updatedReplicas"),
+        count("/status/readyReplicas"),
+    );
+    let line = format!(
+        "updated {}/{} ready {}/{} revision {}",
+        updated,
+        desired,
+        ready,
+        desired,
+        field(statefulset, "/status/updateRevision")
+    );
+
+    let strategy = field(statefulset, "/spec/updateStrategy/type");
+    // Only pods that are not below the partition are updated
+    let partition = statefulset
+        .pointer("/spec/updateStrategy/rollingUpdate/partition")
+        .and_then(Value::as_u64);
+    let progress = if !strategy.is_empty() && strategy != "RollingUpdate" {
+        Progress::Failed(format!(
+            "rollout status is only available for the RollingUpdate strategy, not {}",
+            strategy
+        ))
+    } else if count("/status/observedGeneration") == 0
+        || count("/metadata/generation") > count("/status/observedGeneration")
+        || ready < desired
+    {
+        Progress::Waiting
+    } else if let Some(partition) = partition {
+        match updated < desired.saturating_sub(partition) {
+            true => Progress::Waiting,
+            false => Progress::Complete,
+        }
+    } else if field(statefulset, "/status/updateRevision")
+        != field(statefulset, "/status/currentRevision")
+    {
+        Progress::Waiting
+    } else {
+        Progress::Complete
+    };
+    (line, progress)
+}
+
+// Replica counts of a deployment and whether its rollout is done, using the same rules as
+// "kubectl rollout status"
+fn progress(deployment: &Value) -> (String, Progress) {
+    let count = |pointer: &str| {
+        deployment
+            .pointer(pointer)
+            .and_then(Value::as_u64)
+            .unwrap_or_default()
+    };
+    let desired = deployment
+        .pointer("/spec/replicas")
+        .and_then(Value::as_u64)
+        .unwrap_or(1);
+    let (replicas, updated, ready, available) = (
+        count("/status/replicas"),
+        count("/status/updatedReplicas"),
+        count("/status/readyReplicas"),
+        count("/status/availableReplicas"),
+    );
+    let progressing = array(deployment, "/status/conditions")
+        .iter()
+        .find(|condition| field(condition, "/type") == "Progressing");
+    let mut line = format!(
+        "updated {}/{} ready {}/{} available {}/{}",
+        updated, desired, ready, desired, available, desired
+    );
+    if replicas > updated {
+        line.push_str(&format!(" old {}", replicas - updated));
+    }
+    if let Some(condition) = progressing {
+        line.push_str(&format!(" ({})", field(condition, "/reason")));
+    }
+
+    let progress = if count("/metadata/generation") > count("/status/observedGeneration") {
+        Progress::Waiting
+    } else if let Some(condition) =
+        progressing.filter(|condition| field(condition, "/reason") == "ProgressDeadlineExceeded")
+    {
+        Progress::Failed(field(condition, "/message").to_string())
+    } else if updated < desired || replicas > updated || available < updated {
+        Progress::Waiting
+    } else {
+        Progress::Complete
+    };
+    (line, progress)
+}
@@ -0,0 +1,198 @@
+// Summary of the scheduling constraints of a pod
+// The scheduling file of a pod lists the node it runs on, its node selector, node affinity,
+// pod (anti-)affinity, tolerations and topology spread constraints, one constraint per line,
+// so that they do not have to be picked out of the definition during scheduling investigations.
+//   node: worker-2
+//   nodeSelector:
+//     disktype=ssd
+//   nodeAffinity:
+//     required: topology.kubernetes.io/zone In (eu-1a, eu-1b)
+//     preferred (weight 10): node-role Exists
+//   podAntiAffinity:
+//     required on kubernetes.io/hostname: app=web
+//   tolerations:
+//     node.kubernetes.io/not-ready Exists NoExecute for 300s
+//   topologySpreadConstraints:
+//     maxSkew 1 on topology.kubernetes.io/zone (DoNotSchedule): app=web
+
+use crate::reports::{array, field};
+use serde_json::Value;
+
+pub const SCHEDULING_FILE_NAME: &str = "scheduling";
+
+pub fn summary(pod: &Value) -> String {
+    let node = match field(pod, "/spec/nodeName") {
+        "" => "<not scheduled>",
+        node => node,
+    };
+    let mut report = format!("node: {}\n", node);
+
+    let node_selector = pod
+        .pointer("/spec/nodeSelector")
+        .and_then(Value::as_object)
+        .map(|selector| {
+            selector
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value.as_str().unwrap_or_default()))
+                .collect()
+        })
+        .unwrap_or_default();
+    push_section(&mut report, "nodeSelector", node_selector);
+
+    let node_affinity = pod.pointer("/spec/affinity/nodeAffinity");
+    let mut lines = Vec::new();
+    if let Some(affinity) = node_affinity {
+        // Terms are ORed, the expressions of a term are ANDed
+        for term in array(
+            affinity,
+            "/requiredDuringSchedulingIgnoredDuringExecution/nodeSelectorTerms",
+        ) {
+            lines.push(format!("required: {}", node_selector_term(term)));
+        }
+        for term in array(affinity, "/preferredDuringSchedulingIgnoredDuringExecution") {
+            lines.push(format!(
+                "preferred (weight {}): {}",
+                weight(term),
+                node_selector_term(term.pointer("/preference").unwrap_or(&Value::Null))
+            ));
+        }
+    }
+    push_section(&mut report, "nodeAffinity", lines);
+
+    for kind in ["podAffinity", "podAntiAffinity"] {
+        let mut lines = Vec::new();
+        if let Some(affinity) = pod.pointer(&format!("/spec/affinity/{}", kind)) {
+            for term in array(affinity, "/requiredDuringSchedulingIgnoredDuringExecution") {
+                lines.push(format!("required {}", pod_affinity_term(term)));
+            }
+            for term in array(affinity, "/preferredDuringSchedulingIgnoredDuringExecution") {
+                lines.push(format!(
+                    "preferred (weight {}) {}",
+                    weight(term),
+                    pod_affinity_term(term.pointer("/podAffinityTerm").unwrap_or(&Value::Null))
+                ));
+            }
+        }
+        push_section(&mut report, kind, lines);
+    }
+
+    let tolerations = array(pod, "/spec/tolerations")
+        .iter()
+        .map(toleration)
+        .collect();
+    push_section(&mut report, "tolerations", tolerations);
+
+    let constraints = array(pod, "/spec/topologySpreadConstraints")
+        .iter()
+        .map(|constraint| {
+            format!(
+                "maxSkew {} on {} ({}): {}",
+                constraint
+                    .pointer("/maxSkew")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(1),
+                field(constraint, "/topologyKey"),
+                field(constraint, "/whenUnsatisfiable"),
+                label_selector(constraint.pointer("/labelSelector").unwrap_or(&Value::Null))
+            )
+        })
+        .collect();
+    push_section(&mut report, "topologySpreadConstraints", constraints);
+    report
+}
+
+// Add a section with one indented line per constraint, or <none>
+fn push_section(report: &mut String, name: &str, lines: Vec<String>) {
+    if lines.is_empty() {
+        report.push_str(&format!("{}: <none>\n", name));
+        return;
+    }
+    report.push_str(&format!("{}:\n", name));
+    for line in lines {
+        report.push_str(&format!("  {}\n", line));
+    }
+}
+
+fn weight(term: &Value) -> i64 {
+    term.pointer("/weight")
+        .and_then(Value::as_i64)
+        .unwrap_or_default()
+}
+
+// Requirement of a node selector or a label selector (e.g. zone In (a, b), gpu Exists)
+fn requirement(expression: &Value) -> String {
+    let values: Vec<&str> = array(expression, "/values")
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    let (key, operator) = (field(expression, "/key"), field(expression, "/operator"));
+    match operator {
+        "Exists" | "DoesNotExist" => format!("{} {}", key, operator),
+        "Gt" | "Lt" => format!("{} {} {}", key, operator, values.join(", ")),
+        _ => format!("{} {} ({})", key, operator, values.join(", ")),
+    }
+}
+
+fn node_selector_term(term: &Value) -> String {
+    let requirements: Vec<String> = array(term, "/matchExpressions")
+        .iter()
+        .chain(array(term, "/matchFields"))
+        .map(requirement)
+        .collect();
+    requirements.join(", ")
+}
+
+fn label_selector(selector: &Value) -> String {
+    let mut requirements: Vec<String> = selector
+        .pointer("/matchLabels")
+        .and_then(Value::as_object)
+        .map(|labels| {
+            labels
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value.as_str().unwrap_or_default()))
+                .collect()
+        })
+        .unwrap_or_default();
+    requirements.extend(array(selector, "/matchExpressions").iter().map(requirement));
+    match requirements.is_empty() {
+        true => "<all pods>".to_string(),
+        false => requirements.join(", "),
+    }
+}
+
+fn pod_affinity_term(term: &Value) -> String {
+    let namespaces: Vec<&str> = array(term, "/namespaces")
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    let mut line = format!(
+        "on {}: {}",
+        field(term, "/topologyKey"),
+        label_selector(term.pointer("/labelSelector").unwrap_or(&Value::Null))
+    );
+    if !namespaces.is_empty() {
+        line.push_str(&format!(" in {}", namespaces.join(", ")));
+    }
+    line
+}
+
+// Toleration as <key>[=<value>] <operator> [<effect>] [for <seconds>s]
+// A toleration without a key tolerates every taint.
+fn toleration(toleration: &Value) -> String {
+    let mut line = match (field(toleration, "/key"), field(toleration, "/operator")) {
+        ("", _) => "<all taints> Exists".to_string(),
+        (key, "Exists") => format!("{} Exists", key),
+        (key, _) => format!("{}={} Equal", key, field(toleration, "/value")),
+    };
+    match field(toleration, "/effect") {
+        "" => line.push_str(" <all effects>"),
+        effect => line.push_str(&format!(" {}", effect)),
+    }
+    if let Some(seconds) = toleration
+        .pointer("/tolerationSeconds")
+        .and_then(Value::as_i64)
+    {
+        line.push_str(&format!(" for {}s", seconds));
+    }
+    line
+}
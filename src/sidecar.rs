@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Running k8sfs as a sidecar container that exposes the cluster to the other containers of a pod
+// The configuration comes from environment variables, since there is no shell to build
+// arguments in most images:
+//   K8SFS_MOUNTPOINT  where the filesystem is mounted (default: /mnt/k8s)
+//   K8SFS_OPTIONS     comma separated mount options (e.g. "namespaces=ci,idle_timeout=0")
+// The service account of the pod is used unless a context is part of the options.
+
+pub const MOUNTPOINT_VARIABLE: &str = "K8SFS_MOUNTPOINT";
+pub const OPTIONS_VARIABLE: &str = "K8SFS_OPTIONS";
+pub const DEFAULT_MOUNTPOINT: &str = "/mnt/k8s";
+
+// Set once the kubelet asked the container to stop
+static TERMINATED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_termination(_signal: libc::c_int) {
+    // Only async-signal-safe operations are allowed here
+    TERMINATED.store(true, Ordering::SeqCst);
+}
+
+// Remember SIGTERM (sent by the kubelet when the pod is deleted) and SIGINT instead of exiting
+// right away, so that the filesystem can be unmounted first
+pub fn handle_termination() {
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        // SAFETY: The handler only stores to an atomic
+        let previous =
+            unsafe { libc::signal(signal, on_termination as *const () as libc::sighandler_t) };
+        if previous == libc::SIG_ERR {
+            log::error!("Could not handle signal {}", signal);
+        }
+    }
+}
+
+// Return true if the container is supposed to stop
+pub fn is_terminated() -> bool {
+    TERMINATED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,94 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Number of latencies that are kept per operation to calculate percentiles
+const MAX_LATENCY_SAMPLES: usize = 1024;
+
+#[derive(Default)]
+struct Operation {
+    count: u64,
+    // Latencies of the most recent calls
+    latencies: VecDeque<Duration>,
+}
+
+impl Operation {
+    fn record(&mut self, latency: Duration) {
+        self.count += 1;
+        if self.latencies.len() == MAX_LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    // Return the latency in microseconds that the given share of calls did not exceed
+    fn percentile(sorted: &[Duration], percentile: usize) -> u128 {
+        if sorted.is_empty() {
+            return 0;
+        }
+        let index = (sorted.len() * percentile).div_ceil(100).max(1) - 1;
+        sorted[index].as_micros()
+    }
+}
+
+// Counts and latencies of the FUSE operations that were handled by the filesystem
+// Clones share the same statistics.
+#[derive(Clone, Default)]
+pub struct OperationStats {
+    operations: Arc<Mutex<BTreeMap<&'static str, Operation>>>,
+}
+
+impl OperationStats {
+    // Start timing an operation, its latency is recorded when the timer is dropped
+    pub fn time(&self, name: &'static str) -> Timer {
+        Timer {
+            stats: self.clone(),
+            name,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.operations.lock().unwrap().clear();
+    }
+
+    // Human readable statistics, one line per operation
+    pub fn report(&self) -> String {
+        let operations = self.operations.lock().unwrap();
+        let mut report = String::new();
+        for (name, operation) in operations.iter() {
+            let mut sorted: Vec<Duration> = operation.latencies.iter().copied().collect();
+            sorted.sort();
+            report.push_str(&format!(
+                "{} count {} p50_us {} p90_us {} p99_us {}\n",
+                name,
+                operation.count,
+                Operation::percentile(&sorted, 50),
+                Operation::percentile(&sorted, 90),
+                Operation::percentile(&sorted, 99)
+            ));
+        }
+        report
+    }
+
+    fn record(&self, name: &'static str, latency: Duration) {
+        self.operations
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_default()
+            .record(latency);
+    }
+}
+
+pub struct Timer {
+    stats: OperationStats,
+    name: &'static str,
+    started: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.stats.record(self.name, self.started.elapsed());
+    }
+}
@@ -0,0 +1,214 @@
+// Storage configuration of the cluster
+// storageclasses/ and csidrivers/ in the context directory contain a _summary table and a file
+// per object with one "key: value" line per setting, so that the settings can be grepped.
+
+use crate::kubectl::{self, K8sError};
+use crate::reports::{array, field};
+use serde_json::Value;
+
+// Name of the file inside the storage directories that contains the table of all objects
+pub const SUMMARY_FILE_NAME: &str = "_summary";
+
+// Annotations that mark the default storage class (the beta one is still used by some clusters)
+const DEFAULT_CLASS_ANNOTATIONS: [&str; 2] = [
+    "storageclass.kubernetes.io/is-default-class",
+    "storageclass.beta.kubernetes.io/is-default-class",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageKind {
+    StorageClass,
+    CsiDriver,
+}
+
+impl StorageKind {
+    pub fn all() -> [StorageKind; 2] {
+        [StorageKind::StorageClass, StorageKind::CsiDriver]
+    }
+
+    // Name of the directory in the context directory
+    pub fn directory_name(&self) -> &'static str {
+        match self {
+            StorageKind::StorageClass => "storageclasses",
+            StorageKind::CsiDriver => "csidrivers",
+        }
+    }
+
+    pub fn kubectl_name(&self) -> &'static str {
+        match self {
+            StorageKind::StorageClass => "storageclasses.storage.k8s.io",
+            StorageKind::CsiDriver => "csidrivers.storage.k8s.io",
+        }
+    }
+
+    // Table of all objects, one line per object
+    pub fn summary(&self, context: &str) -> Result<String, K8sError> {
+        let objects = kubectl::get_items(context, None, self.kubectl_name(), &[])?;
+        let mut report = match self {
+            StorageKind::StorageClass => format!(
+                "  {:<30}{:<40}{:<10}{:<22}{}\n",
+                "NAME", "PROVISIONER", "RECLAIM", "BINDING", "EXPANSION"
+            ),
+            StorageKind::CsiDriver => format!(
+                "{:<40}{:<10}{:<10}{:<30}{}\n",
+                "NAME", "ATTACH", "POD-INFO", "FS-GROUP-POLICY", "MODES"
+            ),
+        };
+        for object in &objects {
+            let settings = self.settings(object, &[]);
+            let setting = |name: &str| {
+                settings
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map_or("", |(_, value)| value.as_str())
+            };
+            report.push_str(&match self {
+                // The default storage class is marked with a "*"
+                StorageKind::StorageClass => format!(
+                    "{} {:<30}{:<40}{:<10}{:<22}{}\n",
+                    if setting("default") == "true" {
+                        "*"
+                    } else {
+                        " "
+                    },
+                    field(object, "/metadata/name"),
+                    setting("provisioner"),
+                    setting("reclaimPolicy"),
+                    setting("volumeBindingMode"),
+                    setting("allowVolumeExpansion")
+                ),
+                StorageKind::CsiDriver => format!(
+                    "{:<40}{:<10}{:<10}{:<30}{}\n",
+                    field(object, "/metadata/name"),
+                    setting("attachRequired"),
+                    setting("podInfoOnMount"),
+                    setting("fsGroupPolicy"),
+                    setting("volumeLifecycleModes")
+                ),
+            });
+        }
+        Ok(report)
+    }
+
+    // Settings of a single object as "key: value" lines
+    pub fn report(&self, context: &str, name: &str) -> Result<String, K8sError> {
+        let object = kubectl::get_object(context, None, self.kubectl_name(), name)?;
+        // The storage classes that use a CSI driver are listed with the driver
+        let classes = match self {
+            StorageKind::StorageClass => Vec::new(),
+            StorageKind::CsiDriver => {
+                kubectl::get_items(context, None, StorageKind::StorageClass.kubectl_name(), &[])?
+            }
+        };
+        Ok(self
+            .settings(&object, &classes)
+            .iter()
+            .map(|(key, value)| format!("{}: {}\n", key, value))
+            .collect())
+    }
+
+    // Settings of an object with the values the API server uses for unset fields
+    fn settings(&self, object: &Value, classes: &[Value]) -> Vec<(String, String)> {
+        let text = |pointer: &str, default: &str| match field(object, pointer) {
+            "" => default.to_string(),
+            value => value.to_string(),
+        };
+        let flag = |pointer: &str, default: bool| {
+            object
+                .pointer(pointer)
+                .and_then(Value::as_bool)
+                .unwrap_or(default)
+                .to_string()
+        };
+        match self {
+            StorageKind::StorageClass => {
+                let is_default = DEFAULT_CLASS_ANNOTATIONS.iter().any(|annotation| {
+                    object
+                        .pointer("/metadata/annotations")
+                        .and_then(|annotations| annotations.get(annotation))
+                        .and_then(Value::as_str)
+                        == Some("true")
+                });
+                let mut settings = vec![
+                    ("default".to_string(), is_default.to_string()),
+                    ("provisioner".to_string(), text("/provisioner", "")),
+                    (
+                        "reclaimPolicy".to_string(),
+                        text("/reclaimPolicy", "Delete"),
+                    ),
+                    (
+                        "volumeBindingMode".to_string(),
+                        text("/volumeBindingMode", "Immediate"),
+                    ),
+                    (
+                        "allowVolumeExpansion".to_string(),
+                        flag("/allowVolumeExpansion", false),
+                    ),
+                    (
+                        "mountOptions".to_string(),
+                        strings(array(object, "/mountOptions")),
+                    ),
+                ];
+                let parameters = object.pointer("/parameters").and_then(Value::as_object);
+                for (key, value) in parameters.into_iter().flatten() {
+                    settings.push((
+                        format!("parameters.{}", key),
+                        value.as_str().unwrap_or_default().to_string(),
+                    ));
+                }
+                settings
+            }
+            StorageKind::CsiDriver => {
+                let modes = array(object, "/spec/volumeLifecycleModes");
+                let name = field(object, "/metadata/name");
+                vec![
+                    (
+                        "attachRequired".to_string(),
+                        flag("/spec/attachRequired", true),
+                    ),
+                    (
+                        "podInfoOnMount".to_string(),
+                        flag("/spec/podInfoOnMount", false),
+                    ),
+                    (
+                        "fsGroupPolicy".to_string(),
+                        text("/spec/fsGroupPolicy", "ReadWriteOnceWithFSType"),
+                    ),
+                    (
+                        "volumeLifecycleModes".to_string(),
+                        match modes.is_empty() {
+                            true => "Persistent".to_string(),
+                            false => strings(modes),
+                        },
+                    ),
+                    (
+                        "storageCapacity".to_string(),
+                        flag("/spec/storageCapacity", false),
+                    ),
+                    (
+                        "requiresRepublish".to_string(),
+                        flag("/spec/requiresRepublish", false),
+                    ),
+                    (
+                        "storageClasses".to_string(),
+                        classes
+                            .iter()
+                            .filter(|class| field(class, "/provisioner") == name)
+                            .map(|class| field(class, "/metadata/name"))
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+// Join a list of strings with ","
+fn strings(values: &[Value]) -> String {
+    values
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+#[derive(Default)]
+struct StreamState {
+    // Data that is still buffered, starting at offset start
+    data: VecDeque<u8>,
+    start: usize,
+    finished: bool,
+    // Set if the stream ended with an error, which readers get once they read all data
+    failed: bool,
+    // Older data is dropped once the buffer grows beyond this many bytes
+    max_bytes: Option<usize>,
+}
+
+// Append-only output that readers can wait for (e.g. exec sessions or the event stream)
+// Files that are backed by a stream block on read until there is data at the offset.
+#[derive(Default)]
+pub struct StreamBuffer {
+    state: Mutex<StreamState>,
+    changed: Condvar,
+}
+
+impl StreamBuffer {
+    // Create a stream that only keeps the last max_bytes bytes
+    // Reads of dropped data continue at the oldest data that is still buffered.
+    pub fn with_limit(max_bytes: usize) -> Self {
+        StreamBuffer {
+            state: Mutex::new(StreamState {
+                max_bytes: Some(max_bytes),
+                ..Default::default()
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    // Return up to size bytes starting at offset
+    // Blocks until there is data at the offset or the stream finished, in which case
+    // nothing is returned (EOF).
+    pub fn read(&self, offset: usize, size: usize) -> Vec<u8> {
+        let state = self
+            .changed
+            .wait_while(self.state.lock().unwrap(), |state| {
+                state.start + state.data.len() <= offset && !state.finished
+            })
+            .unwrap();
+        let start = offset.saturating_sub(state.start);
+        state.data.iter().skip(start).take(size).copied().collect()
+    }
+
+    // Return true if reading at the offset would not block
+    pub fn is_available(&self, offset: usize) -> bool {
+        let state = self.state.lock().unwrap();
+        state.start + state.data.len() > offset || state.finished
+    }
+
+    // Offset at which the next data will be appended
+    pub fn end_offset(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.start + state.data.len()
+    }
+
+    pub fn append(&self, data: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        state.data.extend(data);
+        if let Some(max_bytes) = state.max_bytes {
+            let excess = state.data.len().saturating_sub(max_bytes);
+            state.data.drain(..excess);
+            state.start += excess;
+        }
+        self.changed.notify_all();
+    }
+
+    pub fn finish(&self) {
+        self.state.lock().unwrap().finished = true;
+        self.changed.notify_all();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+
+    // Finish the stream with an error
+    pub fn fail(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.finished = true;
+        state.failed = true;
+        self.changed.notify_all();
+    }
+
+    // Return true if the stream ended with an error and there is no data at the offset
+    pub fn has_failed_at(&self, offset: usize) -> bool {
+        let state = self.state.lock().unwrap();
+        state.failed && state.start + state.data.len() <= offset
+    }
+}
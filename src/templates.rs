@@ -0,0 +1,129 @@
+// Placeholders that are replaced when a template is instantiated
+const NAME_PLACEHOLDER: &str = "{{name}}";
+const NAMESPACE_PLACEHOLDER: &str = "{{namespace}}";
+
+// Directory inside namespace directories that contains the manifests of all templates
+pub const TEMPLATES_DIRECTORY_NAME: &str = ".templates";
+
+// Manifest that is applied when a file "<name>.<template>" is created in a namespace directory
+// Templates are defined in the config file:
+//   [templates.deployment]
+//   manifest = """
+//   apiVersion: apps/v1
+//   kind: Deployment
+//   metadata:
+//     name: {{name}}
+//     namespace: {{namespace}}
+//   ...
+//   """
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    // Used as the extension of the file names that instantiate this template
+    pub name: String,
+    pub manifest: String,
+}
+
+impl Template {
+    fn new(name: &str, manifest: &str) -> Self {
+        Template {
+            name: name.to_string(),
+            manifest: manifest.to_string(),
+        }
+    }
+
+    // Parse a template from an entry of the config file
+    pub fn from_toml(name: &str, entry: &toml::Value) -> Result<Template, String> {
+        if name.is_empty() || name.contains('.') || name.contains('/') {
+            return Err(format!("Invalid template name \"{}\"", name));
+        }
+        let manifest = entry
+            .get("manifest")
+            .and_then(toml::Value::as_str)
+            .ok_or_else(|| format!("Template \"{}\" is missing \"manifest\"", name))?;
+        if !manifest.contains(NAME_PLACEHOLDER) {
+            return Err(format!(
+                "Template \"{}\" does not use {}",
+                name, NAME_PLACEHOLDER
+            ));
+        }
+
+        Ok(Template {
+            name: name.to_string(),
+            manifest: manifest.to_string(),
+        })
+    }
+
+    // Return the resource name if the file name instantiates this template
+    //   nginx.deployment => nginx
+    pub fn resource_name<'a>(&self, file_name: &'a str) -> Option<&'a str> {
+        file_name
+            .strip_suffix(&self.name)
+            .and_then(|name| name.strip_suffix('.'))
+            .filter(|name| !name.is_empty())
+    }
+
+    // Fill in the placeholders of the manifest
+    pub fn render(&self, name: &str, namespace: &str) -> String {
+        fill(&self.manifest, name, namespace)
+    }
+}
+
+// Fill in the placeholders of a manifest (e.g. a copy of a template that was edited)
+pub fn fill(manifest: &str, name: &str, namespace: &str) -> String {
+    manifest
+        .replace(NAME_PLACEHOLDER, name)
+        .replace(NAMESPACE_PLACEHOLDER, namespace)
+}
+
+// Skeletons that are available if the config file does not define templates with these names
+pub fn default_templates() -> Vec<Template> {
+    vec![
+        Template::new(
+            "deployment",
+            "apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: {{name}}
+  namespace: {{namespace}}
+spec:
+  replicas: 1
+  selector:
+    matchLabels:
+      app: {{name}}
+  template:
+    metadata:
+      labels:
+        app: {{name}}
+    spec:
+      containers:
+      - name: {{name}}
+        image: {{name}}
+",
+        ),
+        Template::new(
+            "service",
+            "apiVersion: v1
+kind: Service
+metadata:
+  name: {{name}}
+  namespace: {{namespace}}
+spec:
+  selector:
+    app: {{name}}
+  ports:
+  - port: 80
+    targetPort: 80
+",
+        ),
+        Template::new(
+            "configmap",
+            "apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: {{name}}
+  namespace: {{namespace}}
+data: {}
+",
+        ),
+    ]
+}
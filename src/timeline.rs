@@ -0,0 +1,171 @@
+// Timeline of an incident: warning events, container restarts and node condition changes of the
+// last minutes in one chronologically sorted report, so that responders quickly get the story
+// of what happened without querying three different places.
+
+use crate::k8s_resource::{format_timestamp, parse_timestamp};
+use crate::kubectl::{self, K8sError};
+use crate::reports::{array, field};
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+
+// Name of the file inside the context directory
+pub const TIMELINE_FILE_NAME: &str = "timeline";
+// How far back the timeline goes by default
+pub const DEFAULT_TIMELINE_MINUTES: u64 = 60;
+
+// Something that happened in the cluster
+struct Incident {
+    time: SystemTime,
+    source: &'static str,
+    object: String,
+    details: String,
+}
+
+// List what happened within the last minutes, oldest first
+//   TIME                  SOURCE   OBJECT                       DETAILS
+//   2026-10-16T11:58:02Z  node     node-2                       Ready=False KubeletNotReady: ...
+//   2026-10-16T11:58:40Z  event    default/Pod/web-5d9c7-x2x4q  FailedScheduling: 0/3 nodes ...
+//   2026-10-16T11:59:13Z  restart  default/db-0/postgres        OOMKilled (exit code 137), 3 restarts
+// Sources that can not be read (e.g. because of RBAC) are left out with a comment at the end.
+pub fn timeline(context: &str, minutes: u64) -> Result<String, K8sError> {
+    let since = SystemTime::now()
+        .checked_sub(Duration::from_secs(minutes.saturating_mul(60)))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut incidents = Vec::new();
+    let mut missing = Vec::new();
+    match kubectl::get_items(context, None, "events", &["--field-selector=type=Warning"]) {
+        Ok(events) => incidents.extend(events.iter().filter_map(warning_event)),
+        Err(error) => missing.push(format!("# Could not list events: {}", error)),
+    }
+    match kubectl::get_items(context, None, "pods", &[]) {
+        Ok(pods) => incidents.extend(pods.iter().flat_map(restarts)),
+        Err(error) => missing.push(format!("# Could not list pods: {}", error)),
+    }
+    match kubectl::get_items(context, None, "nodes", &[]) {
+        Ok(nodes) => incidents.extend(nodes.iter().flat_map(condition_changes)),
+        Err(error) => missing.push(format!("# Could not list nodes: {}", error)),
+    }
+    // Without any source there is no timeline at all
+    if missing.len() == 3 {
+        return Err(K8sError::Failed(missing.join("\n")));
+    }
+    incidents.retain(|incident| incident.time >= since);
+    incidents.sort_by_key(|incident| incident.time);
+
+    let mut report = format!(
+        "{:<22}{:<9}{:<45}{}\n",
+        "TIME", "SOURCE", "OBJECT", "DETAILS"
+    );
+    for incident in &incidents {
+        report.push_str(&format!(
+            "{:<22}{:<9}{:<45}{}\n",
+            format_timestamp(incident.time),
+            incident.source,
+            incident.object,
+            // Messages can contain line breaks, but every incident has to stay on one line
+            incident
+                .details
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+    for line in missing {
+        report.push_str(&format!("{}\n", line));
+    }
+    Ok(report)
+}
+
+fn warning_event(event: &Value) -> Option<Incident> {
+    let time = [
+        "/lastTimestamp",
+        "/eventTime",
+        "/metadata/creationTimestamp",
+    ]
+    .iter()
+    .find_map(|pointer| parse_timestamp(field(event, pointer)))?;
+    let mut details = format!("{}: {}", field(event, "/reason"), field(event, "/message"));
+    if let Some(count) = event
+        .pointer("/count")
+        .and_then(Value::as_u64)
+        .filter(|count| *count > 1)
+    {
+        details.push_str(&format!(" (x{})", count));
+    }
+    let object = match field(event, "/involvedObject/namespace") {
+        "" => format!(
+            "{}/{}",
+            field(event, "/involvedObject/kind"),
+            field(event, "/involvedObject/name")
+        ),
+        namespace => format!(
+            "{}/{}/{}",
+            namespace,
+            field(event, "/involvedObject/kind"),
+            field(event, "/involvedObject/name")
+        ),
+    };
+    Some(Incident {
+        time,
+        source: "event",
+        object,
+        details,
+    })
+}
+
+// The last restart of every container of a pod, the container statuses do not know earlier ones
+fn restarts(pod: &Value) -> Vec<Incident> {
+    let statuses = array(pod, "/status/initContainerStatuses")
+        .iter()
+        .chain(array(pod, "/status/containerStatuses"));
+    statuses
+        .filter_map(|status| {
+            let terminated = status.pointer("/lastState/terminated")?;
+            let time = parse_timestamp(field(terminated, "/finishedAt"))?;
+            let exit_code = terminated
+                .pointer("/exitCode")
+                .and_then(Value::as_i64)
+                .unwrap_or_default();
+            Some(Incident {
+                time,
+                source: "restart",
+                object: format!(
+                    "{}/{}/{}",
+                    field(pod, "/metadata/namespace"),
+                    field(pod, "/metadata/name"),
+                    field(status, "/name")
+                ),
+                details: format!(
+                    "{} (exit code {}), {} restarts",
+                    field(terminated, "/reason"),
+                    exit_code,
+                    status
+                        .pointer("/restartCount")
+                        .and_then(Value::as_u64)
+                        .unwrap_or_default()
+                ),
+            })
+        })
+        .collect()
+}
+
+// The last change of every condition of a node
+fn condition_changes(node: &Value) -> Vec<Incident> {
+    array(node, "/status/conditions")
+        .iter()
+        .filter_map(|condition| {
+            Some(Incident {
+                time: parse_timestamp(field(condition, "/lastTransitionTime"))?,
+                source: "node",
+                object: field(node, "/metadata/name").to_string(),
+                details: format!(
+                    "{}={} {}: {}",
+                    field(condition, "/type"),
+                    field(condition, "/status"),
+                    field(condition, "/reason"),
+                    field(condition, "/message")
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+// Manifests of resources that were deleted through the filesystem
+// .k8sfs/trash/ keeps the last manifest of every deleted resource for the retention period (see
+// --trash-retention), copying it into .k8sfs/apply/ restores the resource. The trash is kept in
+// memory and is lost once k8sfs exits.
+
+use crate::journal;
+use crate::k8s_resource::format_timestamp;
+use crate::reports::field;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+// Name of the directory inside the control directory
+pub const TRASH_DIRECTORY_NAME: &str = "trash";
+// How long deleted manifests are kept by default
+pub const DEFAULT_TRASH_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub struct TrashedManifest {
+    // File name, e.g. 20261016T120000Z-web.yaml
+    pub name: String,
+    pub manifest: Vec<u8>,
+    deleted: SystemTime,
+}
+
+#[derive(Default)]
+pub struct Trash {
+    // Manifests ordered from oldest to newest
+    manifests: VecDeque<TrashedManifest>,
+}
+
+impl Trash {
+    // Keep the manifest of a deleted object and return it
+    // The manifest is written as JSON, which kubectl reads as YAML as well.
+    pub fn add(&mut self, object: Value) -> &TrashedManifest {
+        let deleted = SystemTime::now();
+        let timestamp: String = format_timestamp(deleted)
+            .chars()
+            .filter(|character| !matches!(character, '-' | ':'))
+            .collect();
+        let name = format!("{}-{}.yaml", timestamp, field(&object, "/metadata/name"));
+        let mut manifest = serde_json::to_vec_pretty(&journal::clean(object)).unwrap_or_default();
+        manifest.push(b'\n');
+        self.manifests.push_back(TrashedManifest {
+            name,
+            manifest,
+            deleted,
+        });
+        self.manifests.back().unwrap()
+    }
+
+    // Forget a manifest (e.g. because it was removed from the trash directory)
+    pub fn remove(&mut self, name: &str) {
+        self.manifests.retain(|trashed| trashed.name != name);
+    }
+
+    // Forget the manifests that are older than the retention period and return their names
+    pub fn expire(&mut self, retention: Duration) -> Vec<String> {
+        let mut expired = Vec::new();
+        while let Some(oldest) = self.manifests.front() {
+            if oldest.deleted.elapsed().unwrap_or_default() < retention {
+                break;
+            }
+            expired.push(self.manifests.pop_front().unwrap().name);
+        }
+        expired
+    }
+
+    pub fn manifests(&self) -> impl Iterator<Item = &TrashedManifest> {
+        self.manifests.iter()
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Number of directory listings within TRAVERSAL_WINDOW that are considered a recursive
+// traversal (e.g. "grep -r" or "find")
+const TRAVERSAL_READDIRS: usize = 8;
+const TRAVERSAL_WINDOW: Duration = Duration::from_secs(2);
+// How long resources are fetched with bulk list calls after a traversal was detected
+pub const BULK_WINDOW_DURATION: Duration = Duration::from_secs(10);
+
+// Detects recursive traversals of the filesystem by the rate of directory listings
+#[derive(Default)]
+pub struct TraversalDetector {
+    // Times of the recent directory listings, oldest first
+    readdirs: VecDeque<Instant>,
+}
+
+impl TraversalDetector {
+    // Record a directory listing and return true if it is part of a traversal
+    pub fn record_readdir(&mut self) -> bool {
+        let now = Instant::now();
+        while self
+            .readdirs
+            .front()
+            .is_some_and(|readdir| now.duration_since(*readdir) > TRAVERSAL_WINDOW)
+        {
+            self.readdirs.pop_front();
+        }
+        self.readdirs.push_back(now);
+
+        self.readdirs.len() >= TRAVERSAL_READDIRS
+    }
+}
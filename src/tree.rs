@@ -0,0 +1,261 @@
+use crate::journal::{object_list, same_object};
+use crate::kubectl;
+use crate::reports::{array, field};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// How long we wait for applied custom resource definitions to become usable
+const CRD_ESTABLISHED_TIMEOUT: Duration = Duration::from_secs(60);
+// Extensions of the files in the tree that contain manifests
+const MANIFEST_EXTENSIONS: [&str; 3] = ["json", "yaml", "yml"];
+
+// Objects are applied in stages, so that everything an object depends on exists before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Namespaces,
+    CustomResourceDefinitions,
+    Resources,
+}
+
+impl Stage {
+    fn all() -> [Stage; 3] {
+        [
+            Stage::Namespaces,
+            Stage::CustomResourceDefinitions,
+            Stage::Resources,
+        ]
+    }
+
+    fn of(kind: &str) -> Stage {
+        match kind {
+            "Namespace" => Stage::Namespaces,
+            "CustomResourceDefinition" => Stage::CustomResourceDefinitions,
+            _ => Stage::Resources,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Namespaces => "namespaces",
+            Stage::CustomResourceDefinitions => "custom resource definitions",
+            Stage::Resources => "resources",
+        }
+    }
+}
+
+// A single object of a manifest in the tree (a JSON object or a YAML document)
+struct Document {
+    path: PathBuf,
+    kind: String,
+    content: String,
+}
+
+// Apply all manifests in a directory tree (e.g. one that was written by "k8sfs export")
+// Namespaces are applied first, then custom resource definitions and then everything else.
+// With prune, namespaced objects that are not part of the tree are deleted from the
+// namespaces and kinds that appear in it. Cluster scoped objects and objects with owners
+// are never pruned.
+// Returns false if anything failed
+pub fn apply(
+    directory: &Path,
+    context: Option<&str>,
+    dry_run: bool,
+    prune: bool,
+    force_conflicts: bool,
+) -> bool {
+    let context = context
+        .map(String::from)
+        .unwrap_or_else(kubectl::current_context);
+    let documents = match manifest_files(directory).and_then(|files| documents(&files)) {
+        Ok(documents) => documents,
+        Err(error) => {
+            eprintln!("Could not read {}: {}", directory.display(), error);
+            return false;
+        }
+    };
+
+    for stage in Stage::all() {
+        let manifest = join(
+            documents
+                .iter()
+                .filter(|document| Stage::of(&document.kind) == stage),
+        );
+        if manifest.is_empty() {
+            continue;
+        }
+        println!("# {}", stage.name());
+        let outcome = if dry_run {
+            kubectl::apply_dry_run(&manifest, &context)
+        } else {
+            kubectl::apply(&manifest, &context, force_conflicts)
+        };
+        print!("{}", outcome.report);
+        // Later stages depend on this one, so there is no point in continuing
+        if !outcome.success {
+            return false;
+        }
+        if stage == Stage::CustomResourceDefinitions && !dry_run {
+            let outcome = kubectl::wait_established(&manifest, &context, CRD_ESTABLISHED_TIMEOUT);
+            if !outcome.success {
+                print!("{}", outcome.report);
+                return false;
+            }
+        }
+    }
+
+    if prune {
+        return prune_objects(&context, &join(documents.iter()), dry_run);
+    }
+    true
+}
+
+// Delete namespaced objects that are not part of the manifest
+fn prune_objects(context: &str, manifest: &[u8], dry_run: bool) -> bool {
+    println!("# prune");
+    let desired = match kubectl::get_manifest_objects(manifest, context) {
+        Ok(desired) => desired,
+        Err(error) => {
+            eprintln!("Could not retrieve the applied objects: {}", error);
+            return false;
+        }
+    };
+    // (namespace, apiVersion, kind) of all namespaced objects in the manifest
+    let scopes: BTreeSet<(&str, &str, &str)> = desired
+        .iter()
+        .filter(|object| !field(object, "/metadata/namespace").is_empty())
+        .map(|object| {
+            (
+                field(object, "/metadata/namespace"),
+                field(object, "/apiVersion"),
+                field(object, "/kind"),
+            )
+        })
+        .collect();
+
+    let mut success = true;
+    let mut pruned = Vec::new();
+    for (namespace, api_version, kind) in scopes {
+        // kubectl accepts <kind>.<group> for resources of API groups (e.g. deployment.apps)
+        let resource = match api_version.split_once('/') {
+            Some((group, _)) => format!("{}.{}", kind.to_lowercase(), group),
+            None => kind.to_lowercase(),
+        };
+        let existing = match kubectl::get_items(context, Some(namespace), &resource, &[]) {
+            Ok(existing) => existing,
+            Err(error) => {
+                eprintln!("Could not list {} in {}: {}", resource, namespace, error);
+                success = false;
+                continue;
+            }
+        };
+        pruned.extend(existing.into_iter().filter(|object| {
+            array(object, "/metadata/ownerReferences").is_empty()
+                && !desired.iter().any(|other| same_object(object, other))
+        }));
+    }
+
+    if dry_run {
+        for object in &pruned {
+            println!(
+                "{}/{} in {} would be pruned",
+                field(object, "/kind").to_lowercase(),
+                field(object, "/metadata/name"),
+                field(object, "/metadata/namespace")
+            );
+        }
+    } else if !pruned.is_empty() {
+        match kubectl::delete(&object_list(&pruned), context) {
+            Ok(deleted) => print!("{}", deleted),
+            Err(error) => {
+                eprintln!("Could not prune: {}", error);
+                success = false;
+            }
+        }
+    }
+
+    success
+}
+
+// Join documents into one manifest with multiple YAML documents
+// JSON objects are valid YAML documents.
+fn join<'a>(documents: impl Iterator<Item = &'a Document>) -> Vec<u8> {
+    documents
+        .map(|document| {
+            log::debug!("Adding {} from {}", document.kind, document.path.display());
+            document.content.as_str()
+        })
+        .collect::<Vec<_>>()
+        .join("\n---\n")
+        .into_bytes()
+}
+
+// Return all manifest files in a directory tree, sorted by path
+fn manifest_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![directory.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| MANIFEST_EXTENSIONS.contains(&extension))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// Split manifest files into their objects
+// Lists in JSON files are split into their items. The kind of YAML documents is taken from
+// their top level "kind:" line, because they are only parsed by kubectl.
+fn documents(files: &[PathBuf]) -> io::Result<Vec<Document>> {
+    let mut documents = Vec::new();
+    for path in files {
+        let content = fs::read_to_string(path)?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            let object: Value = serde_json::from_str(&content).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), error),
+                )
+            })?;
+            let objects = match object.get("items").and_then(Value::as_array) {
+                Some(items) if field(&object, "/kind") == "List" => items.clone(),
+                _ => vec![object],
+            };
+            documents.extend(objects.into_iter().map(|object| Document {
+                path: path.clone(),
+                kind: field(&object, "/kind").to_string(),
+                content: object.to_string(),
+            }));
+            continue;
+        }
+
+        for document in content.split("\n---") {
+            let kind = document.lines().find_map(|line| line.strip_prefix("kind:"));
+            let Some(kind) = kind else {
+                // Empty documents (e.g. before the first separator) and comments
+                continue;
+            };
+            documents.push(Document {
+                path: path.clone(),
+                kind: kind.trim().trim_matches(['"', '\'']).to_string(),
+                content: document.trim_start_matches('-').to_string(),
+            });
+        }
+    }
+    Ok(documents)
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Name of the file inside the control directory that contains the API usage
+pub const API_USAGE_FILE_NAME: &str = "api-usage";
+
+// Flags of kubectl that are followed by a value
+const VALUE_FLAGS: [&str; 9] = [
+    "--context",
+    "--namespace",
+    "--container",
+    "--selector",
+    "--field-selector",
+    "--patch",
+    "--raw",
+    "-f",
+    "-n",
+];
+
+// Number of kubectl calls per (operation, kind) since the mount or the last reset
+struct Usage {
+    since: Option<Instant>,
+    calls: BTreeMap<(String, String), u64>,
+}
+
+static USAGE: Mutex<Usage> = Mutex::new(Usage {
+    since: None,
+    calls: BTreeMap::new(),
+});
+
+// Record a kubectl call with the given arguments
+pub fn record<'a>(args: impl Iterator<Item = &'a OsStr>) {
+    let args: Vec<String> = args.map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let key = classify(&args);
+    let mut usage = USAGE.lock().unwrap();
+    usage.since.get_or_insert_with(Instant::now);
+    *usage.calls.entry(key).or_default() += 1;
+}
+
+pub fn reset() {
+    let mut usage = USAGE.lock().unwrap();
+    usage.since = Some(Instant::now());
+    usage.calls.clear();
+}
+
+// Human readable usage, the busiest operations and kinds first
+//   total 1532 calls in 600s (153.2 per minute)
+//   # Operations
+//   get 1490
+//   # Kinds
+//   pods 1204
+//   # Calls
+//   get pods 1198
+pub fn report() -> String {
+    let usage = USAGE.lock().unwrap();
+    let total: u64 = usage.calls.values().sum();
+    let seconds = usage
+        .since
+        .map_or(0, |since| since.elapsed().as_secs())
+        .max(1);
+    let mut operations: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut kinds: BTreeMap<&str, u64> = BTreeMap::new();
+    for ((operation, kind), count) in usage.calls.iter() {
+        *operations.entry(operation).or_default() += count;
+        *kinds.entry(kind).or_default() += count;
+    }
+
+    let mut report = format!(
+        "total {} calls in {}s ({:.1} per minute)\n",
+        total,
+        seconds,
+        total as f64 * 60.0 / seconds as f64
+    );
+    report.push_str("# Operations\n");
+    report.push_str(&busiest_first(operations.into_iter()));
+    report.push_str("# Kinds\n");
+    report.push_str(&busiest_first(kinds.into_iter()));
+    report.push_str("# Calls\n");
+    report.push_str(&busiest_first(usage.calls.iter().map(
+        |((operation, kind), count)| (format!("{} {}", operation, kind), *count),
+    )));
+    report
+}
+
+fn busiest_first<K: AsRef<str>>(counts: impl Iterator<Item = (K, u64)>) -> String {
+    let mut counts: Vec<(K, u64)> = counts.collect();
+    // The sort is stable, so names with the same count stay in alphabetical order
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+    counts
+        .iter()
+        .map(|(name, count)| format!("{} {}\n", name.as_ref(), count))
+        .collect()
+}
+
+// Split the arguments of a kubectl call into the arguments that are not flags and the flags
+// that are followed by a value, together with their value
+pub fn split_args(args: &[String]) -> (Vec<&str>, Vec<(&str, &str)>) {
+    let mut positional = Vec::new();
+    let mut flags = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            flags.push((arg.as_str(), args.next().map_or("", String::as_str)));
+        } else if !arg.starts_with('-') {
+            positional.push(arg.as_str());
+        }
+    }
+    (positional, flags)
+}
+
+// Return the operation (e.g. "get") and the kind of resource (e.g. "pods") of a kubectl call
+// Calls with a manifest on stdin have the kind "manifest", calls of raw API paths "raw" and
+// calls that are not about resources (e.g. "config view") "-".
+fn classify(args: &[String]) -> (String, String) {
+    let (positional, flags) = split_args(args);
+    let kind = flags.iter().find_map(|(flag, _)| match *flag {
+        "-f" => Some("manifest"),
+        "--raw" => Some("raw"),
+        _ => None,
+    });
+
+    // Commands with subcommands are counted as "<command> <subcommand>" (e.g. "auth can-i")
+    let operation = match positional.as_slice() {
+        [command @ ("auth" | "config"), subcommand, ..] => format!("{} {}", command, subcommand),
+        [operation, ..] => operation.to_string(),
+        [] => "-".to_string(),
+    };
+    let kind = match (kind, positional.as_slice()) {
+        (Some(kind), _) => kind,
+        (None, ["logs" | "exec", ..]) => "pods",
+        // kubectl auth can-i <verb> <resource>
+        (None, ["auth", _, _, resource, ..]) => resource,
+        (None, ["auth" | "config" | "version", ..]) => "-",
+        (None, [_, resource, ..]) => resource,
+        (None, _) => "-",
+    };
+    // <resource>/<name> and <resource>.<group> are counted as <resource>
+    let kind = kind.split(['/', '.']).next().unwrap_or(kind);
+    (operation, kind.to_lowercase())
+}
@@ -0,0 +1,84 @@
+use crate::kubectl::{self, K8sError};
+use crate::reports;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+// Name of the file in the context directory that contains the versions of kubectl and the API
+// server
+pub const VERSION_FILE_NAME: &str = "version";
+// kubectl is supported within one minor version (older or newer) of the API server
+const SUPPORTED_MINOR_SKEW: u64 = 1;
+
+// Human readable versions of kubectl and the API server
+//   client v1.28.2
+//   server v1.31.0
+//   warning kubectl v1.28.2 is 3 minor versions older than the API server v1.31.0 ...
+pub fn report(context: &str) -> Result<String, K8sError> {
+    let versions = kubectl::versions(context)?;
+    let mut report = format!(
+        "client {}\nserver {}\n",
+        reports::field(&versions, "/clientVersion/gitVersion"),
+        reports::field(&versions, "/serverVersion/gitVersion")
+    );
+    if let Some(warning) = skew_warning(&versions) {
+        report.push_str(&format!("warning {}\n", warning));
+    }
+    Ok(report)
+}
+
+// Log a warning if kubectl is outside the supported version skew of the API server
+// Descriptions and reports that look wrong often trace back to such a skew.
+pub fn warn_about_skew(context: &str) {
+    match kubectl::versions(context) {
+        Ok(versions) => {
+            if let Some(warning) = skew_warning(&versions) {
+                log::warn!("{}", warning);
+            }
+        }
+        Err(reason) => {
+            log::debug!(
+                "Could not compare the kubectl and server versions: {}",
+                reason
+            )
+        }
+    }
+}
+
+// Return a warning if kubectl is outside the supported version skew of the API server
+pub fn skew_warning(versions: &Value) -> Option<String> {
+    let (client_major, client_minor) = major_minor(versions, "/clientVersion")?;
+    let (server_major, server_minor) = major_minor(versions, "/serverVersion")?;
+    let client = reports::field(versions, "/clientVersion/gitVersion");
+    let server = reports::field(versions, "/serverVersion/gitVersion");
+    if client_major != server_major {
+        return Some(format!(
+            "kubectl {} and the API server {} have different major versions",
+            client, server
+        ));
+    }
+    let skew = client_minor.abs_diff(server_minor);
+    if skew <= SUPPORTED_MINOR_SKEW {
+        return None;
+    }
+    let direction = match client_minor.cmp(&server_minor) {
+        Ordering::Less => "older",
+        _ => "newer",
+    };
+    Some(format!(
+        "kubectl {} is {} minor versions {} than the API server {}, only a skew of {} is supported",
+        client, skew, direction, server, SUPPORTED_MINOR_SKEW
+    ))
+}
+
+// Return the major and minor version of a version object
+// Managed clusters add a suffix to the minor version (e.g. "28+"), which is ignored.
+fn major_minor(versions: &Value, pointer: &str) -> Option<(u64, u64)> {
+    let version = versions.pointer(pointer)?;
+    let number = |field: &str| {
+        reports::field(version, field)
+            .trim_end_matches(|character: char| !character.is_ascii_digit())
+            .parse::<u64>()
+            .ok()
+    };
+    Some((number("/major")?, number("/minor")?))
+}
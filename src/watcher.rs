@@ -0,0 +1,364 @@
+use crate::k8s_resource::{parse_timestamp, ResourceType};
+use crate::kubectl::{self, Tracked};
+use crate::reports::{self, array, field};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// How long we wait before restarting the watch after kubectl exited
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+// Identifies a container in the cluster (namespace, pod, container)
+pub type ContainerKey = (String, String, String);
+
+// Identifies a watched object (resource type, namespace, name)
+// The namespace is empty for resources that are not namespaced.
+pub type ObjectKey = (ResourceType, String, String);
+
+pub fn object_key(resource_type: ResourceType, namespace: &str, name: &str) -> ObjectKey {
+    let namespace = match resource_type.is_namespaced() {
+        true => namespace,
+        false => "",
+    };
+    (resource_type, namespace.to_string(), name.to_string())
+}
+
+// Last change of a watched object
+struct Modification {
+    resource_version: String,
+    time: SystemTime,
+}
+
+// A single restart of a container
+#[derive(Debug, Clone)]
+pub struct Restart {
+    // Value of restartCount after the restart
+    pub restart_count: u64,
+    pub finished_at: String,
+    pub reason: String,
+    pub exit_code: i64,
+}
+
+// Called with the object that changed and when it changed
+type ChangeListener = Box<dyn Fn(&ObjectKey, SystemTime) + Send>;
+
+// Identifies a registered change listener, so that it can be removed again
+pub type ListenerId = usize;
+
+// State that is collected by the watcher
+#[derive(Default)]
+struct WatchState {
+    // Last resourceVersion that was seen for each object and when it changed to it
+    modifications: HashMap<ObjectKey, Modification>,
+    // Notified about objects that changed after they were seen for the first time
+    listeners: Vec<(ListenerId, ChangeListener)>,
+    next_listener: ListenerId,
+    // Process ids of the running "kubectl get --watch" calls
+    watch_processes: HashMap<ResourceType, u32>,
+    // Set once the watcher is stopped, the watches are not restarted after that
+    stopped: bool,
+    // Last restart count that was seen for each container
+    restart_counts: HashMap<ContainerKey, u64>,
+    // Restarts that were observed since the watcher was started
+    restarts: HashMap<ContainerKey, Vec<Restart>>,
+    // Pod (namespace, pod) that was last seen with an IP address
+    pod_ips: HashMap<String, (String, String)>,
+}
+
+// Watches pods, namespaces and nodes in the background and keeps track of changes that the API
+// does not retain (e.g. the API only knows about the last termination of a container)
+#[derive(Clone)]
+pub struct Watcher {
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl Watcher {
+    // Start watching all pods, namespaces and nodes of a context in background threads
+    pub fn start(context: &str) -> Self {
+        let watcher = Watcher {
+            state: Arc::new(Mutex::new(WatchState::default())),
+        };
+        for resource_type in ResourceType::kinds() {
+            let background_watcher = watcher.clone();
+            let context = context.to_string();
+            thread::spawn(move || loop {
+                background_watcher.watch(&context, resource_type);
+                if background_watcher.state.lock().unwrap().stopped {
+                    return;
+                }
+                log::debug!(
+                    "Watch of {} ended, restarting it in {:?}",
+                    resource_type.kubectl_name(),
+                    RESTART_DELAY
+                );
+                thread::sleep(RESTART_DELAY);
+            });
+        }
+
+        watcher
+    }
+
+    // Run "kubectl get <resource> --watch" until it exits and process every object it emits
+    fn watch(&self, context: &str, resource_type: ResourceType) {
+        let mut command = kubectl::command();
+        command.args(["--context", context, "get", resource_type.kubectl_name()]);
+        if resource_type.is_namespaced() {
+            command.arg("--all-namespaces");
+        }
+        let child = command
+            .args(["--watch", "--output-watch-events", "-ojson"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .tracked_spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(error) => {
+                log::error!(
+                    "Could not start watching {}: {}",
+                    resource_type.kubectl_name(),
+                    error
+                );
+                return;
+            }
+        };
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.stopped {
+                let _ = child.kill();
+                let _ = child.wait();
+                return;
+            }
+            state.watch_processes.insert(resource_type, child.id());
+        }
+
+        // kubectl prints one JSON event per change, one after another
+        //   {"type": "ADDED", "object": {...}}
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        for event in serde_json::Deserializer::from_reader(stdout).into_iter::<Value>() {
+            match event {
+                Ok(event) => {
+                    let object = event.get("object").unwrap_or(&Value::Null);
+                    match field(&event, "/type") {
+                        "DELETED" => self.forget_object(resource_type, object),
+                        _ => self.process_object(resource_type, object),
+                    }
+                }
+                Err(error) => {
+                    log::debug!(
+                        "Could not parse watched {}: {}",
+                        resource_type.kubectl_name(),
+                        error
+                    );
+                    break;
+                }
+            }
+        }
+        // The process id must not be used anymore once the process was waited for
+        self.state
+            .lock()
+            .unwrap()
+            .watch_processes
+            .remove(&resource_type);
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    // Stop watching, the watch processes are terminated (e.g. once the last mount of a context
+    // of the daemon is gone)
+    pub fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.stopped = true;
+        state.listeners.clear();
+        for pid in state.watch_processes.values() {
+            // SAFETY: kill() has no memory safety requirements. The process was not waited for
+            // yet (see watch), so the id still belongs to the kubectl process.
+            unsafe { libc::kill(*pid as libc::pid_t, libc::SIGTERM) };
+        }
+    }
+
+    // Register a function that is called whenever a watched object changed
+    pub fn on_change(
+        &self,
+        listener: impl Fn(&ObjectKey, SystemTime) + Send + 'static,
+    ) -> ListenerId {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_listener;
+        state.next_listener += 1;
+        state.listeners.push((id, Box::new(listener)));
+        id
+    }
+
+    // Stop calling a function that was registered with on_change
+    pub fn remove_listener(&self, id: ListenerId) {
+        self.state
+            .lock()
+            .unwrap()
+            .listeners
+            .retain(|(listener_id, _)| *listener_id != id);
+    }
+
+    // Record the resourceVersion of an object and notify listeners when it changed
+    fn process_object(&self, resource_type: ResourceType, object: &Value) {
+        let key = object_key(
+            resource_type,
+            field(object, "/metadata/namespace"),
+            field(object, "/metadata/name"),
+        );
+        let resource_version = field(object, "/metadata/resourceVersion");
+        let mut state = self.state.lock().unwrap();
+        let previous = state.modifications.get(&key);
+        let changed =
+            previous.is_some_and(|previous| previous.resource_version != resource_version);
+        // The object tells when it was last written to, unless the change did not leave a
+        // timestamp (e.g. changed annotations of an object without managedFields)
+        let updated = reports::last_update(object).and_then(parse_timestamp);
+        let time = match (previous, updated) {
+            (_, Some(updated)) if !changed => updated,
+            (Some(previous), Some(updated)) if updated > previous.time => updated,
+            _ => SystemTime::now(),
+        };
+        // The watch starts with the current state of all objects, which is not a change
+        if previous.is_none_or(|_| changed) {
+            state.modifications.insert(
+                key.clone(),
+                Modification {
+                    resource_version: resource_version.to_string(),
+                    time,
+                },
+            );
+        }
+        if changed {
+            log::debug!("{:?} changed at {:?}", key, time);
+            for (_, listener) in &state.listeners {
+                listener(&key, time);
+            }
+        }
+        drop(state);
+        if resource_type == ResourceType::Pod {
+            self.process_pod(object);
+        }
+    }
+
+    // Forget everything about an object that was deleted and notify listeners about it
+    fn forget_object(&self, resource_type: ResourceType, object: &Value) {
+        let namespace = field(object, "/metadata/namespace");
+        let name = field(object, "/metadata/name");
+        let key = object_key(resource_type, namespace, name);
+        let mut state = self.state.lock().unwrap();
+        state.modifications.remove(&key);
+        if resource_type == ResourceType::Pod {
+            let is_pod = |pod_namespace: &str, pod: &str| pod_namespace == namespace && pod == name;
+            state
+                .pod_ips
+                .retain(|_, (pod_namespace, pod)| !is_pod(pod_namespace, pod));
+            state
+                .restart_counts
+                .retain(|(pod_namespace, pod, _), _| !is_pod(pod_namespace, pod));
+            state
+                .restarts
+                .retain(|(pod_namespace, pod, _), _| !is_pod(pod_namespace, pod));
+        }
+        log::debug!("{:?} was deleted", key);
+        let time = SystemTime::now();
+        for (_, listener) in &state.listeners {
+            listener(&key, time);
+        }
+    }
+
+    // Record the IP addresses and the restarts of the pod's containers
+    fn process_pod(&self, pod: &Value) {
+        let namespace = field(pod, "/metadata/namespace");
+        let pod_name = field(pod, "/metadata/name");
+        let mut state = self.state.lock().unwrap();
+        // Pods in the host network share the IP address of their node
+        let finished = matches!(field(pod, "/status/phase"), "Succeeded" | "Failed");
+        if !finished
+            && !pod
+                .pointer("/spec/hostNetwork")
+                .is_some_and(|host| host == true)
+        {
+            for ip in array(pod, "/status/podIPs") {
+                state.pod_ips.insert(
+                    field(ip, "/ip").to_string(),
+                    (namespace.to_string(), pod_name.to_string()),
+                );
+            }
+        }
+        let statuses = array(pod, "/status/initContainerStatuses")
+            .iter()
+            .chain(array(pod, "/status/containerStatuses"));
+        for status in statuses {
+            let key = (
+                namespace.to_string(),
+                pod_name.to_string(),
+                field(status, "/name").to_string(),
+            );
+            let restart_count = status
+                .pointer("/restartCount")
+                .and_then(Value::as_u64)
+                .unwrap_or(0);
+            let previous_count = state.restart_counts.insert(key.clone(), restart_count);
+            // The first time we see a restarted container, only its last termination is known
+            if restart_count == 0 || previous_count.unwrap_or(0) >= restart_count {
+                continue;
+            }
+
+            let terminated = status
+                .pointer("/lastState/terminated")
+                .unwrap_or(&Value::Null);
+            let restart = Restart {
+                restart_count,
+                finished_at: field(terminated, "/finishedAt").to_string(),
+                reason: field(terminated, "/reason").to_string(),
+                exit_code: terminated
+                    .pointer("/exitCode")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(0),
+            };
+            log::debug!("Container {:?} restarted: {:?}", key, restart);
+            state.restarts.entry(key).or_default().push(restart);
+        }
+    }
+
+    // Return the pod (namespace, pod) that was last seen with an IP address
+    // Pods that finished release their IP address, so it might belong to another pod by now.
+    pub fn pod_with_ip(&self, ip: &str) -> Option<(String, String)> {
+        self.state.lock().unwrap().pod_ips.get(ip).cloned()
+    }
+
+    // Return all restarts of a container that were seen so far
+    pub fn restarts(&self, key: &ContainerKey) -> Vec<Restart> {
+        self.state
+            .lock()
+            .unwrap()
+            .restarts
+            .get(key)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Human readable restart history of a container
+    pub fn restart_history(&self, key: &ContainerKey) -> String {
+        let restarts = self.restarts(key);
+        if restarts.is_empty() {
+            return String::from("No restarts observed since k8sfs was mounted\n");
+        }
+
+        let mut history = format!(
+            "{:<22}{:<10}{:<22}{}\n",
+            "FINISHED AT", "RESTARTS", "REASON", "EXIT CODE"
+        );
+        for restart in restarts {
+            history.push_str(&format!(
+                "{:<22}{:<10}{:<22}{}\n",
+                restart.finished_at, restart.restart_count, restart.reason, restart.exit_code
+            ));
+        }
+        history
+    }
+}
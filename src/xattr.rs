@@ -0,0 +1,28 @@
+// Extended attributes of files
+// user.k8sfs.hash lets sync tools detect changes of definition files without reading them:
+//   fnv1a64:<hash of the content> <resourceVersion>
+
+// Name of the extended attribute with the hash of a definition file
+pub const HASH_XATTR_NAME: &str = "user.k8sfs.hash";
+
+// FNV-1a parameters for 64 bit hashes
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// Value of the hash attribute for the content of a file and the resourceVersion of its resource
+// The hash does not depend on the version of k8sfs (unlike the hasher of the standard library),
+// so it can be compared across mounts.
+pub fn hash_value(content: &[u8], resource_version: &str) -> String {
+    let hash = content.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+    format!("fnv1a64:{:016x} {}", hash, resource_version)
+}
+
+// List of attribute names as listxattr returns it (every name is terminated by a null byte)
+pub fn name_list(names: &[&str]) -> Vec<u8> {
+    names
+        .iter()
+        .flat_map(|name| name.bytes().chain([0]))
+        .collect()
+}
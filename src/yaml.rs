@@ -0,0 +1,173 @@
+// Write JSON values as YAML block collections (e.g. the manifests of "k8sfs export")
+// Strings that could be read as something else (numbers, booleans, ...) or that contain special
+// characters are written as double quoted JSON strings, which are valid YAML as well.
+
+use serde_json::{Map, Value};
+
+// Plain scalars that YAML would not read as strings
+const RESERVED_WORDS: [&str; 10] = [
+    "true", "false", "yes", "no", "on", "off", "y", "n", "null", "~",
+];
+
+// Return the YAML document of a value
+pub fn to_yaml(value: &Value) -> String {
+    let mut yaml = String::new();
+    match value {
+        Value::Object(object) if !object.is_empty() => mapping(object, 0, true, &mut yaml),
+        Value::Array(items) if !items.is_empty() => sequence(items, 0, &mut yaml),
+        value => {
+            yaml.push_str(&scalar(value));
+            yaml.push('\n');
+        }
+    }
+    yaml
+}
+
+// Append an object with its keys indented by indent spaces
+// The first key is not indented if the object is an item of a sequence.
+fn mapping(object: &Map<String, Value>, indent: usize, indent_first: bool, yaml: &mut String) {
+    for (index, (key, value)) in object.iter().enumerate() {
+        if index > 0 || indent_first {
+            yaml.push_str(&" ".repeat(indent));
+        }
+        yaml.push_str(&string(key));
+        yaml.push(':');
+        match value {
+            Value::Object(object) if !object.is_empty() => {
+                yaml.push('\n');
+                mapping(object, indent + 2, true, yaml);
+            }
+            // Items of a sequence are not indented any further than its key (like kubectl does)
+            Value::Array(items) if !items.is_empty() => {
+                yaml.push('\n');
+                sequence(items, indent, yaml);
+            }
+            value => {
+                yaml.push(' ');
+                yaml.push_str(&scalar(value));
+                yaml.push('\n');
+            }
+        }
+    }
+}
+
+// Append the items of an array, with their dashes indented by indent spaces
+fn sequence(items: &[Value], indent: usize, yaml: &mut String) {
+    for item in items {
+        yaml.push_str(&" ".repeat(indent));
+        yaml.push('-');
+        match item {
+            Value::Object(object) if !object.is_empty() => {
+                yaml.push(' ');
+                mapping(object, indent + 2, false, yaml);
+            }
+            Value::Array(items) if !items.is_empty() => {
+                yaml.push('\n');
+                sequence(items, indent + 2, yaml);
+            }
+            item => {
+                yaml.push(' ');
+                yaml.push_str(&scalar(item));
+                yaml.push('\n');
+            }
+        }
+    }
+}
+
+// Return a value that is not a non-empty collection as a YAML scalar
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => string(value),
+        // null, booleans, numbers and empty collections are written the same way in JSON
+        value => value.to_string(),
+    }
+}
+
+// Return a string as a plain scalar if YAML reads it as the same string, quoted otherwise
+fn string(value: &str) -> String {
+    let plain = value.starts_with(|character: char| character.is_ascii_alphabetic())
+        && value
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_./".contains(character))
+        && !RESERVED_WORDS.contains(&value.to_lowercase().as_str());
+    if plain {
+        value.to_string()
+    } else {
+        Value::String(value.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn manifest() {
+        let pod = json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "name": "web-0",
+                "labels": {"app.kubernetes.io/name": "web", "tier": "1"},
+                "annotations": {},
+            },
+            "spec": {
+                "containers": [{
+                    "name": "web",
+                    "image": "nginx:1.25",
+                    "args": ["--port", "8080"],
+                    "ports": [{"containerPort": 8080}],
+                }],
+                "enableServiceLinks": false,
+                "tolerations": [],
+            },
+        });
+        assert_eq!(
+            to_yaml(&pod),
+            "apiVersion: v1\n\
+             kind: Pod\n\
+             metadata:\n  \
+               annotations: {}\n  \
+               labels:\n    \
+                 app.kubernetes.io/name: web\n    \
+                 tier: \"1\"\n  \
+               name: web-0\n\
+             spec:\n  \
+               containers:\n  \
+               - args:\n    \
+                 - \"--port\"\n    \
+                 - \"8080\"\n    \
+                 image: \"nginx:1.25\"\n    \
+                 name: web\n    \
+                 ports:\n    \
+                 - containerPort: 8080\n  \
+               enableServiceLinks: false\n  \
+               tolerations: []\n"
+        );
+    }
+
+    #[test]
+    fn strings_that_are_not_plain_are_quoted() {
+        for (value, expected) in [
+            ("web", "web"),
+            ("on", "\"on\""),
+            ("No", "\"No\""),
+            ("1.25", "\"1.25\""),
+            ("", "\"\""),
+            ("a: b", "\"a: b\""),
+            ("#comment", "\"#comment\""),
+            ("line\nbreak", "\"line\\nbreak\""),
+        ] {
+            assert_eq!(string(value), expected);
+        }
+    }
+
+    #[test]
+    fn nested_sequences() {
+        assert_eq!(
+            to_yaml(&json!({"matrix": [[1, 2], []]})),
+            "matrix:\n-\n  - 1\n  - 2\n- []\n"
+        );
+    }
+}